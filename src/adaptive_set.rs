@@ -0,0 +1,404 @@
+//! `AdaptiveSet`, a set of `u32`s (like a [Roaring bitmap](https://roaringbitmap.org/)), which
+//! stores each chunk of 2¹⁶ values in whichever is smallest of: a sorted array (delta encoded
+//! varints), a list of runs, or a bitmap. Sparse & dense values can be in the same set.
+//!
+//! ```rust
+//! use vartyint::adaptive_set::{AdaptiveSet, ContainerKind};
+//! let mut set: AdaptiveSet = (0..100_000).step_by(2).chain([1_000_000, 5_000_000]).collect();
+//! set.insert(1);
+//! assert!(set.contains(5_000_000));
+//! assert!(!set.contains(3));
+//! assert_eq!(set.len(), 50_003);
+//!
+//! let mut buf = Vec::new();
+//! set.write(&mut buf);
+//! let (read, _) = AdaptiveSet::read(&buf).unwrap();
+//! assert_eq!(read, set);
+//! ```
+//!
+//! The data is the number of chunks, then each chunk: the difference between its key (the top 16
+//! bits) & the previous key, its kind (0 array, 1 runs, 2 bitmap), then:
+//!
+//! * array: the number of values, then the (low 16 bits of the) values, delta encoded
+//! * runs: the number of runs, then for each run, the gap from the end of the previous run to the
+//!   start of this one, & its length minus 1
+//! * bitmap: 1024 little endian `u64`s
+use crate::*;
+use std::collections::BTreeMap;
+
+const BITMAP_WORDS: usize = 1024;
+const BITMAP_LEN: usize = BITMAP_WORDS * 8;
+
+/// How a chunk of an `AdaptiveSet` is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ContainerKind {
+    /// A sorted list of values
+    Array = 0,
+    /// A list of runs of consecutive values
+    Runs = 1,
+    /// One bit for every possible value
+    Bitmap = 2,
+}
+
+#[derive(Debug, Clone)]
+enum Container {
+    Array(Vec<u16>),
+    // (first, last) of each run, inclusive
+    Runs(Vec<(u16, u16)>),
+    Bitmap(Box<[u64; BITMAP_WORDS]>),
+}
+
+impl Container {
+    /// The smallest container for these (sorted, unique) values
+    fn from_sorted(vals: &[u16]) -> Self {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for &val in vals {
+            match runs.last_mut() {
+                Some((_, last)) if *last as u32 + 1 == val as u32 => *last = val,
+                _ => runs.push((val, val)),
+            }
+        }
+        let array = Container::Array(vals.to_vec());
+        let runs = Container::Runs(runs);
+        let (array_len, runs_len) = (array.encoded_len(), runs.encoded_len());
+        if array_len.min(runs_len) > BITMAP_LEN {
+            let mut bitmap = Box::new([0; BITMAP_WORDS]);
+            for &val in vals {
+                bitmap[val as usize / 64] |= 1 << (val % 64);
+            }
+            Container::Bitmap(bitmap)
+        } else if runs_len < array_len {
+            runs
+        } else {
+            array
+        }
+    }
+
+    fn kind(&self) -> ContainerKind {
+        match self {
+            Container::Array(_) => ContainerKind::Array,
+            Container::Runs(_) => ContainerKind::Runs,
+            Container::Bitmap(_) => ContainerKind::Bitmap,
+        }
+    }
+
+    fn len(&self) -> u64 {
+        match self {
+            Container::Array(vals) => vals.len() as u64,
+            Container::Runs(runs) => runs
+                .iter()
+                .map(|(first, last)| (last - first) as u64 + 1)
+                .sum(),
+            Container::Bitmap(bitmap) => bitmap.iter().map(|w| w.count_ones() as u64).sum(),
+        }
+    }
+
+    fn contains(&self, val: u16) -> bool {
+        match self {
+            Container::Array(vals) => vals.binary_search(&val).is_ok(),
+            Container::Runs(runs) => {
+                let i = runs.partition_point(|(first, _)| *first <= val);
+                i > 0 && runs[i - 1].1 >= val
+            }
+            Container::Bitmap(bitmap) => bitmap[val as usize / 64] & (1 << (val % 64)) != 0,
+        }
+    }
+
+    /// Add `val`, which isn't in the container, without changing its kind. Returns the number of
+    /// values (array) or runs (runs) it has now, or 0 if that didn't go up.
+    fn insert(&mut self, val: u16) -> usize {
+        match self {
+            Container::Array(vals) => {
+                vals.insert(vals.partition_point(|v| *v < val), val);
+                vals.len()
+            }
+            Container::Runs(runs) => {
+                let i = runs.partition_point(|(first, _)| *first <= val);
+                let joins_prev = i > 0 && runs[i - 1].1 as u32 + 1 == val as u32;
+                let joins_next = i < runs.len() && runs[i].0 as u32 == val as u32 + 1;
+                match (joins_prev, joins_next) {
+                    (true, true) => {
+                        runs[i - 1].1 = runs[i].1;
+                        runs.remove(i);
+                    }
+                    (true, false) => runs[i - 1].1 = val,
+                    (false, true) => runs[i].0 = val,
+                    (false, false) => {
+                        runs.insert(i, (val, val));
+                        return runs.len();
+                    }
+                }
+                0
+            }
+            Container::Bitmap(bitmap) => {
+                bitmap[val as usize / 64] |= 1 << (val % 64);
+                0
+            }
+        }
+    }
+
+    fn to_vec(&self) -> Vec<u16> {
+        match self {
+            Container::Array(vals) => vals.clone(),
+            Container::Runs(runs) => runs
+                .iter()
+                .flat_map(|(first, last)| *first..=*last)
+                .collect(),
+            Container::Bitmap(bitmap) => (0..=u16::MAX)
+                .filter(|val| bitmap[*val as usize / 64] & (1 << (val % 64)) != 0)
+                .collect(),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        let mut buf = Vec::new();
+        self.write(&mut buf);
+        buf.len()
+    }
+
+    /// Write everything after the kind
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            Container::Array(vals) => {
+                vals.len().write_varint(buf);
+                write_many_delta(vals, buf);
+            }
+            Container::Runs(runs) => {
+                runs.len().write_varint(buf);
+                let mut next = 0;
+                for (first, last) in runs {
+                    (first - next).write_varint(buf);
+                    (last - first).write_varint(buf);
+                    next = last.saturating_add(1);
+                }
+            }
+            Container::Bitmap(bitmap) => {
+                for word in bitmap.iter() {
+                    buf.extend_from_slice(&word.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    /// Read everything after the kind
+    fn read(kind: u8, buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        match kind {
+            0 => {
+                let (len, rest) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+                let (deltas, rest) = read_many_exact::<u16>(rest, len)?;
+                let mut vals = Vec::with_capacity(len);
+                let mut last = 0u16;
+                for (i, delta) in deltas.into_iter().enumerate() {
+                    if i > 0 && delta == 0 {
+                        return Err(VartyIntError::OutOfRange);
+                    }
+                    last = last.checked_add(delta).ok_or(VartyIntError::OutOfRange)?;
+                    vals.push(last);
+                }
+                Ok((Container::Array(vals), rest))
+            }
+            1 => {
+                let (len, rest) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+                let (pairs, rest) = read_many_exact::<(u16, u16)>(rest, len)?;
+                let mut runs = Vec::with_capacity(len);
+                let mut next = 0u16;
+                for (i, (gap, len)) in pairs.into_iter().enumerate() {
+                    // Adjacent runs would be one run
+                    if i > 0 && (gap == 0 || next == 0) {
+                        return Err(VartyIntError::OutOfRange);
+                    }
+                    let first = next.checked_add(gap).ok_or(VartyIntError::OutOfRange)?;
+                    let last = first.checked_add(len).ok_or(VartyIntError::OutOfRange)?;
+                    runs.push((first, last));
+                    next = last.wrapping_add(1);
+                }
+                Ok((Container::Runs(runs), rest))
+            }
+            2 => {
+                if buf.len() < BITMAP_LEN {
+                    return Err(VartyIntError::not_enough(buf.len(), BITMAP_LEN));
+                }
+                let (words, rest) = buf.split_at(BITMAP_LEN);
+                let mut bitmap = Box::new([0; BITMAP_WORDS]);
+                for (word, bytes) in bitmap.iter_mut().zip(words.chunks_exact(8)) {
+                    *word = u64::from_le_bytes(bytes.try_into().unwrap());
+                }
+                Ok((Container::Bitmap(bitmap), rest))
+            }
+            _ => Err(VartyIntError::UnknownDiscriminant),
+        }
+    }
+}
+
+/// A set of `u32`s. See the module docs.
+///
+/// Sets are equal if they have the same values, even if they're stored differently.
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveSet {
+    chunks: BTreeMap<u16, Container>,
+}
+
+impl PartialEq for AdaptiveSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for AdaptiveSet {}
+
+/// Split a value into its chunk key & the value in the chunk
+fn split(val: u32) -> (u16, u16) {
+    ((val >> 16) as u16, val as u16)
+}
+
+impl AdaptiveSet {
+    /// An empty set
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Number of values
+    pub fn len(&self) -> u64 {
+        self.chunks.values().map(|c| c.len()).sum()
+    }
+
+    /// True iff there are no values
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// True iff this value is in the set
+    pub fn contains(&self, val: u32) -> bool {
+        let (key, low) = split(val);
+        self.chunks.get(&key).is_some_and(|c| c.contains(low))
+    }
+
+    /// Add this value. Returns true iff it wasn't already in the set.
+    pub fn insert(&mut self, val: u32) -> bool {
+        let (key, low) = split(val);
+        let Some(container) = self.chunks.get_mut(&key) else {
+            self.chunks.insert(key, Container::Array(vec![low]));
+            return true;
+        };
+        if container.contains(low) {
+            return false;
+        }
+        // Picking the smallest kind again means encoding it, so only do that when the number of
+        // values or runs has doubled
+        if container.insert(low).is_power_of_two() {
+            *container = Container::from_sorted(&container.to_vec());
+        }
+        true
+    }
+
+    /// Iterate over the values, in order
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.chunks.iter().flat_map(|(key, container)| {
+            container
+                .to_vec()
+                .into_iter()
+                .map(move |low| ((*key as u32) << 16) | low as u32)
+        })
+    }
+
+    /// How each chunk is stored, as (the top 16 bits of its values, the kind)
+    pub fn container_kinds(&self) -> Vec<(u16, ContainerKind)> {
+        self.chunks.iter().map(|(k, c)| (*k, c.kind())).collect()
+    }
+
+    /// Every value which is in `self` or `other`
+    pub fn union(&self, other: &AdaptiveSet) -> AdaptiveSet {
+        let mut chunks = self.chunks.clone();
+        for (key, container) in other.chunks.iter() {
+            let merged = match (chunks.get(key), container) {
+                (None, _) => container.clone(),
+                (Some(Container::Bitmap(a)), Container::Bitmap(b)) => {
+                    let mut bitmap = a.clone();
+                    for (word, other) in bitmap.iter_mut().zip(b.iter()) {
+                        *word |= other;
+                    }
+                    Container::Bitmap(bitmap)
+                }
+                (Some(existing), _) => {
+                    let mut vals = existing.to_vec();
+                    vals.extend(container.to_vec());
+                    vals.sort_unstable();
+                    vals.dedup();
+                    Container::from_sorted(&vals)
+                }
+            };
+            chunks.insert(*key, merged);
+        }
+        AdaptiveSet { chunks }
+    }
+
+    /// Every value which is in both `self` & `other`
+    pub fn intersection(&self, other: &AdaptiveSet) -> AdaptiveSet {
+        let mut chunks = BTreeMap::new();
+        for (key, container) in self.chunks.iter() {
+            let Some(other) = other.chunks.get(key) else {
+                continue;
+            };
+            let vals = container
+                .to_vec()
+                .into_iter()
+                .filter(|val| other.contains(*val))
+                .collect::<Vec<_>>();
+            if !vals.is_empty() {
+                chunks.insert(*key, Container::from_sorted(&vals));
+            }
+        }
+        AdaptiveSet { chunks }
+    }
+
+    /// Write this set to the end of `buf`, returning the number of bytes written
+    pub fn write(&self, buf: &mut Vec<u8>) -> usize {
+        let start = buf.len();
+        self.chunks.len().write_varint(buf);
+        let mut last_key = 0;
+        for (key, container) in self.chunks.iter() {
+            (key - last_key).write_varint(buf);
+            last_key = *key;
+            (container.kind() as u8).write_varint(buf);
+            container.write(buf);
+        }
+        buf.len() - start
+    }
+
+    /// Read a set from the start of this buffer, returning it & the rest of the buffer
+    pub fn read(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        let (num, mut rest) = usize::read_varint(buf)?;
+        let mut chunks = BTreeMap::new();
+        let mut key = 0u16;
+        for i in 0..num {
+            let ((delta, kind), newrest) =
+                <(u16, u8)>::read_varint(rest).map_err(empty_to_not_enough::<(u16, u8)>)?;
+            if i > 0 && delta == 0 {
+                return Err(VartyIntError::OutOfRange);
+            }
+            key = key.checked_add(delta).ok_or(VartyIntError::OutOfRange)?;
+            let (container, newrest) = Container::read(kind, newrest)?;
+            if container.len() == 0 {
+                return Err(VartyIntError::OutOfRange);
+            }
+            chunks.insert(key, container);
+            rest = newrest;
+        }
+        Ok((AdaptiveSet { chunks }, rest))
+    }
+}
+
+impl FromIterator<u32> for AdaptiveSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut vals = iter.into_iter().collect::<Vec<_>>();
+        vals.sort_unstable();
+        vals.dedup();
+        let mut chunks = BTreeMap::new();
+        for chunk in vals.chunk_by(|a, b| a >> 16 == b >> 16) {
+            let lows = chunk.iter().map(|val| *val as u16).collect::<Vec<_>>();
+            chunks.insert((chunk[0] >> 16) as u16, Container::from_sorted(&lows));
+        }
+        AdaptiveSet { chunks }
+    }
+}
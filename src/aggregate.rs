@@ -0,0 +1,85 @@
+//! Aggregates over a buffer of varints, without decoding it into a `Vec`.
+//!
+//! ```rust
+//! use vartyint::aggregate;
+//! let buf = vartyint::write_many_delta_new(&[100i64, 105, 103, 110]);
+//! assert_eq!(aggregate::count::<i64>(&buf), Ok(4));
+//! assert_eq!(aggregate::sum_delta::<i64>(&buf), Ok(418));
+//! assert_eq!(aggregate::min_delta::<i64>(&buf), Ok(Some(100)));
+//! assert_eq!(aggregate::max_delta::<i64>(&buf), Ok(Some(110)));
+//! ```
+use crate::*;
+
+/// Call `f` with every value in a delta encoded buffer. If the running total overflows,
+/// `DeltaOverflow` is returned.
+fn for_each_delta<T, F>(buf: &[u8], mut f: F) -> Result<(), VartyIntError>
+where
+    T: DeltaInt,
+    F: FnMut(T),
+{
+    for val in read_many_delta_checked::<T>(buf) {
+        f(val?);
+    }
+    Ok(())
+}
+
+/// How many values are in this buffer
+pub fn count<T: VarInt>(buf: &[u8]) -> Result<usize, VartyIntError> {
+    let mut count = 0;
+    for_each_varint(buf, |_: T| count += 1)?;
+    Ok(count)
+}
+
+/// The sum of the values in this buffer. If the sum doesn't fit in `T`, `OutOfRange` is
+/// returned.
+pub fn sum<T: DeltaInt>(buf: &[u8]) -> Result<T, VartyIntError> {
+    // `None` after an overflow
+    let mut sum = Some(T::zero());
+    for_each_varint(buf, |val: T| {
+        sum = sum.and_then(|s| s.checked_add_delta(val))
+    })?;
+    sum.ok_or(VartyIntError::OutOfRange)
+}
+
+/// The smallest value in this buffer, or `None` if it's empty
+pub fn min<T: VarInt + Ord>(buf: &[u8]) -> Result<Option<T>, VartyIntError> {
+    let mut min = None;
+    for_each_varint(buf, |val: T| min = Some(min.map_or(val, |m: T| m.min(val))))?;
+    Ok(min)
+}
+
+/// The largest value in this buffer, or `None` if it's empty
+pub fn max<T: VarInt + Ord>(buf: &[u8]) -> Result<Option<T>, VartyIntError> {
+    let mut max = None;
+    for_each_varint(buf, |val: T| max = Some(max.map_or(val, |m: T| m.max(val))))?;
+    Ok(max)
+}
+
+/// Like `sum`, for a buffer written with `write_many_delta`
+pub fn sum_delta<T: DeltaInt>(buf: &[u8]) -> Result<T, VartyIntError> {
+    let mut sum = Some(T::zero());
+    for_each_delta(buf, |val: T| {
+        sum = sum.and_then(|s| s.checked_add_delta(val))
+    })?;
+    sum.ok_or(VartyIntError::OutOfRange)
+}
+
+/// Like `min`, for a buffer written with `write_many_delta`
+pub fn min_delta<T>(buf: &[u8]) -> Result<Option<T>, VartyIntError>
+where
+    T: DeltaInt + Ord,
+{
+    let mut min = None;
+    for_each_delta(buf, |val: T| min = Some(min.map_or(val, |m: T| m.min(val))))?;
+    Ok(min)
+}
+
+/// Like `max`, for a buffer written with `write_many_delta`
+pub fn max_delta<T>(buf: &[u8]) -> Result<Option<T>, VartyIntError>
+where
+    T: DeltaInt + Ord,
+{
+    let mut max = None;
+    for_each_delta(buf, |val: T| max = Some(max.map_or(val, |m: T| m.max(val))))?;
+    Ok(max)
+}
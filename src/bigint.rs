@@ -0,0 +1,155 @@
+//! Integers of any size, as unlimited length LEB128 varints.
+//!
+//! The numbers are little endian bytes of the magnitude (with a separate sign for signed
+//! numbers), so they can be converted to & from any big integer library, e.g. `num-bigint`'s
+//! `BigUint::to_bytes_le`/`BigUint::from_bytes_le`. Signed numbers use zigzag encoding, like the
+//! built in types. For numbers which fit in a `u128`/`i128`, the output is the same as
+//! `write_u128`/`write_i128`.
+//!
+//! ```rust
+//! use vartyint::bigint::*;
+//! // 2^136
+//! let mut num = vec![0; 17];
+//! num.push(1);
+//! let mut buf = Vec::new();
+//! write_big_unsigned(&num, &mut buf);
+//! assert_eq!(buf.len(), 20);
+//! assert_eq!(read_big_unsigned(&buf), Ok((num.clone(), &[] as &[u8])));
+//!
+//! buf.clear();
+//! write_big_signed(true, &num, &mut buf);
+//! assert_eq!(read_big_signed(&buf), Ok(((true, num), &[] as &[u8])));
+//! ```
+use crate::*;
+
+/// A signed number, as the sign (`true` for negative) & the magnitude as little endian bytes
+pub type BigSigned = (bool, Vec<u8>);
+
+/// Remove the high zero bytes
+fn trim(mut bytes: Vec<u8>) -> Vec<u8> {
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    bytes
+}
+
+/// Multiply by 2
+fn shl1(bytes: &mut Vec<u8>) {
+    let mut carry = 0;
+    for byte in bytes.iter_mut() {
+        let next_carry = *byte >> 7;
+        *byte = (*byte << 1) | carry;
+        carry = next_carry;
+    }
+    if carry != 0 {
+        bytes.push(carry);
+    }
+}
+
+/// Divide by 2
+fn shr1(bytes: &mut [u8]) {
+    let mut carry = 0;
+    for byte in bytes.iter_mut().rev() {
+        let next_carry = *byte & 1;
+        *byte = (*byte >> 1) | (carry << 7);
+        carry = next_carry;
+    }
+}
+
+/// Add (`+1`) or subtract (`-1`) one. Subtracting from zero is not allowed.
+fn add_one(bytes: &mut Vec<u8>, subtract: bool) {
+    for byte in bytes.iter_mut() {
+        let (new, overflow) = if subtract {
+            byte.overflowing_sub(1)
+        } else {
+            byte.overflowing_add(1)
+        };
+        *byte = new;
+        if !overflow {
+            return;
+        }
+    }
+    bytes.push(1);
+}
+
+/// Write this unsigned number, as little endian bytes, to the buffer. Returns the number of bytes
+/// written.
+pub fn write_big_unsigned(num: &[u8], buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    let mut acc: u16 = 0;
+    let mut bits = 0;
+    for byte in num.iter() {
+        acc |= (*byte as u16) << bits;
+        bits += 8;
+        while bits >= 7 {
+            buf.push(acc as u8 & 0b0111_1111);
+            acc >>= 7;
+            bits -= 7;
+        }
+    }
+    buf.push(acc as u8);
+    // Remove the high zero groups, then set the continuation bit on all but the last
+    while buf.len() > start + 1 && buf[buf.len() - 1] == 0 {
+        buf.pop();
+    }
+    let last = buf.len() - 1;
+    for byte in buf[start..last].iter_mut() {
+        *byte |= 0b1000_0000;
+    }
+    buf.len() - start
+}
+
+/// Read an unsigned number from this buffer, returning it as little endian bytes (without any high
+/// zero bytes, so zero is empty) & the rest of the buffer.
+pub fn read_big_unsigned(buf: &[u8]) -> Result<(Vec<u8>, &[u8]), VartyIntError> {
+    if buf.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    let len = match buf.iter().position(|b| b >> 7 == 0) {
+        None => {
+            return Err(VartyIntError::NotEnoughBytes {
+                read: buf.len(),
+                max_more: None,
+            })
+        }
+        Some(i) => i + 1,
+    };
+    let mut num = Vec::with_capacity(len);
+    let mut acc: u16 = 0;
+    let mut bits = 0;
+    for byte in buf[..len].iter() {
+        acc |= ((byte & 0b0111_1111) as u16) << bits;
+        bits += 7;
+        if bits >= 8 {
+            num.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    }
+    num.push(acc as u8);
+    Ok((trim(num), &buf[len..]))
+}
+
+/// Write this signed number, as a sign (`true` for negative) & the magnitude as little endian
+/// bytes, to the buffer. Returns the number of bytes written.
+pub fn write_big_signed(negative: bool, magnitude: &[u8], buf: &mut Vec<u8>) -> usize {
+    let mut num = trim(magnitude.to_vec());
+    // zigzag: n → 2n, -n → 2n - 1
+    shl1(&mut num);
+    if negative && !num.is_empty() {
+        add_one(&mut num, true);
+    }
+    write_big_unsigned(&num, buf)
+}
+
+/// Read a signed number from this buffer, returning the sign (`true` for negative) & the magnitude
+/// as little endian bytes (without any high zero bytes), & the rest of the buffer.
+pub fn read_big_signed(buf: &[u8]) -> Result<(BigSigned, &[u8]), VartyIntError> {
+    let (mut num, rest) = read_big_unsigned(buf)?;
+    let negative = num.first().is_some_and(|b| b & 1 == 1);
+    if negative {
+        add_one(&mut num, false);
+    }
+    shr1(&mut num);
+    Ok(((negative, trim(num)), rest))
+}
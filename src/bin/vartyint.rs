@@ -0,0 +1,242 @@
+//! Command line tool to encode, decode & inspect varint byte streams.
+//!
+//! ```text
+//! vartyint encode [-t TYPE] [--raw] [NUM...]
+//! vartyint decode [-t TYPE] [--raw] [HEX]
+//! vartyint inspect [-t TYPE] [FILE]
+//! ```
+//!
+//! `TYPE` is one of `u8`, `u16`, `u32`, `u64`, `u128`, `i8`, `i16`, `i32`, `i64`, `i128` (default
+//! `i64`). When no numbers/hex/file is given, it's read from stdin. `--raw` writes (for `encode`)
+//! or reads (for `decode`) raw bytes instead of hex.
+use std::io::{Read, Write};
+use std::process::exit;
+use vartyint::VarInt;
+
+const USAGE: &str = "Usage:
+    vartyint encode [-t TYPE] [--raw] [NUM...]
+    vartyint decode [-t TYPE] [--raw] [HEX]
+    vartyint inspect [-t TYPE] [FILE]
+
+TYPE is one of u8, u16, u32, u64, u128, i8, i16, i32, i64, i128 (default i64)";
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: String,
+    num_type: String,
+    raw: bool,
+    rest: Vec<String>,
+}
+
+/// Parse these arguments (without the program name). An empty error means only print the usage.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or("No command given")?;
+    if command == "-h" || command == "--help" {
+        return Err(String::new());
+    }
+    let mut num_type = "i64".to_string();
+    let mut raw = false;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-t" | "--type" => {
+                num_type = args.next().ok_or("No type given after -t")?;
+            }
+            "--raw" => raw = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ => rest.push(arg),
+        }
+    }
+    if command == "decode" && raw && !rest.is_empty() {
+        return Err("decode --raw reads from stdin, so no HEX can be given".to_string());
+    }
+    Ok(Args {
+        command,
+        num_type,
+        raw,
+        rest,
+    })
+}
+
+fn stdin_bytes() -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+fn stdin_text() -> Result<String, String> {
+    String::from_utf8(stdin_bytes()?).map_err(|e| e.to_string())
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    vartyint::from_hex(text).map_err(|_| format!("Invalid hex: {:?}", text.trim()))
+}
+
+fn encode<T>(args: &Args) -> Result<(), String>
+where
+    T: VarInt + std::str::FromStr,
+{
+    let text = if args.rest.is_empty() {
+        stdin_text()?
+    } else {
+        args.rest.join(" ")
+    };
+    let nums = text
+        .split_whitespace()
+        .map(|s| {
+            s.parse::<T>()
+                .map_err(|_| format!("Invalid number: {:?}", s))
+        })
+        .collect::<Result<Vec<T>, _>>()?;
+    let bytes = vartyint::write_many_new(&nums);
+
+    if args.raw {
+        std::io::stdout()
+            .write_all(&bytes)
+            .map_err(|e| e.to_string())?;
+    } else {
+        println!("{}", vartyint::to_hex(&bytes));
+    }
+    Ok(())
+}
+
+fn decode<T>(args: &Args) -> Result<(), String>
+where
+    T: VarInt + std::fmt::Display,
+{
+    let bytes = if args.raw {
+        stdin_bytes()?
+    } else if args.rest.is_empty() {
+        from_hex(&stdin_text()?)?
+    } else {
+        from_hex(&args.rest.join(""))?
+    };
+    for num in vartyint::read_many::<T>(&bytes) {
+        println!("{}", num.map_err(|e| e.to_string())?);
+    }
+    Ok(())
+}
+
+fn inspect<T>(args: &Args) -> Result<(), String>
+where
+    T: VarInt + std::fmt::Display,
+{
+    let bytes = match args.rest.first() {
+        None => stdin_bytes()?,
+        Some(path) => std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?,
+    };
+    // Where the next varint starts, for errors
+    let (mut index, mut offset) = (0, 0);
+    for expl in vartyint::explain::<T>(&bytes) {
+        let expl = expl.map_err(|e| format!("#{} offset {}: {}", index, offset, e))?;
+        (index, offset) = (expl.index + 1, expl.range.end);
+        println!(
+            "#{}\toffset {}\tlen {}\t[{}]\t{}\t{}{}",
+            expl.index,
+            expl.range.start,
+            expl.bytes.len(),
+            vartyint::to_hex(expl.bytes),
+            expl.continuation_pattern(),
+            expl.value,
+            if expl.minimal { "" } else { "\t(not minimal)" },
+        );
+    }
+    Ok(())
+}
+
+macro_rules! dispatch {
+    ( $func:ident, $args:expr, $( $type:ty ),* ) => {
+        match $args.num_type.as_str() {
+            $( stringify!($type) => $func::<$type>($args), )*
+            other => Err(format!("Unknown type: {:?}", other)),
+        }
+    };
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    match args.command.as_str() {
+        "encode" => dispatch!(encode, args, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128),
+        "decode" => dispatch!(decode, args, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128),
+        "inspect" => dispatch!(inspect, args, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128),
+        other => Err(format!("Unknown command: {:?}", other)),
+    }
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            if !msg.is_empty() {
+                eprintln!("{}\n", msg);
+            }
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    if let Err(msg) = run(&args) {
+        eprintln!("Error: {}", msg);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(
+            parse(&["encode", "-t", "u8", "1", "2"]),
+            Ok(Args {
+                command: "encode".to_string(),
+                num_type: "u8".to_string(),
+                raw: false,
+                rest: vec!["1".to_string(), "2".to_string()],
+            })
+        );
+        let args = parse(&["encode", "--raw", "5"]).unwrap();
+        assert_eq!(args.num_type, "i64");
+        assert!(args.raw);
+        assert_eq!(args.rest, vec!["5"]);
+        assert_eq!(
+            parse(&["encode", "-t"]),
+            Err("No type given after -t".to_string())
+        );
+    }
+
+    #[test]
+    fn decode() {
+        let args = parse(&["decode", "--type", "u32", "ac02"]).unwrap();
+        assert_eq!(args.command, "decode");
+        assert_eq!(args.num_type, "u32");
+        assert_eq!(args.rest, vec!["ac02"]);
+        assert!(parse(&["decode", "--raw"]).unwrap().raw);
+        // Raw bytes are only read from stdin
+        assert!(parse(&["decode", "--raw", "ac02"]).is_err());
+        assert!(parse(&["decode", "ac02", "--raw"]).is_err());
+    }
+
+    #[test]
+    fn inspect() {
+        let args = parse(&["inspect", "nums.bin", "-t", "i8"]).unwrap();
+        assert_eq!(args.command, "inspect");
+        assert_eq!(args.num_type, "i8");
+        assert_eq!(args.rest, vec!["nums.bin"]);
+        assert!(parse(&["inspect"]).unwrap().rest.is_empty());
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(parse(&["--help"]), Err(String::new()));
+        assert_eq!(parse(&["-h"]), Err(String::new()));
+        assert_eq!(parse(&["encode", "-h"]), Err(String::new()));
+        assert_eq!(parse(&[]), Err("No command given".to_string()));
+    }
+}
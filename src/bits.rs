@@ -0,0 +1,129 @@
+//! Write & read individual bits, most significant bit first. Used for bit level formats like
+//! [`gorilla`](crate::gorilla).
+//!
+//! ```rust
+//! use vartyint::bits::{BitReader, BitWriter};
+//! let mut writer = BitWriter::new();
+//! writer.write_bit(true);
+//! writer.write_bits(0b101, 3);
+//! assert_eq!(writer.len_bits(), 4);
+//! let buf = writer.into_bytes();
+//! assert_eq!(buf, vec![0b1101_0000]);
+//!
+//! let mut reader = BitReader::new(&buf);
+//! assert_eq!(reader.read_bit(), Ok(true));
+//! assert_eq!(reader.read_bits(3), Ok(0b101));
+//! ```
+use crate::VartyIntError;
+
+/// Writes bits to a `Vec<u8>`. The last byte is padded with 0 bits.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BitWriter {
+    buf: Vec<u8>,
+    /// Number of bits used in the last byte of `buf` (0 means it's full, or there is no byte)
+    used: u32,
+}
+
+impl BitWriter {
+    /// A writer with no bits
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Write one bit
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write_bits(bit as u64, 1);
+    }
+
+    /// Write the lowest `num_bits` bits of `val` (up to 64), most significant first
+    pub fn write_bits(&mut self, val: u64, num_bits: u32) {
+        assert!(num_bits <= 64);
+        let mut left = num_bits;
+        while left > 0 {
+            if self.used == 0 {
+                self.buf.push(0);
+            }
+            let free = 8 - self.used;
+            let n = free.min(left);
+            // The top `n` bits of what's left
+            let bits = ((val >> (left - n)) & ((1 << n) - 1)) as u8;
+            *self.buf.last_mut().unwrap() |= bits << (free - n);
+            self.used = (self.used + n) % 8;
+            left -= n;
+        }
+    }
+
+    /// Number of bits written
+    pub fn len_bits(&self) -> usize {
+        match self.used {
+            0 => self.buf.len() * 8,
+            used => (self.buf.len() - 1) * 8 + used as usize,
+        }
+    }
+
+    /// True iff no bits have been written
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// The bytes written, with the last byte padded with 0 bits
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Reads bits from a buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitReader<'a> {
+    buf: &'a [u8],
+    /// Number of bits read
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// A reader at the start of this buffer
+    pub fn new(buf: &'a [u8]) -> Self {
+        BitReader { buf, pos: 0 }
+    }
+
+    /// Read one bit
+    pub fn read_bit(&mut self) -> Result<bool, VartyIntError> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Read `num_bits` bits (up to 64), most significant first. If there aren't enough bits,
+    /// `NotEnoughBytes` is returned & nothing is read.
+    pub fn read_bits(&mut self, num_bits: u32) -> Result<u64, VartyIntError> {
+        assert!(num_bits <= 64);
+        if self.pos + num_bits as usize > self.buf.len() * 8 {
+            let start = self.pos / 8;
+            let end = (self.pos + num_bits as usize).div_ceil(8);
+            return Err(VartyIntError::not_enough(
+                self.buf.len() - start,
+                end - start,
+            ));
+        }
+        let mut val = 0u64;
+        let mut left = num_bits;
+        while left > 0 {
+            let byte = self.buf[self.pos / 8];
+            let offset = (self.pos % 8) as u32;
+            let n = (8 - offset).min(left);
+            let bits = (byte >> (8 - offset - n)) & ((1u16 << n) - 1) as u8;
+            val = (val << n) | bits as u64;
+            self.pos += n as usize;
+            left -= n;
+        }
+        Ok(val)
+    }
+
+    /// Number of bits read
+    pub fn position_bits(&self) -> usize {
+        self.pos
+    }
+
+    /// The bytes after the last byte which has been (partly) read
+    pub fn remaining_bytes(&self) -> &'a [u8] {
+        &self.buf[self.pos.div_ceil(8)..]
+    }
+}
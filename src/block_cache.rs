@@ -0,0 +1,138 @@
+//! `BlockCache`, a small least recently used cache of decoded blocks, so reading the same block
+//! again doesn't decode it again. Used by [`BlockFileReader`](crate::block_file::BlockFileReader),
+//! [`ZoneMap`](crate::zonemap::ZoneMap) & [`VarIntMap`](crate::varint_map::VarIntMap) when they're
+//! made `with_cache`.
+//!
+//! ```rust
+//! use vartyint::block_cache::BlockCache;
+//! let cache = BlockCache::new(2);
+//! let decode = |i: usize| Ok::<_, ()>(vec![i; 3]);
+//! assert_eq!(cache.get_or_insert_with(1, decode), Ok(vec![1, 1, 1]));
+//! assert_eq!(cache.get_or_insert_with(1, decode), Ok(vec![1, 1, 1]));
+//! assert_eq!((cache.hits(), cache.misses()), (1, 1));
+//! ```
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Debug)]
+struct Inner<V> {
+    // Block number → (block, when it was last used)
+    blocks: HashMap<usize, (V, u64)>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// Up to `capacity` decoded blocks, by block number. When it's full, the least recently used
+/// block is removed. It can be shared between threads.
+pub struct BlockCache<V> {
+    capacity: usize,
+    inner: Mutex<Inner<V>>,
+}
+
+impl<V> BlockCache<V> {
+    /// An empty cache which holds up to `capacity` blocks. If it's 0, nothing is cached.
+    pub fn new(capacity: usize) -> Self {
+        BlockCache {
+            capacity,
+            inner: Mutex::new(Inner {
+                blocks: HashMap::with_capacity(capacity.min(1024)),
+                tick: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Maximum number of blocks
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of blocks in the cache
+    pub fn len(&self) -> usize {
+        self.lock().blocks.len()
+    }
+
+    /// True iff there are no blocks in the cache
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of times a block was in the cache
+    pub fn hits(&self) -> u64 {
+        self.lock().hits
+    }
+
+    /// Number of times a block had to be decoded
+    pub fn misses(&self) -> u64 {
+        self.lock().misses
+    }
+
+    /// Remove all the blocks
+    pub fn clear(&self) {
+        self.lock().blocks.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner<V>> {
+        // The cache is always consistent, even if another thread panicked
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<V: Clone> BlockCache<V> {
+    /// Block `i` from the cache, or else decode it with `f` & add it. Errors aren't cached.
+    pub fn get_or_insert_with<E>(
+        &self,
+        i: usize,
+        f: impl FnOnce(usize) -> Result<V, E>,
+    ) -> Result<V, E> {
+        {
+            let mut inner = self.lock();
+            inner.tick += 1;
+            let tick = inner.tick;
+            if let Some((block, last_used)) = inner.blocks.get_mut(&i) {
+                *last_used = tick;
+                let block = block.clone();
+                inner.hits += 1;
+                return Ok(block);
+            }
+            inner.misses += 1;
+        }
+
+        // Not locked while decoding, so other threads can use the cache
+        let block = f(i)?;
+        if self.capacity > 0 {
+            let mut inner = self.lock();
+            if inner.blocks.len() >= self.capacity && !inner.blocks.contains_key(&i) {
+                let oldest = inner
+                    .blocks
+                    .iter()
+                    .min_by_key(|(_, (_, last_used))| *last_used)
+                    .map(|(i, _)| *i);
+                if let Some(oldest) = oldest {
+                    inner.blocks.remove(&oldest);
+                }
+            }
+            let tick = inner.tick;
+            inner.blocks.insert(i, (block.clone(), tick));
+        }
+        Ok(block)
+    }
+}
+
+impl<V> Clone for BlockCache<V> {
+    /// A new, empty, cache with the same capacity
+    fn clone(&self) -> Self {
+        BlockCache::new(self.capacity)
+    }
+}
+
+impl<V> std::fmt::Debug for BlockCache<V> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        fmt.debug_struct("BlockCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,218 @@
+//! A file of blocks of integers, with a directory of the blocks at the end, so any block can be
+//! read without reading the ones before it.
+//!
+//! The file is the blocks (each as varints, one after the other), then the directory: the number
+//! of blocks, then the length in bytes & number of integers of each block. The last 12 bytes are
+//! the trailer: the offset of the directory (little endian `u64`), then `VTBF`.
+//!
+//! ```rust
+//! use vartyint::block_file::{BlockFileReader, BlockFileWriter};
+//! use std::io::Cursor;
+//! let mut writer = BlockFileWriter::new(Vec::new());
+//! writer.write_block(&[1u64, 2, 3]).unwrap();
+//! writer.write_block(&[1_000_000]).unwrap();
+//! let file = writer.finish().unwrap();
+//!
+//! let mut reader = BlockFileReader::<_, u64>::open(Cursor::new(file)).unwrap();
+//! assert_eq!(reader.num_blocks(), 2);
+//! assert_eq!(reader.len(), 4);
+//! assert_eq!(reader.read_block(1).unwrap(), vec![1_000_000]);
+//! assert_eq!(reader.read_block(0).unwrap(), vec![1, 2, 3]);
+//! ```
+use crate::block_cache::BlockCache;
+use crate::*;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+const MAGIC: &[u8; 4] = b"VTBF";
+const TRAILER_LEN: usize = 8 + MAGIC.len();
+
+/// Where a block is, & how many integers are in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockInfo {
+    /// Offset of the block from the start of the file
+    pub offset: u64,
+    /// Length of the block in bytes
+    pub len: u64,
+    /// Number of integers in the block
+    pub count: u64,
+}
+
+/// Writes blocks of integers, & the directory at the end
+#[derive(Debug)]
+pub struct BlockFileWriter<W, T> {
+    inner: W,
+    blocks: Vec<BlockInfo>,
+    position: u64,
+    buf: Vec<u8>,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<W: Write, T: VarInt> BlockFileWriter<W, T> {
+    /// A new file, written to `inner`. Offsets are from where `inner` is now.
+    pub fn new(inner: W) -> Self {
+        BlockFileWriter {
+            inner,
+            blocks: Vec::new(),
+            position: 0,
+            buf: Vec::new(),
+            _type: std::marker::PhantomData,
+        }
+    }
+
+    /// Write this block. Returns the number of the block.
+    pub fn write_block(&mut self, nums: &[T]) -> std::io::Result<usize> {
+        self.buf.clear();
+        let len = write_many(nums, &mut self.buf) as u64;
+        self.inner.write_all(&self.buf)?;
+        self.blocks.push(BlockInfo {
+            offset: self.position,
+            len,
+            count: nums.len() as u64,
+        });
+        self.position += len;
+        Ok(self.blocks.len() - 1)
+    }
+
+    /// Number of blocks written so far
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Write the directory & the trailer, & return the inner writer
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.buf.clear();
+        self.blocks.len().write_varint(&mut self.buf);
+        for block in self.blocks.iter() {
+            block.len.write_varint(&mut self.buf);
+            block.count.write_varint(&mut self.buf);
+        }
+        self.buf.extend_from_slice(&self.position.to_le_bytes());
+        self.buf.extend_from_slice(MAGIC);
+        self.inner.write_all(&self.buf)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Read & decode this block, using `buf` for the bytes
+fn read_block<R: Read + Seek, T: VarInt>(
+    inner: &mut R,
+    buf: &mut Vec<u8>,
+    block: BlockInfo,
+) -> Result<Vec<T>, VartyIntReadError> {
+    inner.seek(SeekFrom::Start(block.offset))?;
+    buf.resize(block.len as usize, 0);
+    inner.read_exact(buf)?;
+    let (nums, rest) = read_many_exact::<T>(buf, block.count as usize)?;
+    if !rest.is_empty() {
+        return Err(VartyIntError::TrailingBytes.into());
+    }
+    Ok(nums)
+}
+
+fn invalid_data(msg: &str) -> VartyIntReadError {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg).into()
+}
+
+/// Reads any block of a file from `BlockFileWriter`
+#[derive(Debug)]
+pub struct BlockFileReader<R, T> {
+    inner: R,
+    blocks: Vec<BlockInfo>,
+    buf: Vec<u8>,
+    cache: Option<BlockCache<Vec<T>>>,
+}
+
+impl<R: Read + Seek, T: VarInt> BlockFileReader<R, T> {
+    /// Read the trailer & the directory. Returns an `InvalidData` error if it's not a block file.
+    pub fn open(mut inner: R) -> Result<Self, VartyIntReadError> {
+        let end = inner.seek(SeekFrom::End(0))?;
+        if end < TRAILER_LEN as u64 {
+            return Err(invalid_data("too short for a block file"));
+        }
+        inner.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+        let mut trailer = [0; TRAILER_LEN];
+        inner.read_exact(&mut trailer)?;
+        if &trailer[8..] != MAGIC {
+            return Err(invalid_data("not a block file"));
+        }
+        let dir_offset = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+        if dir_offset > end - TRAILER_LEN as u64 {
+            return Err(invalid_data("directory offset is after the end"));
+        }
+
+        inner.seek(SeekFrom::Start(dir_offset))?;
+        let mut dir = vec![0; (end - TRAILER_LEN as u64 - dir_offset) as usize];
+        inner.read_exact(&mut dir)?;
+        let (num_blocks, mut rest) = usize::read_varint(&dir)?;
+        let mut blocks = Vec::with_capacity(num_blocks.min(dir.len()));
+        let mut offset = 0;
+        for _ in 0..num_blocks {
+            let ((len, count), newrest) =
+                <(u64, u64)>::read_varint(rest).map_err(empty_to_not_enough::<(u64, u64)>)?;
+            blocks.push(BlockInfo { offset, len, count });
+            offset = offset.saturating_add(len);
+            rest = newrest;
+        }
+        if !rest.is_empty() {
+            return Err(VartyIntError::TrailingBytes.into());
+        }
+        if offset != dir_offset {
+            return Err(invalid_data("blocks don't end at the directory"));
+        }
+        Ok(BlockFileReader {
+            inner,
+            blocks,
+            buf: Vec::new(),
+            cache: None,
+        })
+    }
+
+    /// Keep up to `blocks` decoded blocks in a `BlockCache`, so reading them again doesn't read
+    /// the file
+    pub fn with_cache(mut self, blocks: usize) -> Self {
+        self.cache = Some(BlockCache::new(blocks));
+        self
+    }
+
+    /// The cache, if there is one
+    pub fn cache(&self) -> Option<&BlockCache<Vec<T>>> {
+        self.cache.as_ref()
+    }
+
+    /// Number of blocks
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Total number of integers in all the blocks
+    pub fn len(&self) -> u64 {
+        self.blocks.iter().map(|block| block.count).sum()
+    }
+
+    /// True iff there are no integers
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Where each block is
+    pub fn blocks(&self) -> &[BlockInfo] {
+        &self.blocks
+    }
+
+    /// Read all the integers in block `i` (or get them from the cache). Panics if there is no
+    /// block `i`.
+    pub fn read_block(&mut self, i: usize) -> Result<Vec<T>, VartyIntReadError> {
+        let block = self.blocks[i];
+        let (inner, buf) = (&mut self.inner, &mut self.buf);
+        match &self.cache {
+            Some(cache) => cache.get_or_insert_with(i, |_| read_block(inner, buf, block)),
+            None => read_block(inner, buf, block),
+        }
+    }
+
+    /// Return the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
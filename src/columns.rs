@@ -0,0 +1,230 @@
+//! Write tuples column by column ("struct of arrays"), so each position of the tuple is its own
+//! contiguous list of varints. Similar values end up next to each other, which is smaller (esp.
+//! when delta encoded) & compresses better than writing each tuple in turn.
+//!
+//! ```rust
+//! use vartyint::columns;
+//! let points = vec![(1_000u32, 10i64, 5u8), (1_001, 12, 5), (1_003, 13, 6)];
+//! let mut buf = Vec::new();
+//! // Delta encode the first 2 columns
+//! columns::write_columns3(points.iter().copied(), [true, true, false], &mut buf);
+//! assert_eq!(
+//!     columns::read_columns3::<u32, i64, u8>(&buf, [true, true, false]).unwrap(),
+//!     points
+//! );
+//! ```
+//!
+//! The data is the number of tuples, then each column, as the number of bytes in it followed by
+//! the values. The same `delta` flags must be used to read it.
+use crate::*;
+
+/// Writes one column to its own buffer
+struct ColumnWriter<T> {
+    delta: bool,
+    last: T,
+    buf: Vec<u8>,
+}
+
+impl<T> ColumnWriter<T>
+where
+    T: VarInt + std::ops::Sub<T, Output = T>,
+{
+    fn new(delta: bool) -> Self {
+        ColumnWriter {
+            delta,
+            last: T::zero(),
+            buf: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, val: T) {
+        if self.delta {
+            (val - self.last).write_varint(&mut self.buf);
+            self.last = val;
+        } else {
+            val.write_varint(&mut self.buf);
+        }
+    }
+
+    /// Write the length & the column to `buf`
+    fn finish(self, buf: &mut Vec<u8>) {
+        self.buf.len().write_varint(buf);
+        buf.extend_from_slice(&self.buf);
+    }
+}
+
+/// Read one column of `num` values, returning them & the rest of the buffer
+fn read_column<T: DeltaInt>(
+    buf: &[u8],
+    num: usize,
+    delta: bool,
+) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+    let (len, rest) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+    if rest.len() < len {
+        return Err(VartyIntError::not_enough(rest.len(), len));
+    }
+    let (mut column, rest) = rest.split_at(len);
+    let mut vals = Vec::with_capacity(num.min(len));
+    let mut last = T::zero();
+    for _ in 0..num {
+        let (val, newcolumn) = T::read_varint(column).map_err(empty_to_not_enough::<T>)?;
+        column = newcolumn;
+        last = if delta {
+            last.checked_add_delta(val)
+                .ok_or(VartyIntError::DeltaOverflow)?
+        } else {
+            val
+        };
+        vals.push(last);
+    }
+    if !column.is_empty() {
+        return Err(VartyIntError::TrailingBytes);
+    }
+    Ok((vals, rest))
+}
+
+macro_rules! columns_impl {
+    ( $write:ident, $read:ident, $num:literal, $( $type:ident $idx:tt $col:ident ),* ) => {
+        /// Write these tuples to the end of `buf`, one column at a time. Column `i` is delta
+        /// encoded iff `delta[i]`. Returns the number of tuples written.
+        pub fn $write<$( $type ),*>(
+            rows: impl IntoIterator<Item = ( $( $type, )* )>,
+            delta: [bool; $num],
+            buf: &mut Vec<u8>,
+        ) -> usize
+        where
+            $( $type: VarInt + std::ops::Sub<$type, Output = $type>, )*
+        {
+            let mut writers = ( $( ColumnWriter::<$type>::new(delta[$idx]), )* );
+            let mut count = 0;
+            for row in rows {
+                $( writers.$idx.push(row.$idx); )*
+                count += 1;
+            }
+            count.write_varint(buf);
+            $( writers.$idx.finish(buf); )*
+            count
+        }
+
+        /// Read all the tuples in this buffer, written by the matching `write_columns` function
+        /// with the same `delta`.
+        pub fn $read<$( $type ),*>(
+            buf: &[u8],
+            delta: [bool; $num],
+        ) -> Result<Vec<( $( $type, )* )>, VartyIntError>
+        where
+            $( $type: DeltaInt, )*
+        {
+            let (num, mut rest) = usize::read_varint(buf)?;
+            $(
+                let ($col, newrest) = read_column::<$type>(rest, num, delta[$idx])?;
+                rest = newrest;
+                let mut $col = $col.into_iter();
+            )*
+            if !rest.is_empty() {
+                return Err(VartyIntError::TrailingBytes);
+            }
+            Ok((0..num).map(|_| ( $( $col.next().unwrap(), )* )).collect())
+        }
+    };
+}
+
+columns_impl!(write_columns2, read_columns2, 2, A 0 a, B 1 b);
+columns_impl!(write_columns3, read_columns3, 3, A 0 a, B 1 b, C 2 c);
+
+/// Write these `K` streams (e.g. x, y & z coordinates), each delta encoded from its own previous
+/// value, interleaved, i.e. row by row. Returns the number of bytes written.
+///
+/// ```rust
+/// use vartyint::columns;
+/// let points = vec![[100i32, 200, 5], [101, 198, 5], [103, 197, 6]];
+/// let mut buf = Vec::new();
+/// columns::write_interleaved_delta(&points, &mut buf);
+/// assert_eq!(buf.len(), 5 + 3 + 3);
+/// assert_eq!(columns::read_interleaved_delta::<i32, 3>(&buf).unwrap(), points);
+/// ```
+pub fn write_interleaved_delta<T, const K: usize>(rows: &[[T; K]], buf: &mut Vec<u8>) -> usize
+where
+    T: VarInt + std::ops::Sub<T, Output = T>,
+{
+    let mut last = [T::zero(); K];
+    let mut len = 0;
+    for row in rows {
+        for (val, last) in row.iter().zip(last.iter_mut()) {
+            len += (*val - *last).write_varint(buf);
+            *last = *val;
+        }
+    }
+    len
+}
+
+/// Read all the rows in this buffer, written by `write_interleaved_delta`. If the buffer ends in
+/// the middle of a row, `NotEnoughBytes` is returned.
+pub fn read_interleaved_delta<T: DeltaInt, const K: usize>(
+    buf: &[u8],
+) -> Result<Vec<[T; K]>, VartyIntError> {
+    let mut rows = Vec::new();
+    let mut last = [T::zero(); K];
+    let mut rest = buf;
+    while !rest.is_empty() {
+        let (deltas, newrest) = read_array::<T, K>(rest)?;
+        for (last, delta) in last.iter_mut().zip(deltas) {
+            *last = last
+                .checked_add_delta(delta)
+                .ok_or(VartyIntError::DeltaOverflow)?;
+        }
+        rows.push(last);
+        rest = newrest;
+    }
+    Ok(rows)
+}
+
+/// Like `write_interleaved_delta`, but planar, i.e. each stream is written separately, in the
+/// same format as `write_columns2`/`write_columns3`. Returns the number of rows written.
+///
+/// ```rust
+/// use vartyint::columns;
+/// let points = vec![[100i32, 200, 5], [101, 198, 5], [103, 197, 6]];
+/// let mut buf = Vec::new();
+/// columns::write_planar_delta(&points, &mut buf);
+/// assert_eq!(columns::read_planar_delta::<i32, 3>(&buf).unwrap(), points);
+/// assert_eq!(
+///     columns::read_columns3::<i32, i32, i32>(&buf, [true; 3]).unwrap(),
+///     vec![(100, 200, 5), (101, 198, 5), (103, 197, 6)]
+/// );
+/// ```
+pub fn write_planar_delta<T, const K: usize>(rows: &[[T; K]], buf: &mut Vec<u8>) -> usize
+where
+    T: VarInt + std::ops::Sub<T, Output = T>,
+{
+    let mut writers: [ColumnWriter<T>; K] = std::array::from_fn(|_| ColumnWriter::new(true));
+    for row in rows {
+        for (val, writer) in row.iter().zip(writers.iter_mut()) {
+            writer.push(*val);
+        }
+    }
+    rows.len().write_varint(buf);
+    for writer in writers {
+        writer.finish(buf);
+    }
+    rows.len()
+}
+
+/// Read all the rows in this buffer, written by `write_planar_delta`.
+pub fn read_planar_delta<T: DeltaInt, const K: usize>(
+    buf: &[u8],
+) -> Result<Vec<[T; K]>, VartyIntError> {
+    let (num, mut rest) = usize::read_varint(buf)?;
+    let mut columns = Vec::with_capacity(K);
+    for _ in 0..K {
+        let (vals, newrest) = read_column::<T>(rest, num, true)?;
+        columns.push(vals.into_iter());
+        rest = newrest;
+    }
+    if !rest.is_empty() {
+        return Err(VartyIntError::TrailingBytes);
+    }
+    Ok((0..num)
+        .map(|_| std::array::from_fn(|k| columns[k].next().unwrap()))
+        .collect())
+}
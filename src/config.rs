@@ -0,0 +1,201 @@
+//! `EncodingConfig`, to choose how varints are written in one place, rather than choosing between
+//! the different functions every time.
+//!
+//! ```rust
+//! use vartyint::config::EncodingConfig;
+//! let config = EncodingConfig::libp2p();
+//! let mut buf = Vec::new();
+//! config.encode(300u64, &mut buf).unwrap();
+//! assert_eq!(buf, vec![0xAC, 0x02]);
+//! assert_eq!(config.decode::<u64>(&buf), Ok((300, &[] as &[u8])));
+//! // Padded values are rejected
+//! assert_eq!(
+//!     config.decode::<u64>(&[0x81, 0x00]),
+//!     Err(vartyint::VartyIntError::NonCanonical)
+//! );
+//! ```
+use crate::*;
+
+/// The order of the 7 bit groups of a varint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GroupOrder {
+    /// Least significant group first, i.e. LEB128, as used by protobuf & the rest of this crate
+    #[default]
+    LeastSignificantFirst,
+    /// Most significant group first, i.e. VLQ, as used by MIDI & git
+    MostSignificantFirst,
+}
+
+/// How to write & read varints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodingConfig {
+    /// Zigzag encode signed integers (the default). Otherwise they are written like `Raw`.
+    pub zigzag: bool,
+    /// When reading, reject values with redundant continuation bytes (i.e. padded values), with
+    /// `NonCanonical`
+    pub canonical: bool,
+    /// The maximum number of bytes in one value. Longer values are rejected with
+    /// `TooManyBytesForType` when reading, & `DoesNotFit` when writing.
+    pub max_len: Option<usize>,
+    /// The order of the 7 bit groups
+    pub group_order: GroupOrder,
+}
+
+impl Default for EncodingConfig {
+    /// The same as the rest of this crate
+    fn default() -> Self {
+        EncodingConfig {
+            zigzag: true,
+            canonical: false,
+            max_len: None,
+            group_order: GroupOrder::LeastSignificantFirst,
+        }
+    }
+}
+
+impl EncodingConfig {
+    /// The same as the rest of this crate
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Like protobuf's `int64`/`uint64`: signed integers aren't zigzag encoded, and values are at
+    /// most 10 bytes. (Use `zigzag(true)` for `sint64`)
+    pub fn protobuf() -> Self {
+        EncodingConfig::new().zigzag(false).max_len(10)
+    }
+
+    /// Like the [multiformats unsigned-varint](https://github.com/multiformats/unsigned-varint)
+    /// used by libp2p: only canonical encodings, and at most 9 bytes.
+    pub fn libp2p() -> Self {
+        EncodingConfig::new().canonical(true).max_len(9)
+    }
+
+    /// Set whether signed integers are zigzag encoded
+    pub fn zigzag(mut self, zigzag: bool) -> Self {
+        self.zigzag = zigzag;
+        self
+    }
+
+    /// Set whether non canonical (padded) values are rejected when reading
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Set the maximum number of bytes of one value
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Set the order of the 7 bit groups
+    pub fn group_order(mut self, group_order: GroupOrder) -> Self {
+        self.group_order = group_order;
+        self
+    }
+
+    /// Write this value to the end of `buf`, returning the number of bytes written. If it's longer
+    /// than `max_len`, `DoesNotFit` is returned & nothing is written.
+    pub fn encode<T>(&self, val: T, buf: &mut Vec<u8>) -> Result<usize, VartyIntError>
+    where
+        T: VarInt,
+        Raw<T>: VarInt,
+    {
+        let bytes = if self.zigzag {
+            val.as_varint()
+        } else {
+            Raw(val).as_varint()
+        };
+        if self.max_len.is_some_and(|max_len| bytes.len() > max_len) {
+            return Err(VartyIntError::DoesNotFit);
+        }
+        match self.group_order {
+            GroupOrder::LeastSignificantFirst => buf.extend_from_slice(&bytes),
+            GroupOrder::MostSignificantFirst => {
+                // Minimal groups in reverse are still minimal
+                let last = bytes.len() - 1;
+                buf.extend(bytes.iter().rev().enumerate().map(|(i, byte)| {
+                    (byte & 0b0111_1111) | if i == last { 0 } else { 0b1000_0000 }
+                }));
+            }
+        }
+        Ok(bytes.len())
+    }
+
+    /// Read one value from the start of the buffer, returning it & the rest of the buffer.
+    pub fn decode<'a, T>(&self, buf: &'a [u8]) -> Result<(T, &'a [u8]), VartyIntError>
+    where
+        T: VarInt,
+        Raw<T>: VarInt,
+    {
+        if buf.is_empty() {
+            return Err(VartyIntError::EmptyBuffer);
+        }
+        let max_len = self.max_len.unwrap_or(usize::MAX);
+        let len = match buf.iter().position(|b| b & 0b1000_0000 == 0) {
+            Some(i) if i < max_len => i + 1,
+            Some(_) => return Err(VartyIntError::TooManyBytesForType),
+            None if buf.len() >= max_len => return Err(VartyIntError::TooManyBytesForType),
+            None => {
+                return Err(VartyIntError::not_enough(
+                    buf.len(),
+                    max_len.min(T::MAX_LEN),
+                ))
+            }
+        };
+        let (groups, rest) = buf.split_at(len);
+
+        // Convert to LEB128, for `T` to decode
+        let mut bytes = groups.to_vec();
+        if self.group_order == GroupOrder::MostSignificantFirst {
+            bytes.reverse();
+            for byte in bytes.iter_mut() {
+                *byte |= 0b1000_0000;
+            }
+            bytes[len - 1] &= 0b0111_1111;
+        }
+        if self.canonical && len > 1 && bytes[len - 1] == 0 {
+            return Err(VartyIntError::NonCanonical);
+        }
+
+        let val = if self.zigzag {
+            T::read_varint(&bytes)?.0
+        } else {
+            Raw::<T>::read_varint(&bytes)?.0 .0
+        };
+        Ok((val, rest))
+    }
+
+    /// Write all these values to the end of `buf`, returning the number of bytes written. On error,
+    /// `buf` is unchanged.
+    pub fn encode_many<T>(&self, vals: &[T], buf: &mut Vec<u8>) -> Result<usize, VartyIntError>
+    where
+        T: VarInt,
+        Raw<T>: VarInt,
+    {
+        let start = buf.len();
+        for val in vals {
+            if let Err(e) = self.encode(*val, buf) {
+                buf.truncate(start);
+                return Err(e);
+            }
+        }
+        Ok(buf.len() - start)
+    }
+
+    /// Read all the values in this buffer
+    pub fn decode_many<T>(&self, mut buf: &[u8]) -> Result<Vec<T>, VartyIntError>
+    where
+        T: VarInt,
+        Raw<T>: VarInt,
+    {
+        let mut vals = Vec::new();
+        while !buf.is_empty() {
+            let (val, rest) = self.decode(buf)?;
+            vals.push(val);
+            buf = rest;
+        }
+        Ok(vals)
+    }
+}
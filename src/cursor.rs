@@ -0,0 +1,170 @@
+//! Cursors, which keep track of the position in a buffer.
+use crate::*;
+
+/// Reads values one after the other from a buffer, keeping track of the position.
+///
+/// The position can be saved with `snapshot` & returned to with `restore`, to try reading the
+/// data one way, and then go back & try another.
+///
+/// ```rust
+/// use vartyint::cursor::ReadCursor;
+/// let buf = vartyint::write_many_new(&[300u32, 5]);
+/// let mut cursor = ReadCursor::new(&buf);
+///
+/// let start = cursor.snapshot();
+/// assert_eq!(cursor.read::<u8>(), Err(vartyint::VartyIntError::TooManyBytesForType));
+/// cursor.restore(start);
+/// assert_eq!(cursor.read::<u16>(), Ok(300));
+/// assert_eq!(cursor.position(), 2);
+/// assert_eq!(cursor.read::<u16>(), Ok(5));
+/// assert!(cursor.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadCursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+/// A saved position of a `ReadCursor`, from `ReadCursor::snapshot`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Snapshot(usize);
+
+impl<'a> ReadCursor<'a> {
+    /// A cursor at the start of this buffer
+    pub fn new(buf: &'a [u8]) -> Self {
+        ReadCursor { buf, pos: 0 }
+    }
+
+    /// The number of bytes read so far
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The bytes which haven't been read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// True iff everything has been read
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.buf.len()
+    }
+
+    /// Read the next value. On error, the position is unchanged.
+    pub fn read<T: VarInt>(&mut self) -> Result<T, VartyIntError> {
+        let (val, len) = read_with_len(self.remaining())?;
+        self.pos += len;
+        Ok(val)
+    }
+
+    /// Save the current position, to go back to with `restore`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(self.pos)
+    }
+
+    /// Go back (or forward) to a position from `snapshot`. Snapshots from another cursor, past the
+    /// end of this buffer, move this cursor to the end.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.pos = snapshot.0.min(self.buf.len());
+    }
+
+    /// Call `f` with this cursor. If it returns an error, the cursor is restored to where it was
+    /// before, so nothing `f` read is consumed.
+    ///
+    /// ```rust
+    /// use vartyint::cursor::ReadCursor;
+    /// let buf = vartyint::write_many_new(&[1u32, 2, 300]);
+    /// let mut cursor = ReadCursor::new(&buf);
+    /// // Try to read 3 bytes
+    /// let res = cursor.attempt(|c| Ok::<_, vartyint::VartyIntError>((c.read::<u8>()?, c.read::<u8>()?, c.read::<u8>()?)));
+    /// assert!(res.is_err());
+    /// assert_eq!(cursor.position(), 0);
+    /// ```
+    pub fn attempt<R, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<R, E>) -> Result<R, E> {
+        let snapshot = self.snapshot();
+        f(self).inspect_err(|_| self.restore(snapshot))
+    }
+}
+
+/// Writes values one after the other into a fixed size buffer (e.g. a packet), keeping track of
+/// the position. Integers are written without allocating.
+///
+/// ```rust
+/// use vartyint::cursor::WriteCursor;
+/// let mut frame = [0; 4];
+/// let mut cursor = WriteCursor::new(&mut frame);
+/// assert_eq!(cursor.write(300u32), Ok(2));
+/// assert_eq!(cursor.write(-1i8), Ok(1));
+/// assert_eq!(cursor.write(u64::MAX), Err(vartyint::VartyIntError::BufferFull));
+/// assert_eq!(cursor.position(), 3);
+/// assert_eq!(cursor.written(), &[0xAC, 0x02, 0x01]);
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct WriteCursor<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> WriteCursor<'a> {
+    /// A cursor at the start of this buffer
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        WriteCursor { buf, pos: 0 }
+    }
+
+    /// The number of bytes written so far
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The number of bytes which can still be written
+    pub fn remaining_len(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// True iff the buffer is full
+    pub fn is_full(&self) -> bool {
+        self.pos == self.buf.len()
+    }
+
+    /// The bytes written so far
+    pub fn written(&self) -> &[u8] {
+        &self.buf[..self.pos]
+    }
+
+    /// Write this value, returning the number of bytes written. If it doesn't fit, `BufferFull`
+    /// is returned & the position is unchanged.
+    pub fn write<T: VarInt>(&mut self, val: T) -> Result<usize, VartyIntError> {
+        let len = val
+            .write_varint_slice(&mut self.buf[self.pos..])
+            .ok_or(VartyIntError::BufferFull)?;
+        self.pos += len;
+        Ok(len)
+    }
+
+    /// Write these bytes as they are. If they don't fit, `BufferFull` is returned & nothing is
+    /// written.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), VartyIntError> {
+        self.buf
+            .get_mut(self.pos..self.pos + bytes.len())
+            .ok_or(VartyIntError::BufferFull)?
+            .copy_from_slice(bytes);
+        self.pos += bytes.len();
+        Ok(())
+    }
+
+    /// Save the current position, to go back to with `restore`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(self.pos)
+    }
+
+    /// Go back to a position from `snapshot`, so everything written after it is discarded.
+    /// Snapshots after the current position are ignored.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.pos = snapshot.0.min(self.pos);
+    }
+
+    /// The bytes written, for the rest of the buffer's lifetime
+    pub fn into_written(self) -> &'a mut [u8] {
+        &mut self.buf[..self.pos]
+    }
+}
@@ -0,0 +1,137 @@
+//! Delta encode integers straight into a `std::io::Write`, e.g. a `flate2` or `zstd` encoder, &
+//! read them back from a `std::io::Read`. Delta encoded varints compress well with general
+//! purpose compression.
+//!
+//! ```rust
+//! use vartyint::delta_stream::{DeltaReader, DeltaWriter};
+//! let mut writer = DeltaWriter::new(Vec::new());
+//! writer.extend([1_000_000u64, 1_000_005, 1_000_010]).unwrap();
+//! let out = writer.finish().unwrap();
+//! assert_eq!(out, vartyint::write_many_delta_new(&[1_000_000u64, 1_000_005, 1_000_010]));
+//!
+//! let nums = DeltaReader::<_, u64>::new(&out[..])
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(nums, vec![1_000_000, 1_000_005, 1_000_010]);
+//! ```
+use crate::*;
+use std::io::{Read, Write};
+
+/// Writes the difference between each integer & the previous one (like `write_many_delta`) to
+/// the inner writer, through a `VarIntWriter`
+#[derive(Debug)]
+pub struct DeltaWriter<W: Write, T> {
+    writer: VarIntWriter<W>,
+    last: T,
+}
+
+impl<W, T> DeltaWriter<W, T>
+where
+    W: Write,
+    T: VarInt + std::ops::Sub<T, Output = T>,
+{
+    pub fn new(inner: W) -> Self {
+        DeltaWriter {
+            writer: VarIntWriter::new(inner),
+            last: T::zero(),
+        }
+    }
+
+    /// Write this integer, returning the number of bytes written
+    pub fn push(&mut self, val: T) -> std::io::Result<usize> {
+        let len = self.writer.write_value(val - self.last)?;
+        self.last = val;
+        Ok(len)
+    }
+
+    /// Write all these integers, returning the number of bytes written
+    pub fn extend(&mut self, vals: impl IntoIterator<Item = T>) -> std::io::Result<usize> {
+        let mut len = 0;
+        for val in vals {
+            len += self.push(val)?;
+        }
+        Ok(len)
+    }
+
+    /// Total number of bytes written
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes_written()
+    }
+
+    /// Flush everything, & return the inner writer (e.g. to call `finish` on a compressor)
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.writer.flush()?;
+        self.writer.into_inner()
+    }
+}
+
+/// Reads integers written by `DeltaWriter` (or `write_many_delta`) from the inner reader, through
+/// a `VarIntReader`. Stops after the first error.
+#[derive(Debug)]
+pub struct DeltaReader<R: Read, T> {
+    reader: VarIntReader<R>,
+    last: T,
+    failed: bool,
+}
+
+impl<R, T> DeltaReader<R, T>
+where
+    R: Read,
+    T: DeltaInt,
+{
+    pub fn new(inner: R) -> Self {
+        DeltaReader {
+            reader: VarIntReader::new(inner),
+            last: T::zero(),
+            failed: false,
+        }
+    }
+
+    /// Number of bytes read so far
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Return the inner reader. Any buffered bytes are lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R, T> Iterator for DeltaReader<R, T>
+where
+    R: Read,
+    T: DeltaInt,
+{
+    type Item = Result<T, VartyIntReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = match self.reader.read_value::<T>() {
+            Ok(delta) => self
+                .last
+                .checked_add_delta(delta?)
+                .ok_or(VartyIntError::DeltaOverflow.into()),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(last) => {
+                self.last = last;
+                Some(Ok(last))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R, T> std::iter::FusedIterator for DeltaReader<R, T>
+where
+    R: Read,
+    T: DeltaInt,
+{
+}
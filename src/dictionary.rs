@@ -0,0 +1,122 @@
+//! Dictionary encoding, for columns with only a few different values (e.g. enum-like codes).
+//! Each unique value is written once, & the column is written as (small) varint indexes into
+//! that dictionary. If that wouldn't be smaller, the values are written as they are.
+//!
+//! ```rust
+//! use vartyint::dictionary::{self, Encoding};
+//! let statuses = [200u32, 200, 404, 200, 301, 404, 200, 200];
+//! let mut buf = Vec::new();
+//! dictionary::write_dictionary(&statuses, &mut buf);
+//! assert_eq!(dictionary::encoding(&buf), Ok(Encoding::Dictionary));
+//! assert_eq!(buf.len(), 17);
+//!
+//! let (decoded, rest) = dictionary::read_dictionary::<u32>(&buf).unwrap();
+//! assert_eq!(decoded, statuses);
+//! assert!(rest.is_empty());
+//! ```
+//!
+//! The data starts with a tag byte: `0` for plain (then the number of values, & the values) or
+//! `1` for dictionary (then the number of unique values, the unique values, the number of
+//! values, & the index of each value).
+use crate::*;
+use std::collections::HashMap;
+
+/// How the values were written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Plain,
+    Dictionary,
+}
+
+impl Encoding {
+    fn tag(self) -> u8 {
+        match self {
+            Encoding::Plain => 0,
+            Encoding::Dictionary => 1,
+        }
+    }
+}
+
+/// The `Encoding` of data written by `write_dictionary`
+pub fn encoding(buf: &[u8]) -> Result<Encoding, VartyIntError> {
+    match buf.first() {
+        None => Err(VartyIntError::EmptyBuffer),
+        Some(0) => Ok(Encoding::Plain),
+        Some(1) => Ok(Encoding::Dictionary),
+        Some(_) => Err(VartyIntError::UnknownDiscriminant),
+    }
+}
+
+/// Write these values in the dictionary encoding, if that's smaller, else plainly. Returns the
+/// number of bytes written.
+pub fn write_dictionary<T>(nums: &[T], buf: &mut Vec<u8>) -> usize
+where
+    T: VarInt + Eq + std::hash::Hash,
+{
+    let start = buf.len();
+    let mut plain = Vec::new();
+    nums.len().write_varint(&mut plain);
+    write_many(nums, &mut plain);
+
+    if let Some(dict) = dictionary_encode(nums, plain.len()) {
+        buf.push(Encoding::Dictionary.tag());
+        buf.extend_from_slice(&dict);
+    } else {
+        buf.push(Encoding::Plain.tag());
+        buf.extend_from_slice(&plain);
+    }
+    buf.len() - start
+}
+
+/// The dictionary encoding of `nums`, or `None` if it's not shorter than `plain_len`. Gives up
+/// early when there are too many unique values.
+fn dictionary_encode<T>(nums: &[T], plain_len: usize) -> Option<Vec<u8>>
+where
+    T: VarInt + Eq + std::hash::Hash,
+{
+    let mut dict = Vec::new();
+    let mut indexes: HashMap<T, usize> = HashMap::new();
+    let mut idxs = Vec::with_capacity(nums.len());
+    for num in nums {
+        let next = dict.len();
+        let idx = *indexes.entry(*num).or_insert(next);
+        if idx == next {
+            dict.push(*num);
+            if dict.len() > nums.len() / 2 {
+                return None;
+            }
+        }
+        idxs.push(idx);
+    }
+
+    let mut buf = Vec::new();
+    dict.len().write_varint(&mut buf);
+    write_many(&dict, &mut buf);
+    idxs.len().write_varint(&mut buf);
+    write_many(&idxs, &mut buf);
+    (buf.len() < plain_len).then_some(buf)
+}
+
+/// Read values written by `write_dictionary`, in either encoding, returning them & the rest of
+/// the buffer. An index which isn't in the dictionary is `OutOfRange`.
+pub fn read_dictionary<T: VarInt>(buf: &[u8]) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+    let encoding = encoding(buf)?;
+    let buf = &buf[1..];
+    match encoding {
+        Encoding::Plain => {
+            let (num, rest) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+            read_many_exact(rest, num)
+        }
+        Encoding::Dictionary => {
+            let (dict_len, rest) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+            let (dict, rest) = read_many_exact::<T>(rest, dict_len)?;
+            let (num, rest) = usize::read_varint(rest).map_err(empty_to_not_enough::<usize>)?;
+            let (idxs, rest) = read_many_exact::<usize>(rest, num)?;
+            let nums = idxs
+                .into_iter()
+                .map(|idx| dict.get(idx).copied().ok_or(VartyIntError::OutOfRange))
+                .collect::<Result<Vec<T>, _>>()?;
+            Ok((nums, rest))
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Integers whose type is only known at run time, e.g. from a schema or column type.
+//!
+//! `DynCodec` is object safe, so different codecs can be stored as `Box<dyn DynCodec>`.
+//!
+//! ```rust
+//! use vartyint::dynamic::{DeltaCodec, DynCodec, Value, VarIntType};
+//! let columns: Vec<Box<dyn DynCodec>> = vec![
+//!     Box::new(VarIntType::U8),
+//!     Box::new(DeltaCodec(VarIntType::I64)),
+//! ];
+//! let data = [
+//!     vec![Value::U8(1), Value::U8(2)],
+//!     vec![Value::I64(1_000), Value::I64(1_001)],
+//! ];
+//! for (codec, values) in columns.iter().zip(data.iter()) {
+//!     let mut buf = Vec::new();
+//!     codec.write_values(values, &mut buf).unwrap();
+//!     assert_eq!(&codec.read_values(&buf).unwrap(), values);
+//! }
+//! ```
+use crate::*;
+
+macro_rules! dynamic_types {
+    ( $( $variant:ident $type:ty = $tag:expr ),* ) => {
+        /// The type of an integer
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        pub enum VarIntType {
+            $( $variant = $tag, )*
+        }
+
+        /// An integer of any type
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Value {
+            $( $variant($type), )*
+        }
+
+        $(
+            impl From<$type> for Value {
+                fn from(val: $type) -> Self {
+                    Value::$variant(val)
+                }
+            }
+        )*
+
+        impl TryFrom<u8> for VarIntType {
+            type Error = VartyIntError;
+
+            /// The type with this tag (i.e. `VarIntType as u8`)
+            fn try_from(tag: u8) -> Result<Self, VartyIntError> {
+                match tag {
+                    $( $tag => Ok(VarIntType::$variant), )*
+                    _ => Err(VartyIntError::UnknownDiscriminant),
+                }
+            }
+        }
+
+        impl VarIntType {
+            /// Every type
+            pub const ALL: &'static [VarIntType] = &[ $( VarIntType::$variant, )* ];
+
+            /// The Rust name of this type, e.g. `"u32"`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( VarIntType::$variant => stringify!($type), )*
+                }
+            }
+
+            /// The most bytes one value of this type can be written as
+            pub fn max_len(&self) -> usize {
+                match self {
+                    $( VarIntType::$variant => <$type>::MAX_LEN, )*
+                }
+            }
+
+            /// The type with this Rust name, e.g. `"u32"`
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $( stringify!($type) => Some(VarIntType::$variant), )*
+                    _ => None,
+                }
+            }
+        }
+
+        impl Value {
+            /// The type of this value
+            pub fn var_int_type(&self) -> VarIntType {
+                match self {
+                    $( Value::$variant(_) => VarIntType::$variant, )*
+                }
+            }
+
+            /// Write this value to the buffer, returning the number of bytes written.
+            pub fn write(&self, buf: &mut Vec<u8>) -> usize {
+                match self {
+                    $( Value::$variant(val) => val.write_varint(buf), )*
+                }
+            }
+
+            /// Read a value of this type from the buffer
+            pub fn read(var_int_type: VarIntType, buf: &[u8]) -> Result<(Value, &[u8]), VartyIntError> {
+                match var_int_type {
+                    $( VarIntType::$variant => {
+                        let (val, rest) = <$type>::read_varint(buf)?;
+                        Ok((Value::$variant(val), rest))
+                    } )*
+                }
+            }
+
+            /// Write this value like `Raw`, i.e. signed values without zigzag encoding
+            pub(crate) fn write_raw(&self, buf: &mut Vec<u8>) -> usize {
+                match self {
+                    $( Value::$variant(val) => Raw(*val).write_varint(buf), )*
+                }
+            }
+
+            /// Read a value written with `write_raw`
+            pub(crate) fn read_raw(var_int_type: VarIntType, buf: &[u8]) -> Result<(Value, &[u8]), VartyIntError> {
+                match var_int_type {
+                    $( VarIntType::$variant => {
+                        let (val, rest) = Raw::<$type>::read_varint(buf)?;
+                        Ok((Value::$variant(val.0), rest))
+                    } )*
+                }
+            }
+
+            /// `self - other`, wrapping on overflow. Both must be the same type.
+            pub(crate) fn wrapping_sub(&self, other: &Value) -> Result<Value, VartyIntError> {
+                match (self, other) {
+                    $( (Value::$variant(a), Value::$variant(b)) => Ok(Value::$variant(a.wrapping_sub(*b))), )*
+                    _ => Err(VartyIntError::TypeMismatch),
+                }
+            }
+
+            /// `self + other`, wrapping on overflow. Both must be the same type.
+            pub(crate) fn wrapping_add(&self, other: &Value) -> Result<Value, VartyIntError> {
+                match (self, other) {
+                    $( (Value::$variant(a), Value::$variant(b)) => Ok(Value::$variant(a.wrapping_add(*b))), )*
+                    _ => Err(VartyIntError::TypeMismatch),
+                }
+            }
+
+            /// Zero of this type
+            pub(crate) fn zero(var_int_type: VarIntType) -> Value {
+                match var_int_type {
+                    $( VarIntType::$variant => Value::$variant(0), )*
+                }
+            }
+        }
+    };
+}
+
+dynamic_types!(
+    U8 u8 = 0,
+    U16 u16 = 1,
+    U32 u32 = 2,
+    U64 u64 = 3,
+    U128 u128 = 4,
+    I8 i8 = 5,
+    I16 i16 = 6,
+    I32 i32 = 7,
+    I64 i64 = 8,
+    I128 i128 = 9
+);
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Value::U8(v) => write!(fmt, "{}", v),
+            Value::U16(v) => write!(fmt, "{}", v),
+            Value::U32(v) => write!(fmt, "{}", v),
+            Value::U64(v) => write!(fmt, "{}", v),
+            Value::U128(v) => write!(fmt, "{}", v),
+            Value::I8(v) => write!(fmt, "{}", v),
+            Value::I16(v) => write!(fmt, "{}", v),
+            Value::I32(v) => write!(fmt, "{}", v),
+            Value::I64(v) => write!(fmt, "{}", v),
+            Value::I128(v) => write!(fmt, "{}", v),
+        }
+    }
+}
+
+/// Writes & reads `Value`s of one type. Writing a value of another type returns `TypeMismatch`.
+pub trait DynCodec: std::fmt::Debug + Send + Sync {
+    /// The type of values
+    fn var_int_type(&self) -> VarIntType;
+
+    /// Write one value, returning the number of bytes written
+    fn write_value(&self, val: &Value, buf: &mut Vec<u8>) -> Result<usize, VartyIntError> {
+        if val.var_int_type() != self.var_int_type() {
+            return Err(VartyIntError::TypeMismatch);
+        }
+        Ok(val.write(buf))
+    }
+
+    /// Read one value
+    fn read_value<'a>(&self, buf: &'a [u8]) -> Result<(Value, &'a [u8]), VartyIntError> {
+        Value::read(self.var_int_type(), buf)
+    }
+
+    /// Write many values, returning the number of bytes written. If any value is the wrong type,
+    /// `TypeMismatch` is returned & nothing is written.
+    fn write_values(&self, vals: &[Value], buf: &mut Vec<u8>) -> Result<usize, VartyIntError>;
+
+    /// Read all the values in the buffer, written by `write_values`
+    fn read_values(&self, buf: &[u8]) -> Result<Vec<Value>, VartyIntError>;
+}
+
+/// Each value is written one after the other
+impl DynCodec for VarIntType {
+    fn var_int_type(&self) -> VarIntType {
+        *self
+    }
+
+    fn write_values(&self, vals: &[Value], buf: &mut Vec<u8>) -> Result<usize, VartyIntError> {
+        if vals.iter().any(|v| v.var_int_type() != *self) {
+            return Err(VartyIntError::TypeMismatch);
+        }
+        Ok(vals.iter().map(|v| v.write(buf)).sum())
+    }
+
+    fn read_values(&self, mut buf: &[u8]) -> Result<Vec<Value>, VartyIntError> {
+        let mut vals = Vec::new();
+        while !buf.is_empty() {
+            let (val, rest) = Value::read(*self, buf)?;
+            vals.push(val);
+            buf = rest;
+        }
+        Ok(vals)
+    }
+}
+
+/// Values are delta encoded, like `write_many_delta`. Differences wrap on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeltaCodec(pub VarIntType);
+
+impl DynCodec for DeltaCodec {
+    fn var_int_type(&self) -> VarIntType {
+        self.0
+    }
+
+    fn write_values(&self, vals: &[Value], buf: &mut Vec<u8>) -> Result<usize, VartyIntError> {
+        if vals.iter().any(|v| v.var_int_type() != self.0) {
+            return Err(VartyIntError::TypeMismatch);
+        }
+        let mut last = Value::zero(self.0);
+        let mut len = 0;
+        for val in vals {
+            len += val.wrapping_sub(&last)?.write(buf);
+            last = *val;
+        }
+        Ok(len)
+    }
+
+    fn read_values(&self, buf: &[u8]) -> Result<Vec<Value>, VartyIntError> {
+        let mut last = Value::zero(self.0);
+        self.0
+            .read_values(buf)?
+            .into_iter()
+            .map(|delta| {
+                last = last.wrapping_add(&delta)?;
+                Ok(last)
+            })
+            .collect()
+    }
+}
+
+/// Write this value, prefixed with a one byte type tag (`VarIntType as u8`), so it can be read
+/// without knowing its type. Returns the number of bytes written.
+///
+/// ```rust
+/// use vartyint::dynamic::{read_tagged, write_tagged, Value};
+/// let mut buf = Vec::new();
+/// write_tagged(&Value::I32(-1), &mut buf);
+/// write_tagged(&Value::U128(300), &mut buf);
+/// assert_eq!(buf, vec![7, 1, 4, 0xAC, 0x02]);
+///
+/// let vals = read_tagged(&buf).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(vals, vec![Value::I32(-1), Value::U128(300)]);
+/// ```
+pub fn write_tagged(val: &Value, buf: &mut Vec<u8>) -> usize {
+    buf.push(val.var_int_type() as u8);
+    1 + val.write(buf)
+}
+
+/// Write many values with `write_tagged`, returning the number of bytes written.
+pub fn write_many_tagged<'a>(
+    vals: impl IntoIterator<Item = &'a Value>,
+    buf: &mut Vec<u8>,
+) -> usize {
+    vals.into_iter().map(|val| write_tagged(val, buf)).sum()
+}
+
+/// Read one value written with `write_tagged`
+pub fn read_one_tagged(buf: &[u8]) -> Result<(Value, &[u8]), VartyIntError> {
+    let (tag, rest) = buf.split_first().ok_or(VartyIntError::EmptyBuffer)?;
+    let var_int_type = VarIntType::try_from(*tag)?;
+    Value::read(var_int_type, rest).map_err(|e| match e {
+        VartyIntError::EmptyBuffer => VartyIntError::not_enough(0, var_int_type.max_len()),
+        e => e,
+    })
+}
+
+/// Iterate over all the values in this buffer, written with `write_tagged`. Stops after the first
+/// error.
+pub fn read_tagged(buf: &[u8]) -> impl Iterator<Item = Result<Value, VartyIntError>> + '_ {
+    let mut buf = buf;
+    std::iter::from_fn(move || {
+        if buf.is_empty() {
+            return None;
+        }
+        match read_one_tagged(buf) {
+            Err(e) => {
+                buf = &[];
+                Some(Err(e))
+            }
+            Ok((val, newbuf)) => {
+                buf = newbuf;
+                Some(Ok(val))
+            }
+        }
+    })
+}
@@ -0,0 +1,183 @@
+//! C functions, so other languages can read & write the same bytes as this crate. Enable with the
+//! `ffi` feature. The header can be made with `cbindgen`, and a library with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or `cdylib`).
+//!
+//! Every function returns a number of bytes or values (0 or more) on success, or a negative
+//! `VARTYINT_ERR_*` code.
+//!
+//! ```c
+//! uint8_t buf[10];
+//! intptr_t len = vartyint_encode_u64(300, buf, sizeof(buf));  // 2
+//! uint64_t val;
+//! intptr_t read = vartyint_decode_u64(buf, len, &val);       // 2, & val is 300
+//! ```
+use crate::*;
+
+/// The input is empty
+pub const VARTYINT_ERR_EMPTY_BUFFER: isize = -1;
+/// The input ends part way through a value
+pub const VARTYINT_ERR_NOT_ENOUGH_BYTES: isize = -2;
+/// The value is too big for the type
+pub const VARTYINT_ERR_TOO_MANY_BYTES_FOR_TYPE: isize = -3;
+/// The output is too small
+pub const VARTYINT_ERR_OUTPUT_TOO_SMALL: isize = -4;
+/// A pointer was `NULL`
+pub const VARTYINT_ERR_NULL: isize = -5;
+/// Any other error
+pub const VARTYINT_ERR_OTHER: isize = -100;
+
+fn error_code(error: VartyIntError) -> isize {
+    match error {
+        VartyIntError::EmptyBuffer => VARTYINT_ERR_EMPTY_BUFFER,
+        VartyIntError::NotEnoughBytes { .. } => VARTYINT_ERR_NOT_ENOUGH_BYTES,
+        VartyIntError::TooManyBytesForType => VARTYINT_ERR_TOO_MANY_BYTES_FOR_TYPE,
+        VartyIntError::DoesNotFit | VartyIntError::BufferFull => VARTYINT_ERR_OUTPUT_TOO_SMALL,
+        _ => VARTYINT_ERR_OTHER,
+    }
+}
+
+/// Turn a pointer & length into a slice. `NULL` is allowed if the length is 0.
+unsafe fn slice<'a, T>(ptr: *const T, len: usize) -> Option<&'a [T]> {
+    if len == 0 {
+        Some(&[])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Turn a pointer & length into a mutable slice. `NULL` is allowed if the length is 0.
+unsafe fn slice_mut<'a, T>(ptr: *mut T, len: usize) -> Option<&'a mut [T]> {
+    if len == 0 {
+        Some(&mut [])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts_mut(ptr, len))
+    }
+}
+
+/// Copy these bytes to the output, returning the length, or an error if it doesn't fit
+fn copy_out(bytes: &[u8], out: &mut [u8]) -> isize {
+    if bytes.len() > out.len() {
+        return VARTYINT_ERR_OUTPUT_TOO_SMALL;
+    }
+    out[..bytes.len()].copy_from_slice(bytes);
+    bytes.len() as isize
+}
+
+macro_rules! ffi_impl {
+    ( $type:ty, $encode:ident, $decode:ident, $encode_array:ident, $decode_array:ident ) => {
+        /// Write one value to `out` (which has room for `out_cap` bytes). Returns the number of
+        /// bytes written.
+        ///
+        /// # Safety
+        /// `out` must point to `out_cap` writable bytes.
+        #[no_mangle]
+        pub unsafe extern "C" fn $encode(val: $type, out: *mut u8, out_cap: usize) -> isize {
+            match slice_mut(out, out_cap) {
+                None => VARTYINT_ERR_NULL,
+                Some(out) => copy_out(&val.as_varint(), out),
+            }
+        }
+
+        /// Read one value from `buf` (which is `len` bytes) into `out`. Returns the number of bytes
+        /// read.
+        ///
+        /// # Safety
+        /// `buf` must point to `len` readable bytes, and `out` to one writable value.
+        #[no_mangle]
+        pub unsafe extern "C" fn $decode(buf: *const u8, len: usize, out: *mut $type) -> isize {
+            let buf = match slice(buf, len) {
+                None => return VARTYINT_ERR_NULL,
+                Some(buf) => buf,
+            };
+            if out.is_null() {
+                return VARTYINT_ERR_NULL;
+            }
+            match <$type>::read_varint(buf) {
+                Err(e) => error_code(e),
+                Ok((val, rest)) => {
+                    *out = val;
+                    (len - rest.len()) as isize
+                }
+            }
+        }
+
+        /// Write `num_vals` values to `out` (which has room for `out_cap` bytes), one after the
+        /// other. Returns the number of bytes written.
+        ///
+        /// # Safety
+        /// `vals` must point to `num_vals` readable values, and `out` to `out_cap` writable bytes.
+        #[no_mangle]
+        pub unsafe extern "C" fn $encode_array(
+            vals: *const $type,
+            num_vals: usize,
+            out: *mut u8,
+            out_cap: usize,
+        ) -> isize {
+            match (slice(vals, num_vals), slice_mut(out, out_cap)) {
+                (Some(vals), Some(out)) => copy_out(&write_many_new(vals), out),
+                _ => VARTYINT_ERR_NULL,
+            }
+        }
+
+        /// Read all the values in `buf` (which is `len` bytes) into `out` (which has room for
+        /// `out_cap` values). Returns the number of values read.
+        ///
+        /// # Safety
+        /// `buf` must point to `len` readable bytes, and `out` to `out_cap` writable values.
+        #[no_mangle]
+        pub unsafe extern "C" fn $decode_array(
+            buf: *const u8,
+            len: usize,
+            out: *mut $type,
+            out_cap: usize,
+        ) -> isize {
+            let (buf, out) = match (slice(buf, len), slice_mut(out, out_cap)) {
+                (Some(buf), Some(out)) => (buf, out),
+                _ => return VARTYINT_ERR_NULL,
+            };
+            let mut count = 0;
+            let result = try_for_each_varint(buf, |val: $type| {
+                *out.get_mut(count).ok_or(VartyIntError::DoesNotFit)? = val;
+                count += 1;
+                Ok(())
+            });
+            match result {
+                Ok(()) => count as isize,
+                Err(e) => error_code(e),
+            }
+        }
+    };
+}
+
+ffi_impl!(
+    u32,
+    vartyint_encode_u32,
+    vartyint_decode_u32,
+    vartyint_encode_u32_array,
+    vartyint_decode_u32_array
+);
+ffi_impl!(
+    u64,
+    vartyint_encode_u64,
+    vartyint_decode_u64,
+    vartyint_encode_u64_array,
+    vartyint_decode_u64_array
+);
+ffi_impl!(
+    i32,
+    vartyint_encode_i32,
+    vartyint_decode_i32,
+    vartyint_encode_i32_array,
+    vartyint_decode_i32_array
+);
+ffi_impl!(
+    i64,
+    vartyint_encode_i64,
+    vartyint_decode_i64,
+    vartyint_encode_i64_array,
+    vartyint_decode_i64_array
+);
@@ -0,0 +1,109 @@
+//! Floats as fixed point decimals: multiplied by a scale (e.g. `100.` for cents, `1e7` for
+//! coordinates), rounded to an integer, & written as a varint.
+//!
+//! ```rust
+//! use vartyint::fixed_point;
+//! let prices = [19.99, 20.49, 18.75];
+//! let mut buf = Vec::new();
+//! fixed_point::write_many_delta(&prices, 100., &mut buf).unwrap();
+//! assert_eq!(buf, vartyint::write_many_delta_new(&[1_999i64, 2_049, 1_875]));
+//! assert_eq!(fixed_point::read_many_delta(&buf, 100.).unwrap(), prices);
+//!
+//! // Too big
+//! assert_eq!(
+//!     fixed_point::quantize(1e300, 100.),
+//!     Err(vartyint::VartyIntError::OutOfRange)
+//! );
+//! ```
+use crate::*;
+
+/// `val * scale`, rounded to the nearest integer. If it's not finite, or doesn't fit in an `i64`,
+/// `OutOfRange` is returned.
+pub fn quantize(val: f64, scale: f64) -> Result<i64, VartyIntError> {
+    let scaled = (val * scale).round();
+    // i64::MIN is exactly -2⁶³ as an f64, and 2⁶³ is the first value which doesn't fit
+    if scaled.is_finite() && scaled >= i64::MIN as f64 && scaled < -(i64::MIN as f64) {
+        Ok(scaled as i64)
+    } else {
+        Err(VartyIntError::OutOfRange)
+    }
+}
+
+/// The float for this quantized value, i.e. `val / scale`
+pub fn dequantize(val: i64, scale: f64) -> f64 {
+    val as f64 / scale
+}
+
+/// Write this float, quantized with `scale`, to the end of `buf`, returning the number of bytes
+/// written. If it can't be quantized, `OutOfRange` is returned & nothing is written.
+pub fn write(val: f64, scale: f64, buf: &mut Vec<u8>) -> Result<usize, VartyIntError> {
+    Ok(quantize(val, scale)?.write_varint(buf))
+}
+
+/// Read one float from the start of this buffer, returning it & the rest of the buffer.
+pub fn read(buf: &[u8], scale: f64) -> Result<(f64, &[u8]), VartyIntError> {
+    let (val, rest) = i64::read_varint(buf)?;
+    Ok((dequantize(val, scale), rest))
+}
+
+/// Write these floats, quantized with `scale`, to the end of `buf`, returning the number of bytes
+/// written. If any value can't be quantized, `OutOfRange` is returned & `buf` is unchanged.
+pub fn write_many(vals: &[f64], scale: f64, buf: &mut Vec<u8>) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    for val in vals {
+        if let Err(e) = write(*val, scale, buf) {
+            buf.truncate(start);
+            return Err(e);
+        }
+    }
+    Ok(buf.len() - start)
+}
+
+/// Read all the floats in this buffer, written by `write_many` with the same `scale`.
+pub fn read_many(buf: &[u8], scale: f64) -> Result<Vec<f64>, VartyIntError> {
+    crate::read_many::<i64>(buf)
+        .map(|val| val.map(|val| dequantize(val, scale)))
+        .collect()
+}
+
+/// Write these floats, quantized with `scale` & delta encoded (like `write_many_delta`), to the end
+/// of `buf`, returning the number of bytes written. If any value can't be quantized, or the
+/// difference between 2 values doesn't fit in an `i64`, `OutOfRange` is returned & `buf` is
+/// unchanged.
+pub fn write_many_delta(
+    vals: &[f64],
+    scale: f64,
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    let mut last = 0i64;
+    for val in vals {
+        let delta = quantize(*val, scale).and_then(|val| {
+            let delta = val.checked_sub(last).ok_or(VartyIntError::OutOfRange);
+            last = val;
+            delta
+        });
+        match delta {
+            Ok(delta) => delta.write_varint(buf),
+            Err(e) => {
+                buf.truncate(start);
+                return Err(e);
+            }
+        };
+    }
+    Ok(buf.len() - start)
+}
+
+/// Read all the floats in this buffer, written by `write_many_delta` with the same `scale`. If a
+/// value doesn't fit in an `i64`, `OutOfRange` is returned.
+pub fn read_many_delta(mut buf: &[u8], scale: f64) -> Result<Vec<f64>, VartyIntError> {
+    let mut vals = Vec::new();
+    let mut last = 0i64;
+    while !buf.is_empty() {
+        let (delta, rest) = i64::read_varint(buf)?;
+        last = last.checked_add(delta).ok_or(VartyIntError::OutOfRange)?;
+        vals.push(dequantize(last, scale));
+        buf = rest;
+    }
+    Ok(vals)
+}
@@ -0,0 +1,216 @@
+//! Write & read geographic coordinates.
+//!
+//! Coordinates are rounded to a fixed precision (e.g. `1e-7` degrees, like OpenStreetMap), and the
+//! latitudes & longitudes are delta encoded as 2 separate streams. Points near each other take
+//! up very little space.
+//!
+//! ```rust
+//! use vartyint::geo;
+//! let points = [(53.3498, -6.2603), (53.3499, -6.2602), (53.3501, -6.2600)];
+//! let mut buf = Vec::new();
+//! geo::write_coords(&points, geo::DEFAULT_PRECISION, &mut buf).unwrap();
+//!
+//! let (decoded, rest) = geo::read_coords(&buf, geo::DEFAULT_PRECISION).unwrap();
+//! assert!(rest.is_empty());
+//! for (p, d) in points.iter().zip(decoded.iter()) {
+//!     assert!((p.0 - d.0).abs() < 1e-7 && (p.1 - d.1).abs() < 1e-7);
+//! }
+//! ```
+use crate::*;
+
+/// A (latitude, longitude) pair, in degrees
+pub type LatLon = (f64, f64);
+
+/// An (x, y) point, for Morton codes
+pub type XY = (u32, u32);
+
+/// 1e-7 degrees, the precision OpenStreetMap uses (about 1 cm)
+pub const DEFAULT_PRECISION: f64 = 1e-7;
+
+/// Round this coordinate to an integer number of `precision` units. If it's not finite, or
+/// doesn't fit in an `i64`, `OutOfRange` is returned.
+fn quantize(val: f64, precision: f64) -> Result<i64, VartyIntError> {
+    fixed_point::quantize(val, 1. / precision)
+}
+
+fn dequantize(val: i64, precision: f64) -> f64 {
+    fixed_point::dequantize(val, 1. / precision)
+}
+
+/// Write these (latitude, longitude) pairs, rounded to `precision` degrees, adding to the end of
+/// the buf Vec, and returning the number of bytes written. The number of points is written first,
+/// then the delta encoded latitudes, then the delta encoded longitudes.
+///
+/// If a coordinate can't be rounded (e.g. it's NaN or too big), `OutOfRange` is returned, and if
+/// the difference between 2 doesn't fit in an `i64`, `DeltaOverflow` is. `buf` is unchanged.
+pub fn write_coords(
+    coords: &[LatLon],
+    precision: f64,
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let lats = coords
+        .iter()
+        .map(|c| quantize(c.0, precision))
+        .collect::<Result<Vec<_>, _>>()?;
+    let lons = coords
+        .iter()
+        .map(|c| quantize(c.1, precision))
+        .collect::<Result<Vec<_>, _>>()?;
+    let start = buf.len();
+    write_usize(coords.len(), buf);
+    if let Err(e) = write_deltas(&lats, buf).and_then(|_| write_deltas(&lons, buf)) {
+        buf.truncate(start);
+        return Err(e);
+    }
+    Ok(buf.len() - start)
+}
+
+/// Read (latitude, longitude) pairs written by `write_coords`, returning them & the rest of the
+/// buffer. If a coordinate doesn't fit in an `i64`, `DeltaOverflow` is returned.
+pub fn read_coords(buf: &[u8], precision: f64) -> Result<(Vec<LatLon>, &[u8]), VartyIntError> {
+    let (len, buf) = read_usize(buf)?;
+    let (lats, buf) = read_many_delta_exact(buf, len)?;
+    let (lons, buf) = read_many_delta_exact(buf, len)?;
+    let coords = lats
+        .into_iter()
+        .zip(lons)
+        .map(|(lat, lon)| (dequantize(lat, precision), dequantize(lon, precision)))
+        .collect();
+    Ok((coords, buf))
+}
+
+/// Spread the bits of this out, so there is a 0 bit after each bit
+fn spread_bits(val: u32) -> u64 {
+    let mut val = val as u64;
+    val = (val | (val << 16)) & 0x0000_FFFF_0000_FFFF;
+    val = (val | (val << 8)) & 0x00FF_00FF_00FF_00FF;
+    val = (val | (val << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    val = (val | (val << 2)) & 0x3333_3333_3333_3333;
+    val = (val | (val << 1)) & 0x5555_5555_5555_5555;
+    val
+}
+
+/// Opposite of `spread_bits`, takes every 2nd bit
+fn compact_bits(val: u64) -> u32 {
+    let mut val = val & 0x5555_5555_5555_5555;
+    val = (val | (val >> 1)) & 0x3333_3333_3333_3333;
+    val = (val | (val >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    val = (val | (val >> 4)) & 0x00FF_00FF_00FF_00FF;
+    val = (val | (val >> 8)) & 0x0000_FFFF_0000_FFFF;
+    val = (val | (val >> 16)) & 0x0000_0000_FFFF_FFFF;
+    val as u32
+}
+
+/// The Morton code (Z-order) of this point, i.e. the bits of `x` & `y` interleaved. Points near
+/// each other usually have close Morton codes.
+///
+/// ```rust
+/// use vartyint::geo::{morton_decode, morton_encode};
+/// assert_eq!(morton_encode(0b11, 0b01), 0b0111);
+/// assert_eq!(morton_decode(0b0111), (0b11, 0b01));
+/// ```
+pub fn morton_encode(x: u32, y: u32) -> u64 {
+    spread_bits(x) | (spread_bits(y) << 1)
+}
+
+/// The (x, y) point of this Morton code
+pub fn morton_decode(code: u64) -> (u32, u32) {
+    (compact_bits(code), compact_bits(code >> 1))
+}
+
+/// Write these points as Morton codes, delta encoded, adding to the end of the buf Vec. The
+/// number of points is written first. The order is kept, but sorting the points by Morton code
+/// first makes the output much smaller. Returns the number of bytes written.
+///
+/// ```rust
+/// use vartyint::geo;
+/// let points = [(1_000_000, 2_000_000), (1_000_001, 2_000_000), (1_000_001, 2_000_001)];
+/// let mut buf = Vec::new();
+/// assert_eq!(geo::write_morton(&points, &mut buf), 1 + 7 + 1 + 1);
+/// assert_eq!(buf.len(), 1 + 7 + 1 + 1);
+/// assert_eq!(geo::read_morton(&buf).unwrap().0, points);
+/// ```
+pub fn write_morton(points: &[XY], buf: &mut Vec<u8>) -> usize {
+    let mut len = write_usize(points.len(), buf);
+    let mut last = 0u64;
+    for (x, y) in points {
+        let code = morton_encode(*x, *y);
+        // Wrapping, so points can go "backwards"
+        len += write_i64(code.wrapping_sub(last) as i64, buf);
+        last = code;
+    }
+    len
+}
+
+/// Read points written by `write_morton`, returning them & the rest of the buffer.
+pub fn read_morton(buf: &[u8]) -> Result<(Vec<XY>, &[u8]), VartyIntError> {
+    let (len, buf) = read_usize(buf)?;
+    let (deltas, buf) = read_many_exact::<i64>(buf, len)?;
+    let mut last = 0u64;
+    let points = deltas
+        .into_iter()
+        .map(|delta| {
+            last = last.wrapping_add(delta as u64);
+            morton_decode(last)
+        })
+        .collect();
+    Ok((points, buf))
+}
+
+/// Round this coordinate to `precision` units, as a `u32` which has the same order. If it doesn't
+/// fit in an `i32`, `OutOfRange` is returned.
+fn quantize_u32(val: f64, precision: f64) -> Result<u32, VartyIntError> {
+    let val = i32::try_from(quantize(val, precision)?).map_err(|_| VartyIntError::OutOfRange)?;
+    Ok((val as u32) ^ 0x8000_0000)
+}
+
+fn dequantize_u32(val: u32, precision: f64) -> f64 {
+    dequantize((val ^ 0x8000_0000) as i32 as i64, precision)
+}
+
+/// Like `write_coords`, but the coordinates are written with `write_morton`. The rounded
+/// coordinates must fit in an `i32`, which they do for a precision of `1e-7` or more, otherwise
+/// `OutOfRange` is returned & `buf` is unchanged. Returns the number of bytes written.
+///
+/// ```rust
+/// use vartyint::geo;
+/// let points = [(53.3498, -6.2603), (53.3499, -6.2602)];
+/// let mut buf = Vec::new();
+/// geo::write_coords_morton(&points, geo::DEFAULT_PRECISION, &mut buf).unwrap();
+/// let (decoded, _) = geo::read_coords_morton(&buf, geo::DEFAULT_PRECISION).unwrap();
+/// for (p, d) in points.iter().zip(decoded.iter()) {
+///     assert!((p.0 - d.0).abs() < 1e-7 && (p.1 - d.1).abs() < 1e-7);
+/// }
+/// ```
+pub fn write_coords_morton(
+    coords: &[LatLon],
+    precision: f64,
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let points = coords
+        .iter()
+        .map(|(lat, lon)| {
+            Ok((
+                quantize_u32(*lon, precision)?,
+                quantize_u32(*lat, precision)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, VartyIntError>>()?;
+    let start = buf.len();
+    write_morton(&points, buf);
+    Ok(buf.len() - start)
+}
+
+/// Read (latitude, longitude) pairs written by `write_coords_morton`, returning them & the rest
+/// of the buffer.
+pub fn read_coords_morton(
+    buf: &[u8],
+    precision: f64,
+) -> Result<(Vec<LatLon>, &[u8]), VartyIntError> {
+    let (points, buf) = read_morton(buf)?;
+    let coords = points
+        .into_iter()
+        .map(|(x, y)| (dequantize_u32(y, precision), dequantize_u32(x, precision)))
+        .collect();
+    Ok((coords, buf))
+}
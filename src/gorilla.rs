@@ -0,0 +1,116 @@
+//! The timestamp compression from Facebook's [Gorilla](https://www.vldb.org/pvldb/vol8/p1816-teller.pdf)
+//! time series database.
+//!
+//! The "delta of delta" (the change in the difference between values) is written with a variable
+//! number of bits. Regular time series (e.g. a value every 60 seconds) take 1 bit per value.
+//!
+//! The number of values, the first value & the first delta are written as varints. Then each
+//! delta of delta `D`, with a [`BitWriter`]:
+//!
+//! | `D`              | Bits                |
+//! |------------------|---------------------|
+//! | 0                | `0`                 |
+//! | -64 to 63        | `10` & 7 bits       |
+//! | -256 to 255      | `110` & 9 bits      |
+//! | -2048 to 2047    | `1110` & 12 bits    |
+//! | anything else    | `1111` & 64 bits    |
+//!
+//! The ranges are two's complement, so differ slightly from the paper (which uses e.g. -63 to
+//! 64), and the last bucket is 64 bits rather than 32, so any `i64`s can be written.
+//!
+//! ```rust
+//! use vartyint::gorilla;
+//! let times = (0..100).map(|i| 1_700_000_000 + i * 60).collect::<Vec<i64>>();
+//! let mut buf = Vec::new();
+//! gorilla::write_gorilla(&times, &mut buf);
+//! assert_eq!(buf.len(), 1 + 5 + 1 + 13);
+//!
+//! let (decoded, rest) = gorilla::read_gorilla(&buf).unwrap();
+//! assert_eq!(decoded, times);
+//! assert!(rest.is_empty());
+//! ```
+use crate::bits::{BitReader, BitWriter};
+use crate::*;
+
+/// (prefix, prefix length, value bits) of each bucket, after `D == 0`
+const BUCKETS: [(u64, u32, u32); 4] = [
+    (0b10, 2, 7),
+    (0b110, 3, 9),
+    (0b1110, 4, 12),
+    (0b1111, 4, 64),
+];
+
+/// Write these values to the end of `buf`, returning the number of bytes written.
+pub fn write_gorilla(vals: &[i64], buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    write_usize(vals.len(), buf);
+    let (first, second) = match vals {
+        [] => return buf.len() - start,
+        [first] => {
+            write_i64(*first, buf);
+            return buf.len() - start;
+        }
+        [first, second, ..] => (*first, *second),
+    };
+    write_i64(first, buf);
+    let mut last_delta = second.wrapping_sub(first);
+    write_i64(last_delta, buf);
+
+    let mut bits = BitWriter::new();
+    for pair in vals[1..].windows(2) {
+        let delta = pair[1].wrapping_sub(pair[0]);
+        let dod = delta.wrapping_sub(last_delta);
+        last_delta = delta;
+        if dod == 0 {
+            bits.write_bit(false);
+            continue;
+        }
+        let (prefix, prefix_len, num_bits) = *BUCKETS
+            .iter()
+            .find(|(_, _, num_bits)| {
+                *num_bits == 64 || (-(1 << (num_bits - 1))..(1 << (num_bits - 1))).contains(&dod)
+            })
+            .unwrap();
+        bits.write_bits(prefix, prefix_len);
+        bits.write_bits(dod as u64, num_bits);
+    }
+    buf.extend_from_slice(&bits.into_bytes());
+    buf.len() - start
+}
+
+/// Read values written by `write_gorilla`, returning them & the rest of the buffer.
+pub fn read_gorilla(buf: &[u8]) -> Result<(Vec<i64>, &[u8]), VartyIntError> {
+    let (len, buf) = read_usize(buf)?;
+    if len == 0 {
+        return Ok((vec![], buf));
+    }
+    let (first, buf) = read_i64(buf).map_err(empty_to_not_enough::<i64>)?;
+    if len == 1 {
+        return Ok((vec![first], buf));
+    }
+    let (mut delta, buf) = read_i64(buf).map_err(empty_to_not_enough::<i64>)?;
+
+    // Every value is at least 1 bit, so don't trust a large `len`
+    let mut vals = Vec::with_capacity(len.min(2 + buf.len() * 8));
+    vals.push(first);
+    vals.push(first.wrapping_add(delta));
+    let mut bits = BitReader::new(buf);
+    for _ in 2..len {
+        let dod = if bits.read_bit()? {
+            // Number of 1 bits in the prefix after the first one
+            let mut ones = 0;
+            while ones < 3 && bits.read_bit()? {
+                ones += 1;
+            }
+            let (_, _, num_bits) = BUCKETS[ones];
+            let val = bits.read_bits(num_bits)?;
+            // Sign extend
+            ((val << (64 - num_bits)) as i64) >> (64 - num_bits)
+        } else {
+            0
+        };
+        delta = delta.wrapping_add(dod);
+        vals.push(vals.last().unwrap().wrapping_add(delta));
+    }
+    Ok((vals, bits.remaining_bytes()))
+}
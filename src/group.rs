@@ -0,0 +1,292 @@
+//! The "SIMD group varint" formats G8IU & G8CU, from [SIMD-Based Decoding of Posting
+//! Lists](https://www.stepanovpapers.com/CIKM_2011.pdf) (Stepanov et al, 2011).
+//!
+//! `u32`s are written as 1–4 little endian bytes, in blocks of 9 bytes: one descriptor byte, then
+//! 8 data bytes. Bit `i` of the descriptor is 0 iff data byte `i` is the last byte of an integer.
+//!
+//! * G8IU ("incomplete"): integers are never split between blocks. If the next integer doesn't
+//!   fit, the rest of the block is padding (descriptor bits 1).
+//! * G8CU ("complete"): integers can be split between blocks, so every byte is used.
+//!
+//! In both, the end of the last block is padding. Since the position of every integer in a G8IU
+//! block is known from the descriptor alone, G8IU blocks are decoded with a lookup table & a
+//! byte shuffle: SSSE3's `pshufb` when the CPU supports it, otherwise `std::simd` with the
+//! `simd` feature (nightly only).
+//!
+//! ```rust
+//! use vartyint::group;
+//! let nums = [1u32, 300, 70_000, 16_777_216, 5];
+//! let mut buf = Vec::new();
+//! group::write_g8iu(&nums, &mut buf);
+//! assert_eq!(buf.len(), 18);
+//! assert_eq!(group::read_g8iu(&buf).unwrap(), nums);
+//!
+//! let mut buf = Vec::new();
+//! group::write_g8cu(&nums, &mut buf);
+//! assert_eq!(buf.len(), 18);
+//! assert_eq!(group::read_g8cu(&buf).unwrap(), nums);
+//! ```
+use crate::VartyIntError;
+
+/// Number of data bytes in a block
+const DATA_LEN: usize = 8;
+/// Number of bytes in a block, incl. the descriptor
+const BLOCK_LEN: usize = DATA_LEN + 1;
+
+/// What a G8IU descriptor byte means
+#[derive(Debug, Clone, Copy)]
+struct Descriptor {
+    /// Number of integers in the block
+    count: u8,
+    /// False iff an integer is more than 4 bytes
+    valid: bool,
+    /// For each of the (up to 8) integers, which data byte is each of its 4 bytes (`0x80` for a
+    /// zero byte). This is a `pshufb` mask.
+    shuffle: [u8; 32],
+}
+
+const fn build_g8iu_table() -> [Descriptor; 256] {
+    let mut table = [Descriptor {
+        count: 0,
+        valid: true,
+        shuffle: [0x80; 32],
+    }; 256];
+    let mut desc = 0;
+    while desc < 256 {
+        let mut count = 0;
+        let mut start = 0;
+        let mut i = 0;
+        while i < DATA_LEN {
+            if (desc >> i) & 1 == 0 {
+                let len = i + 1 - start;
+                if len > 4 {
+                    table[desc].valid = false;
+                } else {
+                    let mut b = 0;
+                    while b < len {
+                        table[desc].shuffle[count * 4 + b] = (start + b) as u8;
+                        b += 1;
+                    }
+                }
+                count += 1;
+                start = i + 1;
+            }
+            i += 1;
+        }
+        table[desc].count = count as u8;
+        desc += 1;
+    }
+    table
+}
+
+static G8IU_TABLE: [Descriptor; 256] = build_g8iu_table();
+
+/// Number of bytes needed for this integer
+fn byte_len(num: u32) -> usize {
+    (4 - (num.leading_zeros() as usize / 8)).max(1)
+}
+
+/// Write these integers to the end of `buf` as G8IU, returning the number of bytes written.
+pub fn write_g8iu(nums: &[u32], buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    let mut block_start = None;
+    let mut used = 0;
+    for num in nums {
+        let len = byte_len(*num);
+        if block_start.is_none() || used + len > DATA_LEN {
+            block_start = Some(buf.len());
+            buf.extend_from_slice(&[0xFF; BLOCK_LEN]);
+            used = 0;
+        }
+        let block = &mut buf[block_start.unwrap()..];
+        block[1 + used..1 + used + len].copy_from_slice(&num.to_le_bytes()[..len]);
+        used += len;
+        block[0] &= !(1 << (used - 1));
+    }
+    // Make the padding 0
+    if let Some(block_start) = block_start {
+        buf[block_start + 1 + used..].fill(0);
+    }
+    buf.len() - start
+}
+
+/// Decode one block with SSSE3's `pshufb`
+///
+/// # Safety
+///
+/// The CPU must support SSSE3
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn decode_g8iu_block_ssse3(data: &[u8], desc: &Descriptor, nums: &mut Vec<u32>) {
+    use std::arch::x86_64::*;
+    let mut input = [0u8; 16];
+    input[..DATA_LEN].copy_from_slice(data);
+    let mut output = [0u32; 8];
+    // SAFETY: all loads & stores are from/to arrays of 16 (or more) bytes
+    unsafe {
+        let bytes = _mm_loadu_si128(input.as_ptr() as *const __m128i);
+        let low = _mm_loadu_si128(desc.shuffle.as_ptr() as *const __m128i);
+        let high = _mm_loadu_si128(desc.shuffle[16..].as_ptr() as *const __m128i);
+        _mm_storeu_si128(
+            output.as_mut_ptr() as *mut __m128i,
+            _mm_shuffle_epi8(bytes, low),
+        );
+        _mm_storeu_si128(
+            output[4..].as_mut_ptr() as *mut __m128i,
+            _mm_shuffle_epi8(bytes, high),
+        );
+    }
+    nums.extend_from_slice(&output[..desc.count as usize]);
+}
+
+/// Decode one block with `std::simd`
+#[cfg(feature = "simd")]
+fn decode_g8iu_block_simd(data: &[u8], desc: &Descriptor, nums: &mut Vec<u32>) {
+    use std::simd::u8x16;
+    let mut input = [0u8; 16];
+    input[..DATA_LEN].copy_from_slice(data);
+    let bytes = u8x16::from_array(input);
+    let mut output = [0u8; 32];
+    // Out of range indexes (i.e. 0x80) give 0
+    output[..16].copy_from_slice(
+        bytes
+            .swizzle_dyn(u8x16::from_slice(&desc.shuffle[..16]))
+            .as_array(),
+    );
+    output[16..].copy_from_slice(
+        bytes
+            .swizzle_dyn(u8x16::from_slice(&desc.shuffle[16..]))
+            .as_array(),
+    );
+    nums.extend(
+        output
+            .chunks_exact(4)
+            .take(desc.count as usize)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+    );
+}
+
+/// Decode one block without SIMD
+#[cfg_attr(feature = "simd", allow(dead_code))]
+fn decode_g8iu_block_scalar(data: &[u8], desc: &Descriptor, nums: &mut Vec<u32>) {
+    nums.extend(
+        desc.shuffle
+            .chunks_exact(4)
+            .take(desc.count as usize)
+            .map(|idxs| {
+                idxs.iter().rev().fold(0, |num, &idx| {
+                    (num << 8) | data.get(idx as usize).map_or(0, |b| *b as u32)
+                })
+            }),
+    );
+}
+
+/// Decode a G8IU buffer, with this function to decode each block
+#[inline(always)]
+fn read_g8iu_with(
+    buf: &[u8],
+    decode_block: impl Fn(&[u8], &Descriptor, &mut Vec<u32>),
+) -> Result<Vec<u32>, VartyIntError> {
+    if !buf.len().is_multiple_of(BLOCK_LEN) {
+        return Err(VartyIntError::not_enough(buf.len() % BLOCK_LEN, BLOCK_LEN));
+    }
+    let mut nums = Vec::with_capacity(buf.len() / BLOCK_LEN * 2);
+    for block in buf.chunks_exact(BLOCK_LEN) {
+        let desc = &G8IU_TABLE[block[0] as usize];
+        if !desc.valid {
+            return Err(VartyIntError::TooManyBytesForType);
+        }
+        decode_block(&block[1..], desc, &mut nums);
+    }
+    Ok(nums)
+}
+
+/// # Safety
+///
+/// The CPU must support SSSE3
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn read_g8iu_ssse3(buf: &[u8]) -> Result<Vec<u32>, VartyIntError> {
+    // SAFETY: SSSE3 is supported
+    read_g8iu_with(buf, |data, desc, nums| unsafe {
+        decode_g8iu_block_ssse3(data, desc, nums)
+    })
+}
+
+/// `read_g8iu` without SIMD
+#[cfg_attr(feature = "simd", allow(dead_code))]
+pub(crate) fn read_g8iu_scalar(buf: &[u8]) -> Result<Vec<u32>, VartyIntError> {
+    read_g8iu_with(buf, decode_g8iu_block_scalar)
+}
+
+/// Read all the integers in this G8IU buffer.
+///
+/// If the buffer isn't a whole number of blocks, `NotEnoughBytes` is returned. If an integer is
+/// more than 4 bytes, `TooManyBytesForType` is returned.
+///
+/// On x86_64, SSSE3 is used if the CPU supports it (checked at run time). Otherwise `std::simd`
+/// is used with the `simd` feature, or plain Rust without it.
+pub fn read_g8iu(buf: &[u8]) -> Result<Vec<u32>, VartyIntError> {
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("ssse3") {
+        // SAFETY: SSSE3 is supported
+        return unsafe { read_g8iu_ssse3(buf) };
+    }
+    #[cfg(feature = "simd")]
+    return read_g8iu_with(buf, decode_g8iu_block_simd);
+    #[cfg(not(feature = "simd"))]
+    read_g8iu_scalar(buf)
+}
+/// Write these integers to the end of `buf` as G8CU, returning the number of bytes written.
+pub fn write_g8cu(nums: &[u32], buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    let mut block_start = 0;
+    let mut used = DATA_LEN;
+    for num in nums {
+        let len = byte_len(*num);
+        for (i, byte) in num.to_le_bytes()[..len].iter().enumerate() {
+            if used == DATA_LEN {
+                block_start = buf.len();
+                buf.extend_from_slice(&[0xFF, 0, 0, 0, 0, 0, 0, 0, 0]);
+                used = 0;
+            }
+            buf[block_start + 1 + used] = *byte;
+            if i == len - 1 {
+                buf[block_start] &= !(1 << used);
+            }
+            used += 1;
+        }
+    }
+    buf.len() - start
+}
+
+/// Read all the integers in this G8CU buffer.
+///
+/// If the buffer isn't a whole number of blocks, `NotEnoughBytes` is returned. If an integer is
+/// more than 4 bytes, `TooManyBytesForType` is returned.
+pub fn read_g8cu(buf: &[u8]) -> Result<Vec<u32>, VartyIntError> {
+    if !buf.len().is_multiple_of(BLOCK_LEN) {
+        return Err(VartyIntError::not_enough(buf.len() % BLOCK_LEN, BLOCK_LEN));
+    }
+    let mut nums = Vec::with_capacity(buf.len() / BLOCK_LEN * 2);
+    let mut num = 0u32;
+    let mut len = 0;
+    for block in buf.chunks_exact(BLOCK_LEN) {
+        for (i, byte) in block[1..].iter().enumerate() {
+            if len < 4 {
+                num |= (*byte as u32) << (8 * len);
+            }
+            len += 1;
+            if (block[0] >> i) & 1 == 0 {
+                if len > 4 {
+                    return Err(VartyIntError::TooManyBytesForType);
+                }
+                nums.push(num);
+                num = 0;
+                len = 0;
+            }
+        }
+    }
+    // Anything left over is padding
+    Ok(nums)
+}
@@ -0,0 +1,87 @@
+//! A header for the start of a file or stream: some magic bytes (chosen by you), then the format
+//! version & flags, as varints.
+//!
+//! ```rust
+//! use vartyint::header::{read_header, write_header, Header};
+//! let mut buf = Vec::new();
+//! write_header(b"MYFMT", &Header::new(2).flags(0b01), &mut buf);
+//! assert_eq!(buf, b"MYFMT\x02\x01");
+//! vartyint::write_many(&[1u32, 2, 3], &mut buf);
+//!
+//! let (header, rest) = read_header(&buf, b"MYFMT", 1..=2).unwrap();
+//! assert_eq!(header, Header { version: 2, flags: 0b01 });
+//! assert_eq!(rest, &[1, 2, 3]);
+//!
+//! // A newer version than this reader knows about
+//! assert_eq!(
+//!     read_header(b"MYFMT\x03\x00", b"MYFMT", 1..=2),
+//!     Err(vartyint::VartyIntError::UnsupportedVersion { version: 3 })
+//! );
+//! // Not this format at all
+//! assert_eq!(
+//!     read_header(b"PK\x03\x04", b"MYFMT", 1..=2),
+//!     Err(vartyint::VartyIntError::WrongMagic)
+//! );
+//! ```
+use crate::*;
+use std::ops::RangeBounds;
+
+/// The format version & flags from a header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Header {
+    /// Version of the format
+    pub version: u64,
+    /// Flags (bits), for whatever the format needs
+    pub flags: u64,
+}
+
+impl Header {
+    /// This version, with no flags set
+    pub fn new(version: u64) -> Self {
+        Header { version, flags: 0 }
+    }
+
+    /// Set the flags
+    pub fn flags(mut self, flags: u64) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// True iff all the bits of `flag` are set
+    pub fn has_flag(&self, flag: u64) -> bool {
+        self.flags & flag == flag
+    }
+}
+
+/// Write `magic`, then the header, to the end of `buf`. Returns the number of bytes written.
+pub fn write_header(magic: &[u8], header: &Header, buf: &mut Vec<u8>) -> usize {
+    buf.extend_from_slice(magic);
+    magic.len() + header.version.write_varint(buf) + header.flags.write_varint(buf)
+}
+
+/// Read a header from the start of this buffer, returning it & the rest of the buffer.
+///
+/// If the buffer doesn't start with `magic`, `WrongMagic` is returned (or `NotEnoughBytes`, if it's
+/// only the start of `magic`). If the version isn't in `versions`, `UnsupportedVersion` is
+/// returned.
+pub fn read_header<'a>(
+    buf: &'a [u8],
+    magic: &[u8],
+    versions: impl RangeBounds<u64>,
+) -> Result<(Header, &'a [u8]), VartyIntError> {
+    let Some(rest) = buf.strip_prefix(magic) else {
+        if magic.starts_with(buf) {
+            return Err(VartyIntError::not_enough(
+                buf.len(),
+                magic.len() + 2 * u64::MAX_LEN,
+            ));
+        }
+        return Err(VartyIntError::WrongMagic);
+    };
+    let ((version, flags), rest) =
+        <(u64, u64)>::read_varint(rest).map_err(empty_to_not_enough::<(u64, u64)>)?;
+    if !versions.contains(&version) {
+        return Err(VartyIntError::UnsupportedVersion { version });
+    }
+    Ok((Header { version, flags }, rest))
+}
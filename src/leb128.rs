@@ -0,0 +1,187 @@
+//! LEB128, as used by WebAssembly & DWARF.
+//!
+//! Unlike the rest of this crate, signed numbers are written as two's complement (SLEB128), not
+//! zigzag. WebAssembly producers & linkers write patchable immediates (e.g. relocations) padded to
+//! exactly 5 bytes (32 bit values) or 10 bytes (64 bit values). The readers accept padded values,
+//! up to that maximum length.
+//!
+//! ```rust
+//! use vartyint::leb128::*;
+//! let mut code = Vec::new();
+//! let slot = write_sleb128_padded(0, PADDED_32, &mut code).unwrap();
+//! assert_eq!(code, vec![0x80, 0x80, 0x80, 0x80, 0x00]);
+//!
+//! // Relocate it
+//! patch_sleb128(-2, &mut code[slot..(slot + PADDED_32)]).unwrap();
+//! assert_eq!(code, vec![0xFE, 0xFF, 0xFF, 0xFF, 0x7F]);
+//! assert_eq!(read_sleb128_i32(&code), Ok((-2, &[] as &[u8])));
+//! ```
+use crate::*;
+
+/// Width of a padded 32 bit value
+pub const PADDED_32: usize = 5;
+
+/// Width of a padded 64 bit value
+pub const PADDED_64: usize = 10;
+
+/// Write this as ULEB128, in as few bytes as possible. Returns the number of bytes written.
+pub fn write_uleb128(val: u64, buf: &mut Vec<u8>) -> usize {
+    write_u64(val, buf)
+}
+
+/// Write this as SLEB128, in as few bytes as possible. Returns the number of bytes written.
+pub fn write_sleb128(mut val: i64, buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    loop {
+        let byte = (val & 0b0111_1111) as u8;
+        val >>= 7;
+        let sign = byte & 0b0100_0000 != 0;
+        if (val == 0 && !sign) || (val == -1 && sign) {
+            buf.push(byte);
+            return buf.len() - start;
+        }
+        buf.push(byte | 0b1000_0000);
+    }
+}
+
+/// Write this as ULEB128, padded to exactly `width` bytes (at most `PADDED_64`). Returns the offset
+/// of the value in the buffer. If it doesn't fit, `DoesNotFit` is returned & nothing is written.
+pub fn write_uleb128_padded(
+    val: u64,
+    width: usize,
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    buf.resize(start + width, 0);
+    patch_uleb128(val, &mut buf[start..]).inspect_err(|_| buf.truncate(start))?;
+    Ok(start)
+}
+
+/// Write this as SLEB128, padded to exactly `width` bytes (at most `PADDED_64`). Returns the offset
+/// of the value in the buffer. If it doesn't fit, `DoesNotFit` is returned & nothing is written.
+pub fn write_sleb128_padded(
+    val: i64,
+    width: usize,
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    buf.resize(start + width, 0);
+    patch_sleb128(val, &mut buf[start..]).inspect_err(|_| buf.truncate(start))?;
+    Ok(start)
+}
+
+/// Overwrite this slot with `val` as ULEB128, padded to the width of the slot. If it doesn't fit,
+/// `DoesNotFit` is returned & the slot is unchanged.
+pub fn patch_uleb128(val: u64, slot: &mut [u8]) -> Result<(), VartyIntError> {
+    let width = slot.len();
+    if width == 0 || width > PADDED_64 || (width < PADDED_64 && val >> (7 * width) != 0) {
+        return Err(VartyIntError::DoesNotFit);
+    }
+    for (i, byte) in slot.iter_mut().enumerate() {
+        *byte = ((val >> (7 * i)) & 0b0111_1111) as u8;
+        if i < width - 1 {
+            *byte |= 0b1000_0000;
+        }
+    }
+    Ok(())
+}
+
+/// Overwrite this slot with `val` as SLEB128, padded to the width of the slot. If it doesn't fit,
+/// `DoesNotFit` is returned & the slot is unchanged.
+pub fn patch_sleb128(val: i64, slot: &mut [u8]) -> Result<(), VartyIntError> {
+    let width = slot.len();
+    if width == 0 || width > PADDED_64 {
+        return Err(VartyIntError::DoesNotFit);
+    }
+    if width < PADDED_64 {
+        let bits = 7 * width as u32;
+        let (min, max) = (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1);
+        if val < min || val > max {
+            return Err(VartyIntError::DoesNotFit);
+        }
+    }
+    for (i, byte) in slot.iter_mut().enumerate() {
+        *byte = ((val >> (7 * i).min(63)) & 0b0111_1111) as u8;
+        if i < width - 1 {
+            *byte |= 0b1000_0000;
+        }
+    }
+    Ok(())
+}
+
+/// Read a ULEB128 value of at most `bits` bits
+fn read_uleb(buf: &[u8], bits: u32) -> Result<(u64, &[u8]), VartyIntError> {
+    if buf.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    let max_len = bits.div_ceil(7) as usize;
+    let mut val = 0;
+    for (i, byte) in buf.iter().enumerate() {
+        if i == max_len {
+            return Err(VartyIntError::TooManyBytesForType);
+        }
+        let shift = 7 * i as u32;
+        let group = (byte & 0b0111_1111) as u64;
+        // The unused bits of the last possible byte must be 0
+        if shift + 7 > bits && group >> (bits - shift) != 0 {
+            return Err(VartyIntError::TooManyBytesForType);
+        }
+        val |= group << shift;
+        if byte >> 7 == 0 {
+            return Ok((val, &buf[(i + 1)..]));
+        }
+    }
+    Err(VartyIntError::not_enough(buf.len(), max_len))
+}
+
+/// Read a SLEB128 value of at most `bits` bits
+fn read_sleb(buf: &[u8], bits: u32) -> Result<(i64, &[u8]), VartyIntError> {
+    if buf.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    let max_len = bits.div_ceil(7) as usize;
+    let mut val = 0;
+    for (i, byte) in buf.iter().enumerate() {
+        if i == max_len {
+            return Err(VartyIntError::TooManyBytesForType);
+        }
+        let shift = 7 * i as u32;
+        let group = (byte & 0b0111_1111) as i64;
+        // The unused bits of the last possible byte must all be the same as the sign bit
+        if shift + 7 > bits {
+            let upper = group >> (bits - shift - 1);
+            if upper != 0 && upper != 0b0111_1111 >> (bits - shift - 1) {
+                return Err(VartyIntError::TooManyBytesForType);
+            }
+        }
+        val |= group << shift;
+        if byte >> 7 == 0 {
+            let end = shift + 7;
+            if end < 64 && group & 0b0100_0000 != 0 {
+                val |= -1 << end;
+            }
+            return Ok((val, &buf[(i + 1)..]));
+        }
+    }
+    Err(VartyIntError::not_enough(buf.len(), max_len))
+}
+
+/// Read a ULEB128 `u32`, which can be padded up to `PADDED_32` bytes
+pub fn read_uleb128_u32(buf: &[u8]) -> Result<(u32, &[u8]), VartyIntError> {
+    read_uleb(buf, 32).map(|(val, rest)| (val as u32, rest))
+}
+
+/// Read a ULEB128 `u64`, which can be padded up to `PADDED_64` bytes
+pub fn read_uleb128_u64(buf: &[u8]) -> Result<(u64, &[u8]), VartyIntError> {
+    read_uleb(buf, 64)
+}
+
+/// Read a SLEB128 `i32`, which can be padded up to `PADDED_32` bytes
+pub fn read_sleb128_i32(buf: &[u8]) -> Result<(i32, &[u8]), VartyIntError> {
+    read_sleb(buf, 32).map(|(val, rest)| (val as i32, rest))
+}
+
+/// Read a SLEB128 `i64`, which can be padded up to `PADDED_64` bytes
+pub fn read_sleb128_i64(buf: &[u8]) -> Result<(i64, &[u8]), VartyIntError> {
+    read_sleb(buf, 64)
+}
@@ -35,21 +35,109 @@
 //! ```
 //!
 //!
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 #[cfg(test)]
 mod tests;
 
+pub mod adaptive_set;
+pub mod aggregate;
+pub mod bigint;
+pub mod bits;
+pub mod block_cache;
+pub mod block_file;
+pub mod columns;
+pub mod config;
+pub mod cursor;
+pub mod delta_stream;
+pub mod dictionary;
+pub mod dynamic;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed_point;
+pub mod geo;
+pub mod gorilla;
+pub mod group;
+pub mod header;
+pub mod leb128;
+pub mod metrics;
+pub mod mtf;
+pub mod osm;
+pub mod polyline;
+pub mod reverse;
+pub mod schema;
+pub mod sink;
+pub mod sorted;
+pub mod streaming;
+pub mod stride;
+pub mod string_table;
+pub mod text;
+pub mod timestamp;
+pub mod varint_file;
+pub mod varint_log;
+pub mod varint_map;
+pub mod varint_reader;
+pub mod varint_vec;
+pub mod varint_writer;
+pub mod zonemap;
+
+pub use sink::{CountingSink, HashingSink};
+pub use streaming::{EncodeStatus, StreamingEncoder};
+pub use varint_file::VarIntFile;
+pub use varint_log::{VarIntLog, VarIntLogReader};
+pub use varint_reader::VarIntReader;
+pub use varint_vec::{VarIntSlice, VarIntVec};
+pub use varint_writer::VarIntWriter;
+
 /// Error type
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VartyIntError {
     /// Attempted to read from an empty buffer. No bytes, so cannot return anything
     EmptyBuffer,
 
-    /// There are not enough bytes for the integer.
-    NotEnoughBytes,
+    /// There are not enough bytes for the integer. The buffer ended after `read` bytes of the
+    /// value, which could need up to `max_more` more bytes (`None` if there is no limit). If more
+    /// values follow it, even more bytes are needed.
+    NotEnoughBytes {
+        read: usize,
+        max_more: Option<usize>,
+    },
 
     /// Attempted to read an integer that is too small for the data
     TooManyBytesForType,
+
+    /// Read an enum discriminant (or type tag) which doesn't match any known value
+    UnknownDiscriminant,
+
+    /// The decoded value is outside the allowed range of values
+    OutOfRange,
+
+    /// Text input contains a character which isn't allowed
+    InvalidCharacter,
+
+    /// There were bytes left over after reading everything that was expected
+    TrailingBytes,
+
+    /// The value can't be written in the given number of bytes
+    DoesNotFit,
+
+    /// A value is a different type to what was expected
+    TypeMismatch,
+
+    /// The value has redundant continuation bytes, when only the shortest encoding is allowed
+    NonCanonical,
+
+    /// There isn't enough space left in the output buffer
+    BufferFull,
+
+    /// The running total of delta encoded integers doesn't fit in the type
+    DeltaOverflow,
+
+    /// The data doesn't start with the expected magic bytes
+    WrongMagic,
+
+    /// The format version in a header isn't one which is supported
+    UnsupportedVersion { version: u64 },
 }
 
 impl std::fmt::Display for VartyIntError {
@@ -60,15 +148,46 @@ impl std::fmt::Display for VartyIntError {
 
 impl std::error::Error for VartyIntError {}
 
+impl VartyIntError {
+    /// `NotEnoughBytes`, after `read` bytes of a value which can be up to `max_len` bytes
+    pub(crate) fn not_enough(read: usize, max_len: usize) -> Self {
+        VartyIntError::NotEnoughBytes {
+            read,
+            max_more: Some(max_len.saturating_sub(read)),
+        }
+    }
+}
+
+/// Convert `EmptyBuffer`, from reading a `T` after the start of the data, to `NotEnoughBytes`
+pub(crate) fn empty_to_not_enough<T: VarInt>(e: VartyIntError) -> VartyIntError {
+    match e {
+        VartyIntError::EmptyBuffer => VartyIntError::not_enough(0, T::MAX_LEN),
+        e => e,
+    }
+}
+
 macro_rules! write_unsigned {
     ( $name:ident, $type:ty ) => {
-        /// Write an integer to this buffer
-        pub fn $name(mut val: $type, buf: &mut Vec<u8>) {
+        /// Write an integer to this buffer, returning the number of bytes written
+        #[inline]
+        pub fn $name(mut val: $type, buf: &mut Vec<u8>) -> usize {
+            // Fast paths for the (usually) most common 1 & 2 byte values
+            const TWO_BYTE_MAX: $type = <$type>::MAX >> <$type>::BITS.saturating_sub(14);
+            if val < 0b1000_0000 {
+                buf.push(val as u8);
+                return 1;
+            }
+            if val <= TWO_BYTE_MAX {
+                buf.extend_from_slice(&[(val as u8) | 0b1000_0000, (val >> 7) as u8]);
+                return 2;
+            }
+            let start = buf.len();
             while val >= 0b1000_0000 {
                 buf.push((val as u8) | 0b1000_0000);
                 val >>= 7;
             }
             buf.push(val as u8);
+            buf.len() - start
         }
     };
 }
@@ -77,33 +196,44 @@ write_unsigned!(write_u8, u8);
 write_unsigned!(write_u16, u16);
 write_unsigned!(write_u32, u32);
 write_unsigned!(write_u64, u64);
-write_unsigned!(write_usize, usize);
 write_unsigned!(write_u128, u128);
 
 macro_rules! read_unsigned {
     ( $name:ident, $type:ty ) => {
         /// Read an integer from this buffer
+        #[inline]
         pub fn $name(mut buf: &[u8]) -> Result<($type, &[u8]), VartyIntError> {
-            if buf.is_empty() {
-                return Err(VartyIntError::EmptyBuffer);
+            // Fast paths for the (usually) most common 1 & 2 byte values
+            match buf {
+                [] => return Err(VartyIntError::EmptyBuffer),
+                [b0, rest @ ..] if *b0 < 0b1000_0000 => return Ok((*b0 as $type, rest)),
+                [b0, b1, rest @ ..] if *b1 < 0b1000_0000 => {
+                    let val = (*b0 & 0b0111_1111) as u16 | (*b1 as u16) << 7;
+                    return <$type>::try_from(val)
+                        .map(|val| (val, rest))
+                        .map_err(|_| VartyIntError::TooManyBytesForType);
+                }
+                _ => {}
             }
+            let start_len = buf.len();
             let mut val: $type = 0;
             let mut shift = 0;
             let mut byte: $type;
             let mut is_last: bool;
             loop {
                 if buf.is_empty() {
-                    return Err(VartyIntError::NotEnoughBytes);
+                    return Err(VartyIntError::not_enough(start_len, <$type>::MAX_LEN));
                 }
                 byte = buf[0] as $type;
                 is_last = byte >> 7 == 0;
                 byte &= 0b0111_1111;
                 buf = &buf[1..];
                 byte = match byte.checked_shl(shift) {
-                    None => {
+                    // Bits would be lost
+                    Some(b) if b >> shift == byte => b,
+                    _ => {
                         return Err(VartyIntError::TooManyBytesForType);
                     }
-                    Some(b) => b,
                 };
                 val |= byte;
                 shift += 7;
@@ -122,107 +252,324 @@ read_unsigned!(read_u16, u16);
 read_unsigned!(read_u32, u32);
 read_unsigned!(read_u64, u64);
 read_unsigned!(read_u128, u128);
-read_unsigned!(read_usize, usize);
 
-macro_rules! read_signed {
-    ( $name:ident, $type:ty, $bits:expr ) => {
-        /// Read an integer from this buffer
-        pub fn $name(mut buf: &[u8]) -> Result<($type, &[u8]), VartyIntError> {
-            if buf.is_empty() {
-                return Err(VartyIntError::EmptyBuffer);
-            }
-            let mut num_bits_read = 0;
-            let mut val: i128 = 0;
-            let mut is_last: bool;
+/// Write a `usize` to this buffer, returning the number of bytes written. It's always written like
+/// a `u64`, whatever the pointer width of the platform, so data is the same everywhere.
+#[inline]
+pub fn write_usize(val: usize, buf: &mut Vec<u8>) -> usize {
+    write_u64(val as u64, buf)
+}
 
-            let mut byte: i128;
+/// Read a `usize` from this buffer. It's always read like a `u64`, whatever the pointer width of
+/// the platform. If it's too big for a `usize` (e.g. over `u32::MAX` on a 32 bit platform),
+/// `OutOfRange` is returned.
+#[inline]
+pub fn read_usize(buf: &[u8]) -> Result<(usize, &[u8]), VartyIntError> {
+    let (val, rest) = read_u64(buf)?;
+    let val = usize::try_from(val).map_err(|_| VartyIntError::OutOfRange)?;
+    Ok((val, rest))
+}
 
-            loop {
-                if buf.is_empty() {
-                    return Err(VartyIntError::NotEnoughBytes);
-                }
-                byte = buf[0] as i128;
-                buf = &buf[1..];
+/// Zigzag encoding, which maps signed integers to unsigned integers so that numbers near zero
+/// (positive or negative) are small: `0 → 0`, `-1 → 1`, `1 → 2`, `-2 → 3`, etc.
+///
+/// ```rust
+/// use vartyint::ZigZag;
+/// assert_eq!((-2i32).zigzag_encode(), 3u32);
+/// assert_eq!(i32::zigzag_decode(3), -2);
+/// assert_eq!(vartyint::zigzag_encode(i64::MIN), u64::MAX);
+/// ```
+pub trait ZigZag: Copy {
+    /// The unsigned type of the same size
+    type Unsigned: Copy;
 
-                is_last = byte >> 7 == 0;
-                byte &= 0b0111_1111;
+    fn zigzag_encode(self) -> Self::Unsigned;
+    fn zigzag_decode(val: Self::Unsigned) -> Self;
+}
 
-                byte = match byte.checked_shl(num_bits_read) {
-                    None => {
-                        return Err(VartyIntError::TooManyBytesForType);
-                    }
-                    Some(v) => v,
-                };
-                val |= byte;
+macro_rules! zigzag_impl {
+    ( $type:ty, $unsigned:ty ) => {
+        impl ZigZag for $type {
+            type Unsigned = $unsigned;
 
-                num_bits_read += 7;
-                if is_last {
-                    break;
-                }
+            fn zigzag_encode(self) -> $unsigned {
+                ((self << 1) ^ (self >> (<$type>::BITS - 1))) as $unsigned
             }
+            fn zigzag_decode(val: $unsigned) -> $type {
+                ((val >> 1) as $type) ^ -((val & 1) as $type)
+            }
+        }
+    };
+}
 
-            let mut sign = 1;
-            if val & 0b0000_0001 == 1 {
-                sign = -1;
-                val += 1;
+zigzag_impl!(i8, u8);
+zigzag_impl!(i16, u16);
+zigzag_impl!(i32, u32);
+zigzag_impl!(i64, u64);
+zigzag_impl!(i128, u128);
+zigzag_impl!(isize, usize);
+
+/// Zigzag encode this signed integer. See `ZigZag`.
+pub fn zigzag_encode<T: ZigZag>(val: T) -> T::Unsigned {
+    val.zigzag_encode()
+}
+
+/// Decode this zigzag encoded integer. See `ZigZag`.
+pub fn zigzag_decode<T: ZigZag>(val: T::Unsigned) -> T {
+    T::zigzag_decode(val)
+}
+
+/// Zigzag encode every value in `input` into `output`. The loop is simple enough for the compiler
+/// to vectorize. Panics if the slices are different lengths.
+///
+/// ```rust
+/// let mut output = [0; 4];
+/// vartyint::zigzag_encode_slice(&[0i64, -1, 1, -2], &mut output);
+/// assert_eq!(output, [0u64, 1, 2, 3]);
+/// ```
+pub fn zigzag_encode_slice<T: ZigZag>(input: &[T], output: &mut [T::Unsigned]) {
+    assert_eq!(input.len(), output.len(), "slices must be the same length");
+    for (val, out) in input.iter().zip(output.iter_mut()) {
+        *out = val.zigzag_encode();
+    }
+}
+
+/// Decode every zigzag encoded value in `input` into `output`. The loop is simple enough for the
+/// compiler to vectorize. Panics if the slices are different lengths.
+///
+/// ```rust
+/// let mut output = [0; 4];
+/// vartyint::zigzag_decode_slice(&[0u32, 1, 2, 3], &mut output);
+/// assert_eq!(output, [0i32, -1, 1, -2]);
+/// ```
+pub fn zigzag_decode_slice<T: ZigZag>(input: &[T::Unsigned], output: &mut [T]) {
+    assert_eq!(input.len(), output.len(), "slices must be the same length");
+    for (val, out) in input.iter().zip(output.iter_mut()) {
+        *out = T::zigzag_decode(*val);
+    }
+}
+
+/// A signed integer which is zigzag encoded. This is the same as the default encoding of signed
+/// integers, but makes the choice explicit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Zigzag<T>(pub T);
+
+/// An integer which is written as its two's complement bits, i.e. as the unsigned integer of the
+/// same size. Small negative numbers take the maximum number of bytes, like protobuf's `int64`.
+///
+/// ```rust
+/// use vartyint::{Raw, VarInt};
+/// assert_eq!(Raw(-1i32).as_varint(), vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+/// assert_eq!(Raw(1i32).as_varint(), vec![0x01]);
+/// assert_eq!(vartyint::Zigzag(-1i32).as_varint(), vec![0x01]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Raw<T>(pub T);
+
+macro_rules! wrapper_impl {
+    ( $wrapper:ident, $type:ty, $unsigned:ty, $encode:expr, $decode:expr ) => {
+        impl VarInt for $wrapper<$type> {
+            const MAX_LEN: usize = <$unsigned>::MAX_LEN;
+
+            fn zero() -> Self {
+                $wrapper(0)
+            }
+            fn as_varint(&self) -> Vec<u8> {
+                let mut vec = vec![];
+                self.write_varint(&mut vec);
+                vec
+            }
+            fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+                let (val, buf) = <$unsigned>::from_varint(buf)?;
+                let decode: fn($unsigned) -> $type = $decode;
+                Ok(($wrapper(decode(val)), buf))
+            }
+            fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
+                let encode: fn($type) -> $unsigned = $encode;
+                encode(self.0).write_varint(buf)
             }
-            val >>= 1;
-            val *= sign;
+        }
 
-            match val.try_into() {
-                Err(_) => Err(VartyIntError::TooManyBytesForType),
-                Ok(val) => Ok((val, buf)),
+        impl From<$type> for $wrapper<$type> {
+            fn from(val: $type) -> Self {
+                $wrapper(val)
             }
         }
     };
 }
 
-read_signed!(read_i8, i8, 8);
-read_signed!(read_i16, i16, 16);
-read_signed!(read_i32, i32, 32);
-read_signed!(read_i64, i64, 64);
-read_signed!(read_i128, i128, 128);
-read_signed!(read_isize, isize, std::mem::size_of::<isize>() * 8);
+macro_rules! wrappers_impl {
+    ( $type:ty, $unsigned:ty ) => {
+        wrapper_impl!(
+            Zigzag,
+            $type,
+            $unsigned,
+            |v| v.zigzag_encode(),
+            <$type>::zigzag_decode
+        );
+        wrapper_impl!(Raw, $type, $unsigned, |v| v as $unsigned, |v| v as $type);
+        wrapper_impl!(Raw, $unsigned, $unsigned, |v| v, |v| v);
+    };
+}
+
+wrappers_impl!(i8, u8);
+wrappers_impl!(i16, u16);
+wrappers_impl!(i32, u32);
+wrappers_impl!(i64, u64);
+wrappers_impl!(i128, u128);
+wrappers_impl!(isize, usize);
+
+macro_rules! read_signed {
+    ( $name:ident, $type:ty, $read_unsigned:ident ) => {
+        /// Read an integer from this buffer
+        #[inline]
+        pub fn $name(buf: &[u8]) -> Result<($type, &[u8]), VartyIntError> {
+            let (val, buf) = $read_unsigned(buf)?;
+            Ok((<$type>::zigzag_decode(val), buf))
+        }
+    };
+}
+
+read_signed!(read_i8, i8, read_u8);
+read_signed!(read_i16, i16, read_u16);
+read_signed!(read_i32, i32, read_u32);
+read_signed!(read_i64, i64, read_u64);
+read_signed!(read_i128, i128, read_u128);
+read_signed!(read_isize, isize, read_usize);
 
 macro_rules! write_signed {
-    ( $name:ident, $type:ty ) => {
-        /// Write an integer to this buffer
-        pub fn $name(val: $type, buf: &mut Vec<u8>) {
-            if val == 0 {
-                buf.push(0);
-                return;
-            }
-
-            // to prevent around overflows, work with i128 version of numbers
-            // TODO What happens with i128 numbers & overflowing?
-            let val: i128 = val as i128;
-            // convert it to zig zag encoding
-            let mut val = (val << 1) ^ (val >> std::mem::size_of::<$type>() * 8 - 1);
-            let mut num: u8;
-
-            while val != 0 {
-                num = (val & 0b0111_1111) as u8;
-                val >>= 7;
-                if val != 0 {
-                    num |= 0b1000_0000;
+    ( $name:ident, $type:ty, $write_unsigned:ident ) => {
+        /// Write an integer to this buffer, returning the number of bytes written
+        #[inline]
+        pub fn $name(val: $type, buf: &mut Vec<u8>) -> usize {
+            $write_unsigned(val.zigzag_encode(), buf)
+        }
+    };
+}
+
+write_signed!(write_i8, i8, write_u8);
+write_signed!(write_i16, i16, write_u16);
+write_signed!(write_i32, i32, write_u32);
+write_signed!(write_i64, i64, write_u64);
+write_signed!(write_i128, i128, write_u128);
+write_signed!(write_isize, isize, write_usize);
+
+/// The number of bytes in the varint at the start of this buffer
+fn varint_len(buf: &[u8]) -> Result<usize, VartyIntError> {
+    if buf.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    match buf.iter().position(|b| b >> 7 == 0) {
+        None => Err(VartyIntError::NotEnoughBytes {
+            read: buf.len(),
+            max_more: None,
+        }),
+        Some(i) => Ok(i + 1),
+    }
+}
+
+macro_rules! read_saturating {
+    ( $name:ident, $read:ident, $type:ty, $signed:expr ) => {
+        /// Read an integer from this buffer. If it's too big for the type, the whole varint is read
+        /// and the maximum (or minimum) value is returned.
+        pub fn $name(buf: &[u8]) -> Result<($type, &[u8]), VartyIntError> {
+            let len = varint_len(buf)?;
+            match $read(&buf[..len]) {
+                Ok((val, _)) => Ok((val, &buf[len..])),
+                // With zigzag encoding, the lowest bit is the sign
+                Err(VartyIntError::TooManyBytesForType | VartyIntError::OutOfRange)
+                    if $signed && buf[0] & 1 == 1 =>
+                {
+                    Ok((<$type>::MIN, &buf[len..]))
                 }
-                buf.push(num);
+                Err(VartyIntError::TooManyBytesForType | VartyIntError::OutOfRange) => {
+                    Ok((<$type>::MAX, &buf[len..]))
+                }
+                Err(e) => Err(e),
+            }
+        }
+    };
+}
+
+read_saturating!(read_u8_saturating, read_u8, u8, false);
+read_saturating!(read_u16_saturating, read_u16, u16, false);
+read_saturating!(read_u32_saturating, read_u32, u32, false);
+read_saturating!(read_u64_saturating, read_u64, u64, false);
+read_saturating!(read_u128_saturating, read_u128, u128, false);
+read_saturating!(read_usize_saturating, read_usize, usize, false);
+read_saturating!(read_i8_saturating, read_i8, i8, true);
+read_saturating!(read_i16_saturating, read_i16, i16, true);
+read_saturating!(read_i32_saturating, read_i32, i32, true);
+read_saturating!(read_i64_saturating, read_i64, i64, true);
+read_saturating!(read_i128_saturating, read_i128, i128, true);
+read_saturating!(read_isize_saturating, read_isize, isize, true);
+
+/// Read a varint of any length, returning the lowest 128 bits, and bit 128
+fn read_low_bits(buf: &[u8]) -> Result<(u128, bool, &[u8]), VartyIntError> {
+    let len = varint_len(buf)?;
+    let mut val: u128 = 0;
+    let mut bit128 = false;
+    for (i, byte) in buf[..len].iter().enumerate() {
+        let byte = (byte & 0b0111_1111) as u128;
+        let shift = i as u32 * 7;
+        if shift < 128 {
+            val |= byte << shift;
+        }
+        if (shift..(shift + 7)).contains(&128) {
+            bit128 = (byte >> (128 - shift)) & 1 == 1;
+        }
+    }
+    Ok((val, bit128, &buf[len..]))
+}
+
+macro_rules! read_truncating {
+    ( $name:ident, $type:ty, $signed:expr ) => {
+        /// Read an integer from this buffer. The whole varint is read, and if it's too big for the
+        /// type, the high bits are discarded (like `as`).
+        pub fn $name(buf: &[u8]) -> Result<($type, &[u8]), VartyIntError> {
+            let (val, bit128, rest) = read_low_bits(buf)?;
+            if $signed {
+                let val = (((val >> 1) | ((bit128 as u128) << 127)) as i128) ^ -((val & 1) as i128);
+                Ok((val as $type, rest))
+            } else {
+                Ok((val as $type, rest))
             }
         }
     };
 }
 
-write_signed!(write_i8, i8);
-write_signed!(write_i16, i16);
-write_signed!(write_i32, i32);
-write_signed!(write_i64, i64);
-write_signed!(write_i128, i128);
-write_signed!(write_isize, isize);
+read_truncating!(read_u8_truncating, u8, false);
+read_truncating!(read_u16_truncating, u16, false);
+read_truncating!(read_u32_truncating, u32, false);
+read_truncating!(read_u64_truncating, u64, false);
+read_truncating!(read_u128_truncating, u128, false);
+read_truncating!(read_usize_truncating, usize, false);
+read_truncating!(read_i8_truncating, i8, true);
+read_truncating!(read_i16_truncating, i16, true);
+read_truncating!(read_i32_truncating, i32, true);
+read_truncating!(read_i64_truncating, i64, true);
+read_truncating!(read_i128_truncating, i128, true);
+read_truncating!(read_isize_truncating, isize, true);
 
 pub trait VarInt: std::fmt::Debug + Copy {
+    /// The most bytes one value can be written as
+    const MAX_LEN: usize;
+
     fn zero() -> Self;
     fn as_varint(&self) -> Vec<u8>;
-    fn write_varint(&self, buf: &mut Vec<u8>);
+    /// Write this to the end of the buffer, returning the number of bytes written
+    fn write_varint(&self, buf: &mut Vec<u8>) -> usize;
+
+    /// Write this to the start of `out`, returning the number of bytes written, or `None` if it
+    /// doesn't fit (in which case some of `out` may have been overwritten). Integers are written
+    /// without allocating.
+    fn write_varint_slice(&self, out: &mut [u8]) -> Option<usize> {
+        let bytes = self.as_varint();
+        out.get_mut(..bytes.len())?.copy_from_slice(&bytes);
+        Some(bytes.len())
+    }
 
     fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError>
     where
@@ -236,18 +583,116 @@ pub trait VarInt: std::fmt::Debug + Copy {
     }
 }
 
+/// Error when reading from a `std::io::Read`
+#[derive(Debug)]
 pub enum VartyIntReadError {
+    /// The bytes could not be decoded
     VartyIntError(VartyIntError),
+    /// Reading the bytes failed
     ReadError(std::io::Error),
 }
 
-trait ReadVarInt {
-    fn read_varint_i32(&mut self) -> Result<i32, VartyIntError>;
+impl std::fmt::Display for VartyIntReadError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            VartyIntReadError::VartyIntError(e) => write!(fmt, "{}", e),
+            VartyIntReadError::ReadError(e) => write!(fmt, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for VartyIntReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VartyIntReadError::VartyIntError(e) => Some(e),
+            VartyIntReadError::ReadError(e) => Some(e),
+        }
+    }
+}
+
+impl From<VartyIntError> for VartyIntReadError {
+    fn from(e: VartyIntError) -> Self {
+        VartyIntReadError::VartyIntError(e)
+    }
+}
+
+impl From<std::io::Error> for VartyIntReadError {
+    fn from(e: std::io::Error) -> Self {
+        VartyIntReadError::ReadError(e)
+    }
+}
+
+/// Write one value to this writer, returning the number of bytes written.
+///
+/// ```rust
+/// let mut out = Vec::new();
+/// assert_eq!(vartyint::write_to(300u32, &mut out).unwrap(), 2);
+/// assert_eq!(out, vec![0xAC, 0x02]);
+/// ```
+pub fn write_to<T, W>(val: T, writer: &mut W) -> std::io::Result<usize>
+where
+    T: VarInt,
+    W: std::io::Write,
+{
+    let bytes = val.as_varint();
+    writer.write_all(&bytes)?;
+    Ok(bytes.len())
+}
+
+/// Read one value from this reader. Returns `Ok(None)` if the reader is at the end, and
+/// `NotEnoughBytes` if it ends part way through the value.
+///
+/// Bytes are read one at a time, so use a `BufReader` (or similar).
+///
+/// ```rust
+/// let mut reader: &[u8] = &[0xAC, 0x02, 0x01];
+/// assert_eq!(vartyint::read_from::<u32, _>(&mut reader).unwrap(), Some(300));
+/// assert_eq!(vartyint::read_from::<u32, _>(&mut reader).unwrap(), Some(1));
+/// assert_eq!(vartyint::read_from::<u32, _>(&mut reader).unwrap(), None);
+/// ```
+pub fn read_from<T, R>(reader: &mut R) -> Result<Option<T>, VartyIntReadError>
+where
+    T: VarInt,
+    R: std::io::Read,
+{
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = [0];
+        match reader.read_exact(&mut byte) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                if bytes.is_empty() {
+                    return Ok(None);
+                }
+                return Err(VartyIntError::not_enough(bytes.len(), T::MAX_LEN).into());
+            }
+            Err(e) => return Err(e.into()),
+        }
+        bytes.push(byte[0]);
+        // Don't keep reading a run of continuation bytes forever
+        if bytes.len() > T::MAX_LEN {
+            return Err(VartyIntError::TooManyBytesForType.into());
+        }
+        // Only the last byte of a varint has the top bit unset
+        if byte[0] >> 7 == 0 {
+            match T::read_varint(&bytes) {
+                Ok((val, _)) => return Ok(Some(val)),
+                Err(VartyIntError::NotEnoughBytes { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
 }
 
 macro_rules! trait_impl {
-    ( $type:ty, $read: ident, $write: ident ) => {
+    ( $type:ty, $read: ident, $write: ident, $to_unsigned:expr ) => {
+        trait_impl!($type as $type, $read, $write, $to_unsigned);
+    };
+    // Written like `$wire`, e.g. `usize` like `u64`
+    ( $type:ty as $wire:ty, $read: ident, $write: ident, $to_unsigned:expr ) => {
         impl VarInt for $type {
+            const MAX_LEN: usize = (<$wire>::BITS as usize).div_ceil(7);
+
             fn zero() -> Self {
                 0
             }
@@ -260,116 +705,1818 @@ macro_rules! trait_impl {
                 $read(buf)
             }
 
-            fn write_varint(&self, buf: &mut Vec<u8>) {
+            fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
                 $write(*self, buf)
             }
+
+            fn write_varint_slice(&self, out: &mut [u8]) -> Option<usize> {
+                let mut val: u128 = $to_unsigned(*self);
+                let mut len = 0;
+                loop {
+                    let byte = out.get_mut(len)?;
+                    len += 1;
+                    if val < 0b1000_0000 {
+                        *byte = val as u8;
+                        return Some(len);
+                    }
+                    *byte = (val as u8) | 0b1000_0000;
+                    val >>= 7;
+                }
+            }
         }
     };
 }
 
-trait_impl!(i8, read_i8, write_i8);
-trait_impl!(i16, read_i16, write_i16);
-trait_impl!(i32, read_i32, write_i32);
-trait_impl!(i64, read_i64, write_i64);
-trait_impl!(i128, read_i128, write_i128);
+trait_impl!(i8, read_i8, write_i8, |v: i8| v.zigzag_encode() as u128);
+trait_impl!(i16, read_i16, write_i16, |v: i16| v.zigzag_encode() as u128);
+trait_impl!(i32, read_i32, write_i32, |v: i32| v.zigzag_encode() as u128);
+trait_impl!(i64, read_i64, write_i64, |v: i64| v.zigzag_encode() as u128);
+trait_impl!(i128, read_i128, write_i128, |v: i128| v.zigzag_encode());
+trait_impl!(
+    isize as i64,
+    read_isize,
+    write_isize,
+    |v: isize| v.zigzag_encode() as u128
+);
 
-trait_impl!(u8, read_u8, write_u8);
-trait_impl!(u16, read_u16, write_u16);
-trait_impl!(u32, read_u32, write_u32);
-trait_impl!(u64, read_u64, write_u64);
-trait_impl!(u128, read_u128, write_u128);
+trait_impl!(u8, read_u8, write_u8, |v: u8| v as u128);
+trait_impl!(u16, read_u16, write_u16, |v: u16| v as u128);
+trait_impl!(u32, read_u32, write_u32, |v: u32| v as u128);
+trait_impl!(u64, read_u64, write_u64, |v: u64| v as u128);
+trait_impl!(u128, read_u128, write_u128, |v: u128| v);
+trait_impl!(usize as u64, read_usize, write_usize, |v: usize| v as u128);
 
-/// Like write_many, but creates a fresh vec and returns it.
-pub fn write_many_new<T>(nums: &[T]) -> Vec<u8>
-where
-    T: VarInt,
-{
-    let mut buf = vec![];
-    write_many(nums, &mut buf);
-    buf
+/// Write a `Duration` to this buffer, as the whole seconds (`u64`) followed by the subsecond
+/// nanoseconds (`u32`). Returns the number of bytes written.
+pub fn write_duration(val: std::time::Duration, buf: &mut Vec<u8>) -> usize {
+    write_u64(val.as_secs(), buf) + write_u32(val.subsec_nanos(), buf)
 }
-/// turn the numbers into a sequence of varint bytes, adding to the end of the buf Vec
-pub fn write_many<T>(nums: &[T], buf: &mut Vec<u8>)
-where
-    T: VarInt,
-{
-    for num in nums.iter() {
-        num.write_varint(buf);
+
+/// Read a `Duration` from this buffer. Subsecond nanoseconds of 1 second or more is `OutOfRange`
+pub fn read_duration(buf: &[u8]) -> Result<(std::time::Duration, &[u8]), VartyIntError> {
+    let (secs, rest) = read_u64(buf)?;
+    let (nanos, rest) = read_u32(rest).map_err(empty_to_not_enough::<u32>)?;
+    if nanos >= 1_000_000_000 {
+        return Err(VartyIntError::OutOfRange);
     }
+    Ok((std::time::Duration::new(secs, nanos), rest))
 }
 
-/// Read many different integers from this list of bytes, one after the other.
-pub fn read_many<T>(buf: &[u8]) -> impl Iterator<Item = Result<T, VartyIntError>> + '_
-where
-    T: VarInt,
-{
-    let mut buf = buf;
-    std::iter::from_fn(move || {
-        if buf.is_empty() {
-            return None;
+impl VarInt for std::time::Duration {
+    const MAX_LEN: usize = u64::MAX_LEN + u32::MAX_LEN;
+
+    fn zero() -> Self {
+        std::time::Duration::ZERO
+    }
+    fn as_varint(&self) -> Vec<u8> {
+        let mut vec = vec![];
+        write_duration(*self, &mut vec);
+        vec
+    }
+    fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        read_duration(buf)
+    }
+
+    fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
+        write_duration(*self, buf)
+    }
+}
+
+macro_rules! ip_addr_impl {
+    ( $type:ident, $int:ty ) => {
+        /// Written as the address as an integer (`u32` for IPv4, `u128` for IPv6), most significant
+        /// byte first, e.g. `10.0.0.1` is `0x0A000001`
+        impl VarInt for std::net::$type {
+            const MAX_LEN: usize = <$int>::MAX_LEN;
+
+            fn zero() -> Self {
+                std::net::$type::UNSPECIFIED
+            }
+            fn as_varint(&self) -> Vec<u8> {
+                <$int>::from(*self).as_varint()
+            }
+            fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+                let (val, rest) = <$int>::read_varint(buf)?;
+                Ok((val.into(), rest))
+            }
+
+            fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
+                <$int>::from(*self).write_varint(buf)
+            }
         }
-        match T::read_varint(buf) {
-            Err(VartyIntError::EmptyBuffer) => None,
-            Err(e) => Some(Err(e)),
-            Ok((num, newbuf)) => {
-                buf = newbuf;
-                Some(Ok(num))
+    };
+}
+
+ip_addr_impl!(Ipv4Addr, u32);
+ip_addr_impl!(Ipv6Addr, u128);
+
+/// Written as `4` or `6`, followed by the `Ipv4Addr` or `Ipv6Addr`. Any other tag is
+/// `UnknownDiscriminant`.
+///
+/// ```rust
+/// use std::net::IpAddr;
+/// let addr: IpAddr = "192.168.0.1".parse().unwrap();
+/// let buf = vartyint::write_many_new(&[addr, "::1".parse().unwrap()]);
+/// assert_eq!(buf, vec![4, 0x81, 0x80, 0xA0, 0x85, 0x0C, 6, 1]);
+/// ```
+impl VarInt for std::net::IpAddr {
+    const MAX_LEN: usize = 1 + u128::MAX_LEN;
+
+    fn zero() -> Self {
+        std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED)
+    }
+    fn as_varint(&self) -> Vec<u8> {
+        let mut vec = vec![];
+        self.write_varint(&mut vec);
+        vec
+    }
+    fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        let (tag, rest) = read_u8(buf)?;
+        match tag {
+            4 => {
+                let (addr, rest) = std::net::Ipv4Addr::read_varint(rest)
+                    .map_err(empty_to_not_enough::<std::net::Ipv4Addr>)?;
+                Ok((addr.into(), rest))
+            }
+            6 => {
+                let (addr, rest) = std::net::Ipv6Addr::read_varint(rest)
+                    .map_err(empty_to_not_enough::<std::net::Ipv6Addr>)?;
+                Ok((addr.into(), rest))
             }
+            _ => Err(VartyIntError::UnknownDiscriminant),
         }
-    })
+    }
+
+    fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
+        match self {
+            std::net::IpAddr::V4(addr) => write_u8(4, buf) + addr.write_varint(buf),
+            std::net::IpAddr::V6(addr) => write_u8(6, buf) + addr.write_varint(buf),
+        }
+    }
 }
 
-pub fn write_many_delta_new<T>(nums: &[T]) -> Vec<u8>
-where
-    T: VarInt + std::ops::Sub<T, Output = T> + Copy,
-{
-    let mut buf = Vec::with_capacity(nums.len());
-    write_many_delta(nums, &mut buf);
-    buf
+macro_rules! num_wrapper_impl {
+    ( $wrapper:ident ) => {
+        /// Written the same as the inner value
+        impl<T: VarInt> VarInt for std::num::$wrapper<T> {
+            const MAX_LEN: usize = T::MAX_LEN;
+
+            fn zero() -> Self {
+                std::num::$wrapper(T::zero())
+            }
+            fn as_varint(&self) -> Vec<u8> {
+                self.0.as_varint()
+            }
+            fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+                let (val, rest) = T::from_varint(buf)?;
+                Ok((std::num::$wrapper(val), rest))
+            }
+            fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
+                self.0.write_varint(buf)
+            }
+        }
+    };
 }
 
-pub fn write_many_delta<T>(nums: &[T], buf: &mut Vec<u8>)
-where
-    T: VarInt + std::ops::Sub<T, Output = T>,
-{
-    let mut last: T = T::zero();
-    for num in nums {
-        (*num - last).write_varint(buf);
-        last = *num
+num_wrapper_impl!(Wrapping);
+num_wrapper_impl!(Saturating);
+
+/// Read one element of a tuple. Running out of bytes after the first element is `NotEnoughBytes`
+fn read_tuple_element<'a, T: VarInt>(
+    whole: &[u8],
+    buf: &'a [u8],
+) -> Result<(T, &'a [u8]), VartyIntError> {
+    match T::read_varint(buf) {
+        Err(e) if buf.len() < whole.len() => Err(empty_to_not_enough::<T>(e)),
+        res => res,
     }
 }
 
-/// Read many different integers from this list of bytes, one after the other, where the integers
-/// are stores as offsets from each other. This is very effecient when a lot of integers are
-/// incrementing
-pub fn read_many_delta<'a, T>(buf: &'a [u8]) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
-where
-    T: VarInt + std::ops::Add<T, Output = T> + Copy + 'a,
-{
-    let mut buf = buf;
-    let mut last = T::zero();
-    std::iter::from_fn(move || {
-        if buf.is_empty() {
-            return None;
-        }
-        match T::read_varint(buf) {
-            Err(VartyIntError::EmptyBuffer) => None,
-            Err(e) => Some(Err(e)),
-            Ok((num, newbuf)) => {
-                buf = newbuf;
-                last = last + num;
-                Some(Ok(last))
+macro_rules! tuple_impl {
+    ( $( $type:ident $var:ident ),+ ) => {
+        /// Each element is written one after the other
+        impl<$( $type: VarInt ),+> VarInt for ( $( $type, )+ ) {
+            const MAX_LEN: usize = 0 $( + $type::MAX_LEN )+;
+
+            fn zero() -> Self {
+                ( $( $type::zero(), )+ )
+            }
+            fn as_varint(&self) -> Vec<u8> {
+                let mut vec = vec![];
+                self.write_varint(&mut vec);
+                vec
+            }
+            fn from_varint(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+                let whole = buf;
+                $( let ($var, buf) = read_tuple_element::<$type>(whole, buf)?; )+
+                Ok((( $( $var, )+ ), buf))
+            }
+
+            fn write_varint(&self, buf: &mut Vec<u8>) -> usize {
+                let ( $( $var, )+ ) = self;
+                0 $( + $var.write_varint(buf) )+
             }
         }
-    })
+    };
 }
 
-/// Read many different integers from this list of bytes, one after the other, where the integers
-/// are stores as offsets from each other. This is very effecient when a lot of integers are
-/// incrementing. Like `read_many_delta`, but returns the allocated vec for you.
-pub fn read_many_delta_new<'a, T>(buf: &'a [u8]) -> Result<Vec<T>, VartyIntError>
-where
-    T: VarInt + std::ops::Add<T, Output = T> + Copy + 'a,
-{
-    read_many_delta(buf).collect::<Result<Vec<_>, _>>()
+tuple_impl!(A a);
+tuple_impl!(A a, B b);
+tuple_impl!(A a, B b, C c);
+tuple_impl!(A a, B b, C c, D d);
+tuple_impl!(A a, B b, C c, D d, E e);
+tuple_impl!(A a, B b, C c, D d, E e, F f);
+tuple_impl!(A a, B b, C c, D d, E e, F f, G g);
+tuple_impl!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Read exactly one value from this buffer. If there are any bytes left over, `TrailingBytes` is
+/// returned.
+///
+/// ```rust
+/// assert_eq!(vartyint::read_single::<u32>(&[0xAC, 0x02]), Ok(300));
+/// assert_eq!(
+///     vartyint::read_single::<u32>(&[0xAC, 0x02, 0x00]),
+///     Err(vartyint::VartyIntError::TrailingBytes)
+/// );
+/// ```
+pub fn read_single<T: VarInt>(buf: &[u8]) -> Result<T, VartyIntError> {
+    match T::read_varint(buf)? {
+        (val, []) => Ok(val),
+        _ => Err(VartyIntError::TrailingBytes),
+    }
+}
+
+/// Read a value from the start of this buffer, returning it & the number of bytes read (rather
+/// than the rest of the buffer).
+///
+/// ```rust
+/// assert_eq!(vartyint::read_with_len::<u32>(&[0xAC, 0x02, 0x05]), Ok((300, 2)));
+/// ```
+pub fn read_with_len<T: VarInt>(buf: &[u8]) -> Result<(T, usize), VartyIntError> {
+    let (val, rest) = T::read_varint(buf)?;
+    Ok((val, buf.len() - rest.len()))
+}
+
+/// Like `read_with_len`, but if the buffer is empty, or ends part way through the value, `Ok(None)`
+/// is returned, i.e. "wait for more data". Only malformed data (e.g. too many bytes for `T`) is an
+/// error. Useful for reading from non-blocking sockets.
+///
+/// ```rust
+/// let mut buf = vec![0xAC];
+/// assert_eq!(vartyint::try_read::<u32>(&buf), Ok(None));
+/// buf.push(0x02);
+/// assert_eq!(vartyint::try_read::<u32>(&buf), Ok(Some((300, 2))));
+/// ```
+pub fn try_read<T: VarInt>(buf: &[u8]) -> Result<Option<(T, usize)>, VartyIntError> {
+    match read_with_len(buf) {
+        Ok(res) => Ok(Some(res)),
+        Err(VartyIntError::EmptyBuffer | VartyIntError::NotEnoughBytes { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Read a value which starts at `offset` in this buffer, returning it & the number of bytes read,
+/// so the next value is at `offset + len`. If `offset` is at (or after) the end of the buffer,
+/// `EmptyBuffer` is returned.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[1u32, 300, 5]);
+/// let mut offset = 0;
+/// let mut nums = Vec::new();
+/// while offset < buf.len() {
+///     let (num, len) = vartyint::read_at::<u32>(&buf, offset).unwrap();
+///     nums.push(num);
+///     offset += len;
+/// }
+/// assert_eq!(nums, vec![1, 300, 5]);
+/// ```
+pub fn read_at<T: VarInt>(buf: &[u8], offset: usize) -> Result<(T, usize), VartyIntError> {
+    read_with_len(buf.get(offset..).unwrap_or_default())
+}
+
+/// Read exactly `N` values, one after the other, into an array, returning it & the rest of the
+/// buffer. If the buffer ends before `N` values, `NotEnoughBytes` is returned.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[0x5641u32, 2, 100, 7]);
+/// let ([magic, version, count], rest) = vartyint::read_array::<u32, 3>(&buf).unwrap();
+/// assert_eq!((magic, version, count), (0x5641, 2, 100));
+/// assert_eq!(rest, &[7]);
+/// ```
+pub fn read_array<T: VarInt, const N: usize>(buf: &[u8]) -> Result<([T; N], &[u8]), VartyIntError> {
+    let mut vals = [T::zero(); N];
+    let mut rest = buf;
+    for (i, val) in vals.iter_mut().enumerate() {
+        let (num, newrest) = T::read_varint(rest).map_err(|e| match e {
+            VartyIntError::EmptyBuffer if i > 0 => empty_to_not_enough::<T>(e),
+            e => e,
+        })?;
+        *val = num;
+        rest = newrest;
+    }
+    Ok((vals, rest))
+}
+
+/// Read exactly `n` values, one after the other, returning them & the rest of the buffer. Useful
+/// when a count is followed by that many values & then other data. If the buffer ends before `n`
+/// values, `NotEnoughBytes` is returned.
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// vartyint::write_usize(3, &mut buf);
+/// vartyint::write_many(&[10u32, 20, 30], &mut buf);
+/// vartyint::write_i8(-1, &mut buf);
+///
+/// let (count, rest) = vartyint::read_usize(&buf).unwrap();
+/// let (nums, rest) = vartyint::take_n::<u32>(rest, count).unwrap();
+/// assert_eq!(nums, vec![10, 20, 30]);
+/// assert_eq!(vartyint::read_i8(rest).unwrap().0, -1);
+/// ```
+pub fn take_n<T: VarInt>(buf: &[u8], n: usize) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+    // Every value is at least one byte, so don't trust a large `n`
+    let mut vals = Vec::with_capacity(n.min(buf.len()));
+    let mut rest = buf;
+    for i in 0..n {
+        let (num, newrest) = T::read_varint(rest).map_err(|e| match e {
+            VartyIntError::EmptyBuffer if i > 0 => empty_to_not_enough::<T>(e),
+            e => e,
+        })?;
+        vals.push(num);
+        rest = newrest;
+    }
+    Ok((vals, rest))
+}
+
+/// Read a value from this buffer, returning `OutOfRange` if it's not in the range.
+///
+/// ```rust
+/// let buf = [0x0A, 0xAC, 0x02];
+/// let (percent, rest) = vartyint::read_in_range::<u8, _>(&buf, 0..=100).unwrap();
+/// assert_eq!(percent, 10);
+/// assert_eq!(
+///     vartyint::read_in_range::<u32, _>(rest, ..256),
+///     Err(vartyint::VartyIntError::OutOfRange)
+/// );
+/// ```
+pub fn read_in_range<T, R>(buf: &[u8], range: R) -> Result<(T, &[u8]), VartyIntError>
+where
+    T: VarInt + PartialOrd,
+    R: std::ops::RangeBounds<T>,
+{
+    let (val, rest) = T::read_varint(buf)?;
+    if !range.contains(&val) {
+        return Err(VartyIntError::OutOfRange);
+    }
+    Ok((val, rest))
+}
+
+/// Write `val` into `slot`, padded to exactly `slot.len()` bytes with redundant continuation
+/// bytes.
+fn pad_into<T: VarInt>(val: T, slot: &mut [u8]) -> Result<(), VartyIntError> {
+    let bytes = val.as_varint();
+    if slot.is_empty() || bytes.len() > slot.len() {
+        return Err(VartyIntError::DoesNotFit);
+    }
+    slot.fill(0b1000_0000);
+    slot[..bytes.len()].copy_from_slice(&bytes);
+    for byte in slot.iter_mut() {
+        *byte |= 0b1000_0000;
+    }
+    let last = slot.len() - 1;
+    slot[last] &= 0b0111_1111;
+    // Too wide for the type, or not a single varint (e.g. a tuple)
+    if !matches!(T::read_varint(slot), Ok((_, []))) {
+        return Err(VartyIntError::DoesNotFit);
+    }
+    Ok(())
+}
+
+/// Write this value, padded to exactly `width` bytes, to the end of the buffer. Returns the offset
+/// of the value in the buffer, so it can be changed later with `patch_padded`. If the value needs
+/// more than `width` bytes, or `width` is longer than the maximum for the type, `DoesNotFit` is
+/// returned, and nothing is written.
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// // Reserve space for the length, which isn't known yet
+/// let slot = vartyint::write_padded(0u32, 3, &mut buf).unwrap();
+/// buf.extend_from_slice(b"hello");
+/// let len = (buf.len() - slot - 3) as u32;
+/// vartyint::patch_padded(len, &mut buf[slot..(slot + 3)]).unwrap();
+/// assert_eq!(buf[..3], [0x85, 0x80, 0x00]);
+/// assert_eq!(vartyint::read_u32(&buf), Ok((5, &b"hello"[..])));
+/// ```
+pub fn write_padded<T: VarInt>(
+    val: T,
+    width: usize,
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    buf.resize(start + width, 0);
+    pad_into(val, &mut buf[start..]).inspect_err(|_| buf.truncate(start))?;
+    Ok(start)
+}
+
+/// Overwrite this slot (written by `write_padded`) with a new value, padded to the same width.
+pub fn patch_padded<T: VarInt>(val: T, slot: &mut [u8]) -> Result<(), VartyIntError> {
+    let mut new = slot.to_vec();
+    pad_into(val, &mut new)?;
+    slot.copy_from_slice(&new);
+    Ok(())
+}
+
+/// Like write_many, but creates a fresh vec and returns it.
+pub fn write_many_new<T>(nums: &[T]) -> Vec<u8>
+where
+    T: VarInt,
+{
+    let mut buf = vec![];
+    write_many(nums, &mut buf);
+    buf
+}
+/// turn the numbers into a sequence of varint bytes, adding to the end of the buf Vec. Returns the
+/// number of bytes written.
+pub fn write_many<T>(nums: &[T], buf: &mut Vec<u8>) -> usize
+where
+    T: VarInt,
+{
+    let bytes = nums.iter().map(|num| num.write_varint(buf)).sum();
+    metrics::encoded(nums.len(), bytes);
+    bytes
+}
+
+/// Read many different integers from this list of bytes, one after the other. Stops after the
+/// first error.
+pub fn read_many<T>(buf: &[u8]) -> ReadMany<'_, T>
+where
+    T: VarInt,
+{
+    ReadMany {
+        buf,
+        _type: std::marker::PhantomData,
+    }
+}
+
+/// Iterator from `read_many`
+#[derive(Debug, Clone)]
+pub struct ReadMany<'a, T> {
+    buf: &'a [u8],
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<'a, T: VarInt> ReadMany<'a, T> {
+    /// The bytes which haven't been read yet. Empty after an error.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.buf
+    }
+}
+
+impl<T: VarInt> Iterator for ReadMany<'_, T> {
+    type Item = Result<T, VartyIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        match T::read_varint(self.buf) {
+            Err(e) => {
+                self.buf = &[];
+                Some(Err(e))
+            }
+            Ok((num, newbuf)) => {
+                self.buf = newbuf;
+                Some(Ok(num))
+            }
+        }
+    }
+
+    // Every value is at least 1 byte. If there are any bytes left, there's at least 1 more value
+    // (or an error)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::from(!self.buf.is_empty()), Some(self.buf.len()))
+    }
+}
+
+impl<T: VarInt> std::iter::FusedIterator for ReadMany<'_, T> {}
+
+/// Call `f` with every value in this buffer, one after the other. There is no iterator or
+/// allocation. Stops at the first error.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[1u64, 300, 5]);
+/// let mut total = 0;
+/// vartyint::for_each_varint::<u64, _>(&buf, |n| total += n).unwrap();
+/// assert_eq!(total, 306);
+/// ```
+pub fn for_each_varint<T, F>(buf: &[u8], mut f: F) -> Result<(), VartyIntError>
+where
+    T: VarInt,
+    F: FnMut(T),
+{
+    try_for_each_varint(buf, |val| {
+        f(val);
+        Ok(())
+    })
+}
+
+/// Like `for_each_varint`, but `f` can return an error to stop early. Decoding errors are converted
+/// into `E`.
+///
+/// ```rust
+/// use vartyint::VartyIntError;
+/// let buf = vartyint::write_many_new(&[1u64, 300, 5]);
+/// let mut seen = Vec::new();
+/// let res = vartyint::try_for_each_varint::<u64, _, _>(&buf, |n| {
+///     if n > 100 {
+///         return Err(VartyIntError::OutOfRange);
+///     }
+///     seen.push(n);
+///     Ok(())
+/// });
+/// assert_eq!(res, Err(VartyIntError::OutOfRange));
+/// assert_eq!(seen, vec![1]);
+/// ```
+pub fn try_for_each_varint<T, E, F>(mut buf: &[u8], mut f: F) -> Result<(), E>
+where
+    T: VarInt,
+    E: From<VartyIntError>,
+    F: FnMut(T) -> Result<(), E>,
+{
+    let start = buf.len();
+    let mut count = 0;
+    let result = loop {
+        if buf.is_empty() {
+            break Ok(());
+        }
+        let (val, rest) = match T::read_varint(buf) {
+            Ok(res) => res,
+            Err(e) => {
+                metrics::error(&e);
+                break Err(e.into());
+            }
+        };
+        count += 1;
+        buf = rest;
+        if let Err(e) = f(val) {
+            break Err(e);
+        }
+    };
+    metrics::decoded(count, start - buf.len());
+    result
+}
+
+/// Read the values in this buffer which match the predicate. Errors are always returned, and stop
+/// the iterator.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[1u32, 500, 7, 1_000]);
+/// let big = vartyint::filter_values::<u32, _>(&buf, |n| *n > 100)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(big, vec![500, 1_000]);
+/// ```
+pub fn filter_values<'a, T, P>(
+    buf: &'a [u8],
+    mut predicate: P,
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
+where
+    T: VarInt + 'a,
+    P: FnMut(&T) -> bool + 'a,
+{
+    read_many_owned::<T, _>(buf).filter(move |val| match val {
+        Ok(val) => predicate(val),
+        Err(_) => true,
+    })
+}
+
+/// Like `filter_values`, for a buffer written with `write_many_delta`. The predicate is called with
+/// the decoded values, not the deltas.
+pub fn filter_values_delta<'a, T, P>(
+    buf: &'a [u8],
+    mut predicate: P,
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
+where
+    T: DeltaInt + 'a,
+    P: FnMut(&T) -> bool + 'a,
+{
+    read_many_delta_owned::<T, _>(buf).filter(move |val| match val {
+        Ok(val) => predicate(val),
+        Err(_) => true,
+    })
+}
+
+/// Like `filter_values`, but returns the index of each matching value (i.e. 0 for the first value,
+/// 1 for the second, etc.) and its byte offset in the buffer, as well as the value.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[1u32, 500, 7, 1_000]);
+/// let big = vartyint::filter_offsets::<u32, _>(&buf, |n| *n > 100)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(big, vec![(1, 1, 500), (3, 4, 1_000)]);
+/// ```
+pub fn filter_offsets<'a, T, P>(
+    buf: &'a [u8],
+    mut predicate: P,
+) -> impl Iterator<Item = Result<(usize, usize, T), VartyIntError>> + 'a
+where
+    T: VarInt + 'a,
+    P: FnMut(&T) -> bool + 'a,
+{
+    explain::<T>(buf).filter_map(move |expl| match expl {
+        Ok(expl) if predicate(&expl.value) => Some(Ok((expl.index, expl.range.start, expl.value))),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+    })
+}
+
+/// Like `read_many`, but doesn't stop at errors. When a value can't be read, the bytes up to the end
+/// of the next varint are skipped, and their range in the buffer is returned as the error.
+///
+/// ```rust
+/// // 300 doesn't fit in a u8
+/// let buf = [1, 0xAC, 0x02, 3];
+/// let nums = vartyint::read_many_lossy::<u8>(&buf).collect::<Vec<_>>();
+/// assert_eq!(nums, vec![Ok(1), Err(1..3), Ok(3)]);
+/// ```
+pub fn read_many_lossy<T>(
+    buf: &[u8],
+) -> impl Iterator<Item = Result<T, std::ops::Range<usize>>> + '_
+where
+    T: VarInt,
+{
+    let mut rest = buf;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let start = buf.len() - rest.len();
+        match T::read_varint(rest) {
+            Ok((num, newrest)) => {
+                rest = newrest;
+                Some(Ok(num))
+            }
+            Err(_) => {
+                let skip = varint_len(rest).unwrap_or(rest.len());
+                rest = &rest[skip..];
+                Some(Err(start..(start + skip)))
+            }
+        }
+    })
+}
+
+/// Read exactly `len` integers, returning them & the rest of the buffer
+pub(crate) fn read_many_exact<T>(
+    mut buf: &[u8],
+    len: usize,
+) -> Result<(Vec<T>, &[u8]), VartyIntError>
+where
+    T: VarInt,
+{
+    let mut nums = Vec::with_capacity(len.min(buf.len()));
+    for _ in 0..len {
+        let (num, rest) = T::read_varint(buf).map_err(empty_to_not_enough::<T>)?;
+        nums.push(num);
+        buf = rest;
+    }
+    Ok((nums, buf))
+}
+
+/// Read exactly `len` delta encoded integers, returning them & the rest of the buffer. If the
+/// running total overflows, `DeltaOverflow` is returned.
+pub(crate) fn read_many_delta_exact<T: DeltaInt>(
+    buf: &[u8],
+    len: usize,
+) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+    let (mut nums, rest) = read_many_exact::<T>(buf, len)?;
+    let mut last = T::zero();
+    for num in nums.iter_mut() {
+        last = last
+            .checked_add_delta(*num)
+            .ok_or(VartyIntError::DeltaOverflow)?;
+        *num = last;
+    }
+    Ok((nums, rest))
+}
+
+/// Like `read_many`, but the iterator owns the buffer, so it can be returned from functions or
+/// moved to other threads. Stops after the first error.
+///
+/// The buffer can be anything which derefs to bytes, e.g. a `Vec<u8>`, or a reference counted
+/// `Arc<[u8]>` (or `bytes::Bytes`), so one buffer can be shared & decoded on many threads without
+/// copying.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// let buf: Arc<[u8]> = vartyint::write_many_new(&[1u64, 2, 3]).into();
+/// let handles = (0..4)
+///     .map(|_| {
+///         let iter = vartyint::read_many_owned::<u64, _>(Arc::clone(&buf));
+///         std::thread::spawn(move || iter.map(|n| n.unwrap()).sum::<u64>())
+///     })
+///     .collect::<Vec<_>>();
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap(), 6);
+/// }
+/// ```
+pub fn read_many_owned<T, B>(buf: B) -> impl Iterator<Item = Result<T, VartyIntError>>
+where
+    T: VarInt,
+    B: AsRef<[u8]>,
+{
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let buf = buf.as_ref();
+        let rest = &buf[pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        match T::read_varint(rest) {
+            Err(e) => {
+                pos = buf.len();
+                Some(Err(e))
+            }
+            Ok((num, newrest)) => {
+                pos += rest.len() - newrest.len();
+                Some(Ok(num))
+            }
+        }
+    })
+}
+
+/// Like `read_many_delta`, but the iterator owns the buffer, so it can be returned from functions
+/// or moved to other threads. If the running total overflows, `DeltaOverflow` is returned. Stops
+/// after the first error. Like `read_many_owned`, the buffer can be anything which derefs to
+/// bytes, e.g. `Vec<u8>` or `Arc<[u8]>`.
+pub fn read_many_delta_owned<T, B>(buf: B) -> impl Iterator<Item = Result<T, VartyIntError>>
+where
+    T: DeltaInt,
+    B: AsRef<[u8]>,
+{
+    // `None` after an overflow
+    let mut last = Some(T::zero());
+    read_many_owned::<T, B>(buf).map_while(move |num| {
+        let prev = last?;
+        let result = num.and_then(|num| {
+            prev.checked_add_delta(num)
+                .ok_or(VartyIntError::DeltaOverflow)
+        });
+        last = result.as_ref().ok().copied();
+        Some(result)
+    })
+}
+
+/// Like `read_many`, but reading from many separate buffers, one after the other, e.g. the 2
+/// halves of a ring buffer, or scatter/gather reads. Integers can be split across buffers. Stops
+/// after the first error.
+///
+/// ```rust
+/// // 300 is [0xAC, 0x02], split across 2 buffers
+/// let segments: [&[u8]; 3] = [&[1, 0xAC], &[0x02], &[3]];
+/// let nums = vartyint::read_many_segments::<u32>(&segments)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(nums, vec![1, 300, 3]);
+/// ```
+pub fn read_many_segments<'a, T>(
+    segments: &'a [&'a [u8]],
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
+where
+    T: VarInt + 'a,
+{
+    // Current segment, & position in it
+    let mut seg = 0;
+    let mut pos = 0;
+    let mut stitched = Vec::new();
+    std::iter::from_fn(move || {
+        while seg < segments.len() && pos >= segments[seg].len() {
+            seg += 1;
+            pos = 0;
+        }
+        if seg >= segments.len() {
+            return None;
+        }
+        let rest = &segments[seg][pos..];
+        let result = match T::read_varint(rest) {
+            Ok((num, newrest)) => {
+                pos += rest.len() - newrest.len();
+                Ok(num)
+            }
+            Err(e @ VartyIntError::NotEnoughBytes { .. }) => {
+                // This integer continues in the next segment(s). Copy bytes into one buffer until
+                // it can be read.
+                stitched.clear();
+                stitched.extend_from_slice(rest);
+                let mut result = Err(e);
+                for next in segments[(seg + 1)..].iter() {
+                    for chunk in next.chunks(16) {
+                        stitched.extend_from_slice(chunk);
+                        result = T::read_varint(&stitched)
+                            .map(|(num, newrest)| (num, stitched.len() - newrest.len()));
+                        if !matches!(result, Err(VartyIntError::NotEnoughBytes { .. })) {
+                            break;
+                        }
+                    }
+                    if !matches!(result, Err(VartyIntError::NotEnoughBytes { .. })) {
+                        break;
+                    }
+                }
+                result.map(|(num, mut consumed)| {
+                    // Move forward past the consumed bytes
+                    while consumed > 0 {
+                        let available = segments[seg].len() - pos;
+                        if consumed < available {
+                            pos += consumed;
+                            break;
+                        }
+                        consumed -= available;
+                        seg += 1;
+                        pos = 0;
+                    }
+                    num
+                })
+            }
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            seg = segments.len();
+        }
+        Some(result)
+    })
+}
+
+pub fn write_many_delta_new<T>(nums: &[T]) -> Vec<u8>
+where
+    T: VarInt + std::ops::Sub<T, Output = T> + Copy,
+{
+    let mut buf = Vec::with_capacity(nums.len());
+    write_many_delta(nums, &mut buf);
+    buf
+}
+
+/// Write these numbers as the differences between each one. Returns the number of bytes written.
+pub fn write_many_delta<T>(nums: &[T], buf: &mut Vec<u8>) -> usize
+where
+    T: VarInt + std::ops::Sub<T, Output = T>,
+{
+    let mut last: T = T::zero();
+    let mut len = 0;
+    for num in nums {
+        len += (*num - last).write_varint(buf);
+        last = *num
+    }
+    metrics::encoded(nums.len(), len);
+    len
+}
+
+/// Read many different integers from this list of bytes, one after the other, where the integers
+/// are stores as offsets from each other. This is very effecient when a lot of integers are
+/// incrementing
+///
+/// The running total is added with `+`, so (for invalid data) it panics in debug builds & wraps
+/// in release builds if it overflows. Use `read_many_delta_checked` for untrusted data.
+///
+/// Stops after the first error.
+pub fn read_many_delta<T>(buf: &[u8]) -> ReadManyDelta<'_, T>
+where
+    T: VarInt + std::ops::Add<T, Output = T>,
+{
+    ReadManyDelta {
+        inner: read_many(buf),
+        last: T::zero(),
+    }
+}
+
+/// Iterator from `read_many_delta`
+#[derive(Debug, Clone)]
+pub struct ReadManyDelta<'a, T> {
+    inner: ReadMany<'a, T>,
+    last: T,
+}
+
+impl<'a, T: VarInt> ReadManyDelta<'a, T> {
+    /// The bytes which haven't been read yet. Empty after an error.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.inner.remaining()
+    }
+}
+
+impl<T> Iterator for ReadManyDelta<'_, T>
+where
+    T: VarInt + std::ops::Add<T, Output = T>,
+{
+    type Item = Result<T, VartyIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let delta = self.inner.next()?;
+        Some(delta.map(|delta| {
+            self.last = self.last + delta;
+            self.last
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> std::iter::FusedIterator for ReadManyDelta<'_, T> where
+    T: VarInt + std::ops::Add<T, Output = T>
+{
+}
+
+/// Read many different integers from this list of bytes, one after the other, where the integers
+/// are stores as offsets from each other. This is very effecient when a lot of integers are
+/// incrementing. Like `read_many_delta`, but returns the allocated vec for you.
+pub fn read_many_delta_new<'a, T>(buf: &'a [u8]) -> Result<Vec<T>, VartyIntError>
+where
+    T: VarInt + std::ops::Add<T, Output = T> + Copy + 'a,
+{
+    let result = read_many_delta(buf).collect::<Result<Vec<_>, _>>();
+    match &result {
+        Ok(nums) => metrics::decoded(nums.len(), buf.len()),
+        Err(e) => metrics::error(e),
+    }
+    result
+}
+
+/// Integers which can be delta encoded with overflow checks, or with wrapping arithmetic
+pub trait DeltaInt: VarInt {
+    /// The signed type of the same size, for wrapping differences
+    type Wrapping: VarInt + PartialEq;
+
+    /// `self + delta`, or `None` if it overflows
+    fn checked_add_delta(self, delta: Self) -> Option<Self>;
+
+    /// The smallest difference from `prev` to `self`, if values wrap around
+    fn wrapping_delta(self, prev: Self) -> Self::Wrapping;
+
+    /// `self + delta`, wrapping around
+    fn wrapping_add_delta(self, delta: Self::Wrapping) -> Self;
+}
+
+macro_rules! delta_int_impl {
+    ( $($type:ty => $signed:ty),* ) => {
+        $(
+            impl DeltaInt for $type {
+                type Wrapping = $signed;
+
+                fn checked_add_delta(self, delta: $type) -> Option<$type> {
+                    self.checked_add(delta)
+                }
+                fn wrapping_delta(self, prev: $type) -> $signed {
+                    self.wrapping_sub(prev) as $signed
+                }
+                fn wrapping_add_delta(self, delta: $signed) -> $type {
+                    self.wrapping_add(delta as $type)
+                }
+            }
+        )*
+    };
+}
+
+delta_int_impl!(u8 => i8, u16 => i16, u32 => i32, u64 => i64, u128 => i128, usize => isize);
+delta_int_impl!(i8 => i8, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => isize);
+
+/// Like `read_many_delta`, but if the running total overflows, `DeltaOverflow` is returned
+/// (instead of panicking or wrapping). Stops after the first error.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[250u8, 10]);
+/// let nums = vartyint::read_many_delta_checked::<u8>(&buf).collect::<Vec<_>>();
+/// assert_eq!(nums, vec![Ok(250), Err(vartyint::VartyIntError::DeltaOverflow)]);
+/// ```
+pub fn read_many_delta_checked<'a, T: DeltaInt + 'a>(
+    buf: &'a [u8],
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a {
+    let mut buf = buf;
+    let mut last = T::zero();
+    std::iter::from_fn(move || {
+        if buf.is_empty() {
+            return None;
+        }
+        let result = T::read_varint(buf).and_then(|(num, newbuf)| {
+            buf = newbuf;
+            last = last
+                .checked_add_delta(num)
+                .ok_or(VartyIntError::DeltaOverflow)?;
+            Ok(last)
+        });
+        if result.is_err() {
+            buf = &[];
+        }
+        Some(result)
+    })
+}
+
+/// Like `read_many_delta_checked`, but returns the allocated vec for you.
+pub fn read_many_delta_checked_new<T: DeltaInt>(buf: &[u8]) -> Result<Vec<T>, VartyIntError> {
+    let result = read_many_delta_checked(buf).collect::<Result<Vec<_>, _>>();
+    match &result {
+        Ok(nums) => metrics::decoded(nums.len(), buf.len()),
+        Err(e) => metrics::error(e),
+    }
+    result
+}
+
+/// Write these numbers as the differences between each one, treating them as wrapping around
+/// (e.g. counters which go from `u32::MAX` back to 0). Each difference is the shortest way around,
+/// so a counter which wraps is still small, & nothing can overflow. Returns the number of bytes
+/// written.
+///
+/// ```rust
+/// let counters = [u32::MAX - 1, u32::MAX, 0, 1];
+/// let buf = vartyint::write_many_delta_wrapping_new(&counters);
+/// // -2, then +1 three times
+/// assert_eq!(buf, vec![3, 2, 2, 2]);
+/// assert_eq!(vartyint::read_many_delta_wrapping_new::<u32>(&buf), Ok(counters.to_vec()));
+/// ```
+pub fn write_many_delta_wrapping<T: DeltaInt>(nums: &[T], buf: &mut Vec<u8>) -> usize {
+    let mut last = T::zero();
+    let mut len = 0;
+    for num in nums {
+        len += num.wrapping_delta(last).write_varint(buf);
+        last = *num;
+    }
+    metrics::encoded(nums.len(), len);
+    len
+}
+
+/// Like `write_many_delta_wrapping`, but returns a new Vec
+pub fn write_many_delta_wrapping_new<T: DeltaInt>(nums: &[T]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(nums.len());
+    write_many_delta_wrapping(nums, &mut buf);
+    buf
+}
+
+/// Read integers written by `write_many_delta_wrapping`. Stops after the first error.
+pub fn read_many_delta_wrapping<'a, T: DeltaInt + 'a>(
+    buf: &'a [u8],
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a {
+    let mut buf = buf;
+    let mut last = T::zero();
+    std::iter::from_fn(move || {
+        if buf.is_empty() {
+            return None;
+        }
+        match T::Wrapping::read_varint(buf) {
+            Err(e) => {
+                buf = &[];
+                Some(Err(e))
+            }
+            Ok((delta, newbuf)) => {
+                buf = newbuf;
+                last = last.wrapping_add_delta(delta);
+                Some(Ok(last))
+            }
+        }
+    })
+}
+
+/// Like `read_many_delta_wrapping`, but returns the allocated vec for you.
+pub fn read_many_delta_wrapping_new<T: DeltaInt>(buf: &[u8]) -> Result<Vec<T>, VartyIntError> {
+    let result = read_many_delta_wrapping(buf).collect::<Result<Vec<_>, _>>();
+    match &result {
+        Ok(nums) => metrics::decoded(nums.len(), buf.len()),
+        Err(e) => metrics::error(e),
+    }
+    result
+}
+
+/// Signed integers. Differences between them can be negative, so are zigzag encoded. Used by
+/// `write_deltas` & `read_deltas`.
+pub trait VarIntSigned: DeltaInt + ZigZag + Ord {
+    /// `self - prev`, or `None` if it overflows
+    fn checked_delta(self, prev: Self) -> Option<Self>;
+}
+
+/// Unsigned integers. In sorted data, the differences (gaps) between them are never negative, so
+/// are written as they are. Used by `write_gaps` & `read_gaps`.
+pub trait VarIntUnsigned: DeltaInt + Ord {
+    /// `self - prev`, or `None` if `prev` is bigger
+    fn checked_gap(self, prev: Self) -> Option<Self>;
+}
+
+macro_rules! signed_unsigned_impl {
+    ( $trait:ident, $fn:ident, $($type:ty),* ) => {
+        $(
+            impl $trait for $type {
+                fn $fn(self, prev: $type) -> Option<$type> {
+                    self.checked_sub(prev)
+                }
+            }
+        )*
+    };
+}
+
+signed_unsigned_impl!(VarIntSigned, checked_delta, i8, i16, i32, i64, i128, isize);
+signed_unsigned_impl!(VarIntUnsigned, checked_gap, u8, u16, u32, u64, u128, usize);
+
+/// Write these signed integers, in any order, as the differences between each one. Like
+/// `write_many_delta`, but if a difference doesn't fit in the type, `DeltaOverflow` is returned &
+/// `buf` is unchanged. Returns the number of bytes written.
+///
+/// Unsigned integers can't be used (their differences can be negative), use `write_gaps` for
+/// sorted unsigned integers.
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// vartyint::write_deltas(&[10i32, 5, 7], &mut buf).unwrap();
+/// assert_eq!(buf, vec![20, 9, 4]);
+/// assert_eq!(vartyint::read_deltas::<i32>(&buf), Ok(vec![10, 5, 7]));
+/// assert_eq!(
+///     vartyint::write_deltas(&[i8::MIN, i8::MAX], &mut buf),
+///     Err(vartyint::VartyIntError::DeltaOverflow)
+/// );
+/// ```
+pub fn write_deltas<T: VarIntSigned>(
+    nums: &[T],
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    let mut last = T::zero();
+    for num in nums {
+        let Some(delta) = num.checked_delta(last) else {
+            buf.truncate(start);
+            return Err(VartyIntError::DeltaOverflow);
+        };
+        delta.write_varint(buf);
+        last = *num;
+    }
+    Ok(buf.len() - start)
+}
+
+/// Read all the integers written by `write_deltas`. If the running total overflows,
+/// `DeltaOverflow` is returned.
+pub fn read_deltas<T: VarIntSigned>(buf: &[u8]) -> Result<Vec<T>, VartyIntError> {
+    read_many_delta_checked_new(buf)
+}
+
+/// Write these sorted unsigned integers as the gaps between each one. Like `write_many_delta`,
+/// but if they aren't sorted (smallest first), `OutOfRange` is returned & `buf` is unchanged.
+/// Returns the number of bytes written.
+///
+/// Signed integers can't be used, use `write_deltas`.
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// vartyint::write_gaps(&[10u32, 15, 15, 300], &mut buf).unwrap();
+/// assert_eq!(buf, vec![10, 5, 0, 0x9D, 0x02]);
+/// assert_eq!(vartyint::read_gaps::<u32>(&buf), Ok(vec![10, 15, 15, 300]));
+/// assert_eq!(
+///     vartyint::write_gaps(&[2u32, 1], &mut buf),
+///     Err(vartyint::VartyIntError::OutOfRange)
+/// );
+/// ```
+pub fn write_gaps<T: VarIntUnsigned>(
+    nums: &[T],
+    buf: &mut Vec<u8>,
+) -> Result<usize, VartyIntError> {
+    let start = buf.len();
+    let mut last = T::zero();
+    for num in nums {
+        let Some(gap) = num.checked_gap(last) else {
+            buf.truncate(start);
+            return Err(VartyIntError::OutOfRange);
+        };
+        gap.write_varint(buf);
+        last = *num;
+    }
+    Ok(buf.len() - start)
+}
+
+/// Read all the integers written by `write_gaps`. If the running total overflows,
+/// `DeltaOverflow` is returned.
+pub fn read_gaps<T: VarIntUnsigned>(buf: &[u8]) -> Result<Vec<T>, VartyIntError> {
+    read_many_delta_checked_new(buf)
+}
+
+/// Like `read_many_delta_new`, but decoded on `threads` threads, for very large buffers.
+///
+/// The buffer is split into chunks on varint boundaries. Each chunk is decoded, relative to its
+/// start, in parallel, then the total of each previous chunk is added, also in parallel. If the
+/// running total overflows, it wraps around (like `read_many_delta_new` in release builds), since
+/// the total of one chunk can overflow even when the real total doesn't.
+///
+/// ```rust
+/// let nums = (0..100_000i64).map(|i| i * 3).collect::<Vec<_>>();
+/// let buf = vartyint::write_many_delta_new(&nums);
+/// assert_eq!(vartyint::read_many_delta_parallel::<i64>(&buf, 4), Ok(nums));
+/// ```
+pub fn read_many_delta_parallel<T>(buf: &[u8], threads: usize) -> Result<Vec<T>, VartyIntError>
+where
+    T: DeltaInt + Send + Sync,
+{
+    // `a + b`, wrapping around
+    fn wrapping_add<T: DeltaInt>(a: T, b: T) -> T {
+        a.wrapping_add_delta(b.wrapping_delta(T::zero()))
+    }
+
+    // Decode a chunk as if it started at zero
+    fn decode<T: DeltaInt>(chunk: &[u8]) -> Result<Vec<T>, VartyIntError> {
+        let mut last = T::zero();
+        read_many::<T>(chunk)
+            .map(|num| {
+                last = wrapping_add(last, num?);
+                Ok(last)
+            })
+            .collect()
+    }
+
+    let threads = threads.max(1);
+
+    // Split after the last byte of a varint (i.e. top bit unset)
+    let mut chunks = Vec::with_capacity(threads);
+    let mut start = 0;
+    for i in 1..threads {
+        let mut end = (buf.len() * i / threads).max(start + 1);
+        while end < buf.len() && buf[end - 1] >> 7 == 1 {
+            end += 1;
+        }
+        if end >= buf.len() {
+            break;
+        }
+        chunks.push(&buf[start..end]);
+        start = end;
+    }
+    chunks.push(&buf[start..]);
+
+    let result = if chunks.len() == 1 {
+        decode(buf)
+    } else {
+        std::thread::scope(|scope| {
+            let handles = chunks
+                .iter()
+                .map(|chunk| scope.spawn(move || decode::<T>(chunk)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map(|mut decoded| {
+            // Then add the last value of the previous chunks
+            let mut bases = Vec::with_capacity(decoded.len());
+            let mut base = T::zero();
+            for chunk in decoded.iter() {
+                bases.push(base);
+                if let Some(last) = chunk.last() {
+                    base = wrapping_add(base, *last);
+                }
+            }
+            std::thread::scope(|scope| {
+                for (chunk, base) in decoded.iter_mut().zip(bases).skip(1) {
+                    scope.spawn(move || {
+                        for num in chunk.iter_mut() {
+                            *num = wrapping_add(base, *num);
+                        }
+                    });
+                }
+            });
+            decoded.concat()
+        })
+    };
+    match &result {
+        Ok(nums) => metrics::decoded(nums.len(), buf.len()),
+        Err(e) => metrics::error(e),
+    }
+    result
+}
+
+macro_rules! transcode {
+    ( $to_varint:ident, $from_varint:ident, $type:ty, $write:ident, $read:ident ) => {
+        /// Convert a packed array of little endian fixed width integers into varints, adding to the
+        /// end of the buf Vec. A trailing partial integer returns `NotEnoughBytes`.
+        pub fn $to_varint(input: &[u8], buf: &mut Vec<u8>) -> Result<(), VartyIntError> {
+            const WIDTH: usize = std::mem::size_of::<$type>();
+            let chunks = input.chunks_exact(WIDTH);
+            if !chunks.remainder().is_empty() {
+                return Err(VartyIntError::not_enough(chunks.remainder().len(), WIDTH));
+            }
+            buf.reserve(input.len() / WIDTH);
+            for chunk in chunks {
+                $write(<$type>::from_le_bytes(chunk.try_into().unwrap()), buf);
+            }
+            Ok(())
+        }
+
+        /// Convert a stream of varints into a packed array of little endian fixed width integers,
+        /// adding to the end of the buf Vec.
+        pub fn $from_varint(mut input: &[u8], buf: &mut Vec<u8>) -> Result<(), VartyIntError> {
+            buf.reserve(input.len() * std::mem::size_of::<$type>());
+            while !input.is_empty() {
+                let (num, rest) = $read(input)?;
+                buf.extend_from_slice(&num.to_le_bytes());
+                input = rest;
+            }
+            Ok(())
+        }
+    };
+}
+
+transcode!(
+    transcode_le_u32_to_varint,
+    transcode_varint_to_le_u32,
+    u32,
+    write_u32,
+    read_u32
+);
+transcode!(
+    transcode_le_u64_to_varint,
+    transcode_varint_to_le_u64,
+    u64,
+    write_u64,
+    read_u64
+);
+
+/// Read every value in `input`, pass it to `f`, & write the result to the end of `output`,
+/// returning the number of values. The values are never all in memory at once. On error,
+/// `output` is unchanged.
+///
+/// ```rust
+/// let input = vartyint::write_many_new(&[1u32, 2, 3]);
+/// let mut output = Vec::new();
+/// vartyint::transform(&input, |id: u32| id as i64 * -10, &mut output).unwrap();
+/// assert_eq!(output, vartyint::write_many_new(&[-10i64, -20, -30]));
+/// ```
+pub fn transform<T, U, F>(
+    mut input: &[u8],
+    mut f: F,
+    output: &mut Vec<u8>,
+) -> Result<usize, VartyIntError>
+where
+    T: VarInt,
+    U: VarInt,
+    F: FnMut(T) -> U,
+{
+    let start = output.len();
+    let mut count = 0;
+    while !input.is_empty() {
+        match T::read_varint(input) {
+            Ok((val, rest)) => {
+                f(val).write_varint(output);
+                input = rest;
+                count += 1;
+            }
+            Err(e) => {
+                output.truncate(start);
+                return Err(e);
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Error from `transcode`, with where it happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranscodeError {
+    /// Position of the value, i.e. 0 for the first value, 1 for the second etc.
+    pub index: usize,
+    /// Offset of the value in the input, in bytes
+    pub offset: usize,
+    /// What went wrong. `OutOfRange` if the value doesn't fit in the new type.
+    pub error: VartyIntError,
+}
+
+impl std::fmt::Display for TranscodeError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            fmt,
+            "{} at value {} (byte {})",
+            self.error, self.index, self.offset
+        )
+    }
+}
+
+impl std::error::Error for TranscodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Read every value in `input` as a `T`, convert it to a `U`, & write it to the end of `output`,
+/// returning the number of values. E.g. to widen `u32`s to `u64`s, or narrow `i64`s to `i32`s.
+/// If a value doesn't fit in a `U`, the error is `OutOfRange`, with the index of that value. On
+/// error, `output` is unchanged.
+///
+/// ```rust
+/// let input = vartyint::write_many_new(&[1i64, -300, 70_000]);
+/// let mut output = Vec::new();
+/// let err = vartyint::transcode::<i64, i16>(&input, &mut output).unwrap_err();
+/// assert_eq!((err.index, err.offset), (2, 3));
+/// assert_eq!(err.error, vartyint::VartyIntError::OutOfRange);
+///
+/// assert_eq!(vartyint::transcode::<i64, i32>(&input, &mut output), Ok(3));
+/// let nums = vartyint::read_many::<i32>(&output).collect::<Result<Vec<_>, _>>();
+/// assert_eq!(nums, Ok(vec![1, -300, 70_000]));
+/// ```
+pub fn transcode<T, U>(input: &[u8], output: &mut Vec<u8>) -> Result<usize, TranscodeError>
+where
+    T: VarInt,
+    U: VarInt + TryFrom<T>,
+{
+    let start = output.len();
+    let mut rest = input;
+    let mut index = 0;
+    while !rest.is_empty() {
+        let result = T::read_varint(rest).and_then(|(val, newrest)| {
+            let val = U::try_from(val).map_err(|_| VartyIntError::OutOfRange)?;
+            Ok((val, newrest))
+        });
+        match result {
+            Ok((val, newrest)) => {
+                val.write_varint(output);
+                rest = newrest;
+                index += 1;
+            }
+            Err(error) => {
+                output.truncate(start);
+                return Err(TranscodeError {
+                    index,
+                    offset: input.len() - rest.len(),
+                    error,
+                });
+            }
+        }
+    }
+    Ok(index)
+}
+
+/// Like `transform`, but read from a `std::io::Read` & write to a `std::io::Write`, a buffer at a
+/// time, so files bigger than memory can be transformed. Returns the number of values. The
+/// writer is flushed at the end.
+///
+/// ```rust
+/// let input = vartyint::write_many_new(&[1u64, 2, 3]);
+/// let mut output = Vec::new();
+/// let count = vartyint::transform_io(&input[..], |id: u64| id + 100, &mut output).unwrap();
+/// assert_eq!(count, 3);
+/// assert_eq!(output, vartyint::write_many_new(&[101u64, 102, 103]));
+/// ```
+pub fn transform_io<T, U, F, R, W>(reader: R, mut f: F, writer: W) -> Result<u64, VartyIntReadError>
+where
+    T: VarInt,
+    U: VarInt,
+    F: FnMut(T) -> U,
+    R: std::io::Read,
+    W: std::io::Write,
+{
+    let mut reader = VarIntReader::new(reader);
+    let mut writer = VarIntWriter::new(writer);
+    let mut count = 0;
+    while let Some(val) = reader.read_value::<T>()? {
+        writer.write_value(f(val))?;
+        count += 1;
+    }
+    std::io::Write::flush(&mut writer)?;
+    Ok(count)
+}
+
+/// These bytes as lowercase hex, separated by spaces, e.g. `"ac 02"`. Useful for logs & bug
+/// reports.
+///
+/// ```rust
+/// let buf = vartyint::write_many_new(&[1u32, 300]);
+/// assert_eq!(vartyint::to_hex(&buf), "01 ac 02");
+/// assert_eq!(vartyint::decode_hex_varints::<u32>("01 ac 02"), Ok(vec![1, 300]));
+/// ```
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The bytes of this hex string, e.g. from `to_hex`. Upper & lower case are allowed, &
+/// whitespace is ignored. Anything else, or an odd number of digits, is `InvalidCharacter`.
+pub fn from_hex(text: &str) -> Result<Vec<u8>, VartyIntError> {
+    let mut digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).ok_or(VartyIntError::InvalidCharacter));
+    let mut bytes = Vec::with_capacity(text.len() / 2);
+    while let Some(high) = digits.next() {
+        let low = digits.next().ok_or(VartyIntError::InvalidCharacter)?;
+        bytes.push((high? << 4 | low?) as u8);
+    }
+    Ok(bytes)
+}
+
+/// Decode all the integers in this hex string (see `from_hex`), e.g. pasted from a bug report.
+pub fn decode_hex_varints<T: VarInt>(text: &str) -> Result<Vec<T>, VartyIntError> {
+    read_many(&from_hex(text)?).collect()
+}
+
+/// Description of one varint in a buffer, as returned by `explain`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation<'a, T> {
+    /// Position of this value in the buffer, i.e. 0 for the first varint, 1 for the second etc.
+    pub index: usize,
+    /// Where in the buffer this varint is
+    pub range: std::ops::Range<usize>,
+    /// The raw bytes of this varint
+    pub bytes: &'a [u8],
+    /// The decoded value
+    pub value: T,
+    /// True iff this is the shortest possible encoding of `value`
+    pub minimal: bool,
+}
+
+impl<T> Explanation<'_, T> {
+    /// The continuation bit of each byte, as a string of `1`s & `0`s, e.g. `"110"`
+    pub fn continuation_pattern(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|b| if b >> 7 == 1 { '1' } else { '0' })
+            .collect()
+    }
+}
+
+/// Decode the integers in this buffer, one after the other, returning a description of each one.
+/// Useful for debugging or pretty printing.
+///
+/// ```rust
+/// let expl = vartyint::explain::<u32>(&[0x01, 0xAC, 0x02])
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(expl[1].value, 300);
+/// assert_eq!(expl[1].range, 1..3);
+/// assert_eq!(expl[1].continuation_pattern(), "10");
+/// assert!(expl[1].minimal);
+/// ```
+pub fn explain<T>(
+    buf: &[u8],
+) -> impl Iterator<Item = Result<Explanation<'_, T>, VartyIntError>> + '_
+where
+    T: VarInt,
+{
+    let mut rest = buf;
+    let mut index = 0;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        match T::read_varint(rest) {
+            Err(e) => {
+                rest = &[];
+                Some(Err(e))
+            }
+            Ok((value, newrest)) => {
+                let start = buf.len() - rest.len();
+                let len = rest.len() - newrest.len();
+                let explanation = Explanation {
+                    index,
+                    range: start..(start + len),
+                    bytes: &rest[..len],
+                    value,
+                    minimal: value.as_varint().len() == len,
+                };
+                rest = newrest;
+                index += 1;
+                Some(Ok(explanation))
+            }
+        }
+    })
+}
+
+/// Something which can be written as, and read from, a sequence of varints.
+///
+/// Every `VarInt` is a `VarIntCodec`. A `Vec` is written as its length followed by the items.
+/// With the `derive` feature, structs can `#[derive(VarIntCodec)]`, which writes each field in
+/// order. A field can have `#[varint(delta)]` (for a `Vec` of integers, delta encode it) or
+/// `#[varint(fixed)]` (for an integer, write it as fixed width little endian bytes). C-like enums
+/// can also `#[derive(VarIntCodec)]`, which writes the discriminant as a varint of the
+/// `#[repr(...)]` type.
+///
+/// ```rust
+/// # #[cfg(feature = "derive")] {
+/// use vartyint::VarIntCodec;
+///
+/// #[derive(VarIntCodec, Debug, PartialEq)]
+/// struct Node {
+///     id: u64,
+///     #[varint(fixed)]
+///     magic: u32,
+///     #[varint(delta)]
+///     refs: Vec<i64>,
+/// }
+///
+/// let node = Node { id: 1, magic: 0xFEED, refs: vec![100, 101, 102] };
+/// let mut buf = Vec::new();
+/// node.write_codec(&mut buf);
+/// assert_eq!(Node::read_codec(&buf), Ok((node, &[] as &[u8])));
+/// # }
+/// ```
+pub trait VarIntCodec: Sized {
+    /// Write this to the end of the buf Vec, returning the number of bytes written
+    fn write_codec(&self, buf: &mut Vec<u8>) -> usize;
+
+    /// Read one of these from the start of the buffer, returning it & the rest of the buffer.
+    fn read_codec(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError>;
+}
+
+#[cfg(feature = "derive")]
+pub use vartyint_derive::VarIntCodec;
+
+impl<T> VarIntCodec for T
+where
+    T: VarInt,
+{
+    fn write_codec(&self, buf: &mut Vec<u8>) -> usize {
+        self.write_varint(buf)
+    }
+    fn read_codec(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        T::read_varint(buf)
+    }
+}
+
+impl<T> VarIntCodec for Vec<T>
+where
+    T: VarIntCodec,
+{
+    fn write_codec(&self, buf: &mut Vec<u8>) -> usize {
+        let mut len = write_u64(self.len() as u64, buf);
+        for item in self.iter() {
+            len += item.write_codec(buf);
+        }
+        len
+    }
+    fn read_codec(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        let (len, mut buf) = read_u64(buf)?;
+        // Don't trust the length for the allocation, every item is at least 1 byte.
+        let mut items = Vec::with_capacity((len as usize).min(buf.len()));
+        for _ in 0..len {
+            let (item, rest) = T::read_codec(buf).map_err(__derive::not_first)?;
+            items.push(item);
+            buf = rest;
+        }
+        Ok((items, buf))
+    }
+}
+
+/// Used by the code generated by `#[derive(VarIntCodec)]`. Not public API.
+#[doc(hidden)]
+pub mod __derive {
+    use super::*;
+
+    pub trait FixedWidth: Sized {
+        fn write_fixed(&self, buf: &mut Vec<u8>) -> usize;
+        fn read_fixed(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError>;
+    }
+
+    macro_rules! fixed_width {
+        ( $( $type:ty ),* ) => {
+            $(
+                impl FixedWidth for $type {
+                    fn write_fixed(&self, buf: &mut Vec<u8>) -> usize {
+                        buf.extend_from_slice(&self.to_le_bytes());
+                        std::mem::size_of::<$type>()
+                    }
+                    fn read_fixed(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+                        const WIDTH: usize = std::mem::size_of::<$type>();
+                        if buf.is_empty() {
+                            return Err(VartyIntError::EmptyBuffer);
+                        }
+                        if buf.len() < WIDTH {
+                            return Err(VartyIntError::not_enough(buf.len(), WIDTH));
+                        }
+                        let (bytes, rest) = buf.split_at(WIDTH);
+                        Ok((<$type>::from_le_bytes(bytes.try_into().unwrap()), rest))
+                    }
+                }
+            )*
+        };
+    }
+
+    fixed_width!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+    /// `EmptyBuffer` from reading any field after the first is `NotEnoughBytes`, since the data
+    /// ends part way through the struct. How many more bytes are needed isn't known.
+    pub fn not_first(e: VartyIntError) -> VartyIntError {
+        match e {
+            VartyIntError::EmptyBuffer => VartyIntError::NotEnoughBytes {
+                read: 0,
+                max_more: None,
+            },
+            e => e,
+        }
+    }
+
+    pub fn write_fixed<T: FixedWidth>(val: &T, buf: &mut Vec<u8>) -> usize {
+        val.write_fixed(buf)
+    }
+
+    pub fn read_fixed<T: FixedWidth>(buf: &[u8]) -> Result<(T, &[u8]), VartyIntError> {
+        T::read_fixed(buf)
+    }
+
+    pub fn write_delta<T>(nums: &[T], buf: &mut Vec<u8>) -> usize
+    where
+        T: VarInt + std::ops::Sub<T, Output = T>,
+    {
+        let start = buf.len();
+        write_u64(nums.len() as u64, buf);
+        write_many_delta(nums, buf);
+        buf.len() - start
+    }
+
+    pub fn read_delta<T: DeltaInt>(buf: &[u8]) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+        let (len, buf) = read_u64(buf)?;
+        read_many_delta_exact(buf, len as usize)
+    }
+}
+
+/// Write a sorted map to the end of the buf Vec. The number of entries is written, then the delta
+/// encoded keys, then the values. Returns the number of bytes written.
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+/// let counts = BTreeMap::from([(1_000u64, 5u64), (1_001, 2), (1_010, 7)]);
+/// let mut buf = Vec::new();
+/// assert_eq!(vartyint::write_map(&counts, &mut buf), 8);
+/// assert_eq!(buf, vec![3, 0xE8, 0x07, 1, 9, 5, 2, 7]);
+/// assert_eq!(vartyint::read_map(&buf), Ok((counts, &[] as &[u8])));
+/// ```
+pub fn write_map<K, V>(map: &std::collections::BTreeMap<K, V>, buf: &mut Vec<u8>) -> usize
+where
+    K: VarInt + std::ops::Sub<K, Output = K>,
+    V: VarInt,
+{
+    let mut len = write_usize(map.len(), buf);
+    let mut last = K::zero();
+    for key in map.keys() {
+        len += (*key - last).write_varint(buf);
+        last = *key;
+    }
+    for value in map.values() {
+        len += value.write_varint(buf);
+    }
+    len
+}
+
+/// Read a map written by `write_map`, returning it & the rest of the buffer. If the keys
+/// overflow, `DeltaOverflow` is returned.
+pub fn read_map<K, V>(
+    buf: &[u8],
+) -> Result<(std::collections::BTreeMap<K, V>, &[u8]), VartyIntError>
+where
+    K: DeltaInt + Ord,
+    V: VarInt,
+{
+    let (len, buf) = read_usize(buf)?;
+    let (keys, buf) = read_many_delta_exact::<K>(buf, len)?;
+    let (values, buf) = read_many_exact::<V>(buf, len)?;
+    Ok((keys.into_iter().zip(values).collect(), buf))
+}
+
+/// Write this payload as a record, i.e. its length (as a varint) followed by the bytes. Returns
+/// the number of bytes written.
+pub fn write_record(payload: &[u8], buf: &mut Vec<u8>) -> usize {
+    let len = write_usize(payload.len(), buf);
+    buf.extend_from_slice(payload);
+    len + payload.len()
+}
+
+/// Read the records (written by `write_record`) in this buffer, returning the offset of each
+/// record in the buffer, and its payload. The payload isn't interpreted.
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// vartyint::write_record(b"hello", &mut buf);
+/// vartyint::write_record(b"", &mut buf);
+/// vartyint::write_record(&[0xFF; 200], &mut buf);
+/// let records = vartyint::read_records(&buf)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(records[0], (0, &b"hello"[..]));
+/// assert_eq!(records[1], (6, &b""[..]));
+/// assert_eq!(records[2].0, 7);
+/// assert_eq!(records[2].1.len(), 200);
+/// ```
+pub fn read_records(buf: &[u8]) -> impl Iterator<Item = Result<(usize, &[u8]), VartyIntError>> {
+    let mut rest = buf;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let offset = buf.len() - rest.len();
+        let result = read_usize(rest).and_then(|(len, newrest)| {
+            if newrest.len() < len {
+                return Err(VartyIntError::not_enough(newrest.len(), len));
+            }
+            let (payload, newrest) = newrest.split_at(len);
+            Ok((payload, newrest))
+        });
+        match result {
+            Ok((payload, newrest)) => {
+                rest = newrest;
+                Some(Ok((offset, payload)))
+            }
+            Err(e) => {
+                rest = &[];
+                Some(Err(e))
+            }
+        }
+    })
 }
@@ -0,0 +1,63 @@
+//! Hooks to count how much is encoded & decoded, e.g. to export to Prometheus or statsd.
+//!
+//! Install a `Metrics` once, with `set_metrics`. It's called by the bulk functions: `write_many`,
+//! `write_many_delta`, `for_each_varint`, `try_for_each_varint` (and so the `aggregate`
+//! functions), `read_many_delta_new` & `read_many_delta_parallel`. Single value functions &
+//! iterators don't call it.
+//!
+//! ```rust
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use vartyint::metrics::{set_metrics, Metrics};
+//!
+//! struct Counter(AtomicUsize);
+//! impl Metrics for Counter {
+//!     fn encoded(&self, _values: usize, bytes: usize) {
+//!         self.0.fetch_add(bytes, Ordering::Relaxed);
+//!     }
+//! }
+//! static COUNTER: Counter = Counter(AtomicUsize::new(0));
+//!
+//! set_metrics(&COUNTER).ok().unwrap();
+//! vartyint::write_many_new(&[1u32, 300]);
+//! assert_eq!(COUNTER.0.load(Ordering::Relaxed), 3);
+//! ```
+use crate::*;
+use std::sync::OnceLock;
+
+/// Called when values are encoded or decoded. Every method does nothing by default.
+pub trait Metrics: Send + Sync {
+    /// `values` values were written, in `bytes` bytes
+    fn encoded(&self, _values: usize, _bytes: usize) {}
+
+    /// `values` values were read, from `bytes` bytes
+    fn decoded(&self, _values: usize, _bytes: usize) {}
+
+    /// Decoding failed with this error
+    fn error(&self, _error: &VartyIntError) {}
+}
+
+static METRICS: OnceLock<&'static dyn Metrics> = OnceLock::new();
+
+/// Install this as the global `Metrics`. It can only be set once; if it's already set, `metrics`
+/// is returned as the error.
+pub fn set_metrics(metrics: &'static dyn Metrics) -> Result<(), &'static dyn Metrics> {
+    METRICS.set(metrics)
+}
+
+pub(crate) fn encoded(values: usize, bytes: usize) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.encoded(values, bytes);
+    }
+}
+
+pub(crate) fn decoded(values: usize, bytes: usize) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.decoded(values, bytes);
+    }
+}
+
+pub(crate) fn error(error: &VartyIntError) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.error(error);
+    }
+}
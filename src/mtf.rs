@@ -0,0 +1,180 @@
+//! Move-to-front transform. Each value is replaced by its position in a list of the recently seen
+//! values, & then moved to the front of that list. Streams where the same few values are repeated
+//! close together (e.g. IDs) become lots of small numbers, which are 1 byte varints.
+//!
+//! ```rust
+//! use vartyint::mtf;
+//! let ids = [1_000_000u64, 2_000_000, 1_000_000, 1_000_000, 2_000_000];
+//! let mut buf = Vec::new();
+//! mtf::write_mtf(&ids, &mut buf);
+//! assert_eq!(buf, vec![5, 127, 0, 0xC0, 0x84, 0x3D, 0, 0x80, 0x89, 0x7A, 2, 1, 2]);
+//!
+//! let (decoded, rest) = mtf::read_mtf::<u64>(&buf).unwrap();
+//! assert_eq!(decoded, ids);
+//! assert!(rest.is_empty());
+//! ```
+//!
+//! `MoveToFront` can be used on its own, as a stage before any other encoding. With
+//! `write_mtf`, the number of values & the `max_len` of the list are written, then a value which
+//! has been seen before is written as its position + 1, & a new value is written as `0` followed
+//! by the value.
+use crate::*;
+
+/// One value after the transform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MtfToken<T> {
+    /// This value was seen before, & is at this position in the list
+    Recent(usize),
+    /// This value isn't in the list
+    New(T),
+}
+
+impl<T: VarInt> MtfToken<T> {
+    /// Write this token to the end of `buf`, returning the number of bytes written
+    pub fn write(&self, buf: &mut Vec<u8>) -> usize {
+        match self {
+            MtfToken::Recent(idx) => (idx + 1).write_varint(buf),
+            MtfToken::New(val) => 0usize.write_varint(buf) + val.write_varint(buf),
+        }
+    }
+
+    /// Read one token from the start of this buffer, returning it & the rest of the buffer
+    pub fn read(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        match usize::read_varint(buf)? {
+            (0, rest) => {
+                let (val, rest) = T::read_varint(rest).map_err(empty_to_not_enough::<T>)?;
+                Ok((MtfToken::New(val), rest))
+            }
+            (idx, rest) => Ok((MtfToken::Recent(idx - 1), rest)),
+        }
+    }
+}
+
+/// The list of recently seen values. The encoder & decoder each need one, with the same
+/// `max_len`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveToFront<T> {
+    recent: Vec<T>,
+    max_len: usize,
+}
+
+impl<T> Default for MoveToFront<T> {
+    fn default() -> Self {
+        MoveToFront {
+            recent: Vec::new(),
+            max_len: usize::MAX,
+        }
+    }
+}
+
+impl<T: PartialEq + Copy> MoveToFront<T> {
+    /// A new, empty, list which remembers every value
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A new, empty, list which only remembers the `max_len` most recent values. Searching the
+    /// list is linear, so this keeps large alphabets fast.
+    pub fn with_max_len(max_len: usize) -> Self {
+        MoveToFront {
+            recent: Vec::new(),
+            max_len,
+        }
+    }
+
+    /// The recently seen values, most recent first
+    pub fn recent(&self) -> &[T] {
+        &self.recent
+    }
+
+    /// Forget all the values
+    pub fn reset(&mut self) {
+        self.recent.clear();
+    }
+
+    fn move_to_front(&mut self, idx: usize) {
+        self.recent[..=idx].rotate_right(1);
+    }
+
+    fn push_front(&mut self, val: T) {
+        if self.max_len == 0 {
+            return;
+        }
+        if self.recent.len() == self.max_len {
+            self.recent.pop();
+        }
+        self.recent.insert(0, val);
+    }
+
+    /// Transform this value
+    pub fn encode(&mut self, val: T) -> MtfToken<T> {
+        match self.recent.iter().position(|x| *x == val) {
+            Some(idx) => {
+                self.move_to_front(idx);
+                MtfToken::Recent(idx)
+            }
+            None => {
+                self.push_front(val);
+                MtfToken::New(val)
+            }
+        }
+    }
+
+    /// Undo the transform of one value. A position which isn't in the list is `OutOfRange`.
+    pub fn decode(&mut self, token: MtfToken<T>) -> Result<T, VartyIntError> {
+        match token {
+            MtfToken::Recent(idx) => {
+                let val = *self.recent.get(idx).ok_or(VartyIntError::OutOfRange)?;
+                self.move_to_front(idx);
+                Ok(val)
+            }
+            MtfToken::New(val) => {
+                self.push_front(val);
+                Ok(val)
+            }
+        }
+    }
+}
+
+/// `max_len` for `write_mtf`. Every position in the list is a 1 byte varint.
+pub const DEFAULT_MAX_LEN: usize = 127;
+
+/// Write the number of values, then each value move-to-front transformed, to the end of `buf`.
+/// Only the `DEFAULT_MAX_LEN` most recent values are remembered. Returns the number of bytes
+/// written.
+pub fn write_mtf<T: VarInt + PartialEq>(nums: &[T], buf: &mut Vec<u8>) -> usize {
+    write_mtf_with_max_len(nums, DEFAULT_MAX_LEN, buf)
+}
+
+/// Like `write_mtf`, but the `max_len` most recent values are remembered. Each value takes time
+/// linear in `max_len` to write & to read, so large values are slow for data with many different
+/// values. Returns the number of bytes written.
+pub fn write_mtf_with_max_len<T: VarInt + PartialEq>(
+    nums: &[T],
+    max_len: usize,
+    buf: &mut Vec<u8>,
+) -> usize {
+    let start = buf.len();
+    let mut mtf = MoveToFront::with_max_len(max_len);
+    nums.len().write_varint(buf);
+    max_len.write_varint(buf);
+    for num in nums {
+        mtf.encode(*num).write(buf);
+    }
+    buf.len() - start
+}
+
+/// Read values written by `write_mtf` (or `write_mtf_with_max_len`), returning them & the rest of
+/// the buffer
+pub fn read_mtf<T: VarInt + PartialEq>(buf: &[u8]) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+    let (len, buf) = usize::read_varint(buf)?;
+    let (max_len, mut buf) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+    let mut mtf = MoveToFront::with_max_len(max_len);
+    let mut nums = Vec::with_capacity(len.min(buf.len()));
+    for _ in 0..len {
+        let (token, rest) = MtfToken::read(buf).map_err(empty_to_not_enough::<usize>)?;
+        nums.push(mtf.decode(token)?);
+        buf = rest;
+    }
+    Ok((nums, buf))
+}
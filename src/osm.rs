@@ -0,0 +1,122 @@
+//! Helpers for OpenStreetMap PBF style "DenseNodes".
+//!
+//! Each property of the nodes (id, latitude, longitude, timestamp) is stored as its own delta
+//! encoded column. Each column is prefixed with its length in bytes, so all the columns can be
+//! read in parallel, 1 node at a time.
+//!
+//! ```rust
+//! use vartyint::osm::{self, DenseNode};
+//! let nodes = [
+//!     DenseNode { id: 100, lat: 533_498_000, lon: -62_603_000, timestamp: 1_700_000_000 },
+//!     DenseNode { id: 101, lat: 533_499_000, lon: -62_602_000, timestamp: 1_700_000_010 },
+//! ];
+//! let mut buf = Vec::new();
+//! osm::write_dense_nodes(&nodes, &mut buf);
+//!
+//! let (decoded, rest) = osm::read_dense_nodes(&buf).unwrap();
+//! assert_eq!(decoded, nodes);
+//! assert!(rest.is_empty());
+//! ```
+use crate::*;
+
+/// One node. `lat` & `lon` are fixed point integers (e.g. in units of 1e-7 degrees, see `geo`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DenseNode {
+    pub id: i64,
+    pub lat: i64,
+    pub lon: i64,
+    pub timestamp: i64,
+}
+
+/// Write one delta encoded column, prefixed with its length in bytes, returning the number of
+/// bytes written
+fn write_column(nodes: &[DenseNode], get: impl Fn(&DenseNode) -> i64, buf: &mut Vec<u8>) -> usize {
+    let column = nodes.iter().map(get).collect::<Vec<_>>();
+    let bytes = write_many_delta_new(&column);
+    let len = write_usize(bytes.len(), buf);
+    buf.extend_from_slice(&bytes);
+    len + bytes.len()
+}
+
+/// Write these nodes as 4 delta encoded columns (id, lat, lon, timestamp), adding to the end of
+/// the buf Vec. Returns the number of bytes written.
+pub fn write_dense_nodes(nodes: &[DenseNode], buf: &mut Vec<u8>) -> usize {
+    write_column(nodes, |n| n.id, buf)
+        + write_column(nodes, |n| n.lat, buf)
+        + write_column(nodes, |n| n.lon, buf)
+        + write_column(nodes, |n| n.timestamp, buf)
+}
+
+/// The columns of nodes written by `write_dense_nodes`, which can be iterated over without
+/// allocating.
+#[derive(Debug, Clone)]
+pub struct DenseNodes<'a> {
+    columns: [&'a [u8]; 4],
+}
+
+impl<'a> DenseNodes<'a> {
+    /// Find the columns at the start of the buffer, returning them & the rest of the buffer.
+    pub fn new(mut buf: &'a [u8]) -> Result<(Self, &'a [u8]), VartyIntError> {
+        let mut columns: [&[u8]; 4] = [&[]; 4];
+        for (i, column) in columns.iter_mut().enumerate() {
+            let (len, rest) = match read_usize(buf) {
+                Err(e) if i > 0 => return Err(empty_to_not_enough::<usize>(e)),
+                res => res?,
+            };
+            if rest.len() < len {
+                return Err(VartyIntError::not_enough(rest.len(), len));
+            }
+            (*column, buf) = rest.split_at(len);
+        }
+        Ok((DenseNodes { columns }, buf))
+    }
+
+    /// Iterate over the nodes, reading each column in step. If a value overflows, `DeltaOverflow`
+    /// is returned. Stops after the first error.
+    pub fn iter(&self) -> impl Iterator<Item = Result<DenseNode, VartyIntError>> + 'a {
+        let mut columns = self.columns;
+        let mut last = [0_i64; 4];
+        std::iter::from_fn(move || {
+            if columns.iter().all(|c| c.is_empty()) {
+                return None;
+            }
+            for i in 0..columns.len() {
+                let result = match read_i64(columns[i]) {
+                    // One column is shorter than the others
+                    Err(VartyIntError::EmptyBuffer) => {
+                        Err(VartyIntError::not_enough(0, i64::MAX_LEN))
+                    }
+                    Err(e) => Err(e),
+                    Ok((delta, rest)) => last[i]
+                        .checked_add(delta)
+                        .ok_or(VartyIntError::DeltaOverflow)
+                        .map(|val| (val, rest)),
+                };
+                match result {
+                    Ok((val, rest)) => {
+                        last[i] = val;
+                        columns[i] = rest;
+                    }
+                    Err(e) => {
+                        // Stop after the first error
+                        columns = [&[]; 4];
+                        return Some(Err(e));
+                    }
+                }
+            }
+            Some(Ok(DenseNode {
+                id: last[0],
+                lat: last[1],
+                lon: last[2],
+                timestamp: last[3],
+            }))
+        })
+    }
+}
+
+/// Read nodes written by `write_dense_nodes`, returning them & the rest of the buffer.
+pub fn read_dense_nodes(buf: &[u8]) -> Result<(Vec<DenseNode>, &[u8]), VartyIntError> {
+    let (nodes, rest) = DenseNodes::new(buf)?;
+    let nodes = nodes.iter().collect::<Result<Vec<_>, _>>()?;
+    Ok((nodes, rest))
+}
@@ -0,0 +1,100 @@
+//! Google's [Encoded Polyline](https://developers.google.com/maps/documentation/utilities/polylinealgorithm)
+//! format.
+//!
+//! Coordinates are rounded to `precision` decimal places (usually 5, some tools use 6), delta
+//! encoded, zigzag encoded, and then written as 5 bit groups in printable ASCII.
+//!
+//! ```rust
+//! use vartyint::polyline;
+//! let points = [(38.5, -120.2), (40.7, -120.95), (43.252, -126.453)];
+//! let line = polyline::encode(&points, 5).unwrap();
+//! assert_eq!(line, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+//! assert_eq!(polyline::decode(&line, 5).unwrap(), points);
+//! ```
+use crate::geo::LatLon;
+use crate::{fixed_point, VartyIntError};
+
+fn write_value(val: i64, output: &mut String) {
+    let mut val = ((val << 1) ^ (val >> 63)) as u64;
+    while val >= 0x20 {
+        output.push(((0x20 | (val & 0x1F)) as u8 + 63) as char);
+        val >>= 5;
+    }
+    output.push((val as u8 + 63) as char);
+}
+
+fn read_value(input: &mut &[u8]) -> Result<i64, VartyIntError> {
+    if input.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    let mut val: u64 = 0;
+    let mut shift = 0;
+    loop {
+        // A 64 bit value is at most 13 characters
+        let (&byte, rest) = input
+            .split_first()
+            .ok_or(VartyIntError::not_enough(shift / 5, 13))?;
+        *input = rest;
+        if !(63..=126).contains(&byte) {
+            return Err(VartyIntError::InvalidCharacter);
+        }
+        let chunk = (byte - 63) as u64;
+        // Only 4 bits of the 13th chunk fit
+        if shift >= 64 || (shift > 59 && (chunk & 0x1F) >> (64 - shift) != 0) {
+            return Err(VartyIntError::TooManyBytesForType);
+        }
+        val |= (chunk & 0x1F) << shift;
+        shift += 5;
+        if chunk & 0x20 == 0 {
+            break;
+        }
+    }
+    Ok(((val >> 1) as i64) ^ -((val & 1) as i64))
+}
+
+/// Encode these (latitude, longitude) pairs as a polyline, with `precision` decimal places. If a
+/// coordinate isn't finite, or doesn't fit in an `i64` after rounding, `OutOfRange` is returned.
+/// If the difference between 2 coordinates doesn't fit, `DeltaOverflow` is returned.
+pub fn encode(coords: &[LatLon], precision: u32) -> Result<String, VartyIntError> {
+    let factor = 10_f64.powi(precision as i32);
+    let mut output = String::new();
+    let (mut last_lat, mut last_lon) = (0_i64, 0_i64);
+    for (lat, lon) in coords {
+        let lat = fixed_point::quantize(*lat, factor)?;
+        let lon = fixed_point::quantize(*lon, factor)?;
+        let lat_delta = lat
+            .checked_sub(last_lat)
+            .ok_or(VartyIntError::DeltaOverflow)?;
+        let lon_delta = lon
+            .checked_sub(last_lon)
+            .ok_or(VartyIntError::DeltaOverflow)?;
+        write_value(lat_delta, &mut output);
+        write_value(lon_delta, &mut output);
+        (last_lat, last_lon) = (lat, lon);
+    }
+    Ok(output)
+}
+
+/// Decode this polyline, with `precision` decimal places, into (latitude, longitude) pairs. If a
+/// coordinate doesn't fit in an `i64`, `DeltaOverflow` is returned.
+pub fn decode(polyline: &str, precision: u32) -> Result<Vec<LatLon>, VartyIntError> {
+    let factor = 10_f64.powi(precision as i32);
+    let mut input = polyline.as_bytes();
+    let mut coords = Vec::new();
+    let (mut lat, mut lon) = (0_i64, 0_i64);
+    while !input.is_empty() {
+        let lat_delta = read_value(&mut input)?;
+        let lon_delta = match read_value(&mut input) {
+            Err(VartyIntError::EmptyBuffer) => return Err(VartyIntError::not_enough(0, 13)),
+            res => res?,
+        };
+        lat = lat
+            .checked_add(lat_delta)
+            .ok_or(VartyIntError::DeltaOverflow)?;
+        lon = lon
+            .checked_add(lon_delta)
+            .ok_or(VartyIntError::DeltaOverflow)?;
+        coords.push((lat as f64 / factor, lon as f64 / factor));
+    }
+    Ok(coords)
+}
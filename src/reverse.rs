@@ -0,0 +1,86 @@
+//! Reverse varints, which are read backwards from the end of a buffer, e.g. for file footers, or
+//! to walk a log from newest to oldest.
+//!
+//! The value is written as the bytes of the normal varint in reverse order, so the last byte is
+//! the least significant group, & the first byte (without the continuation bit) is where it
+//! starts. The values can only be read backwards.
+//!
+//! ```rust
+//! use vartyint::reverse;
+//! let mut buf = vec![0xAA];
+//! reverse::write_rev(300u32, &mut buf);
+//! reverse::write_rev(-1i8, &mut buf);
+//! assert_eq!(buf, vec![0xAA, 0x02, 0xAC, 0x01]);
+//! let (num, rest) = reverse::read_rev::<i8>(&buf).unwrap();
+//! assert_eq!(num, -1);
+//! let (num, rest) = reverse::read_rev::<u32>(rest).unwrap();
+//! assert_eq!(num, 300);
+//! assert_eq!(rest, &[0xAA]);
+//! ```
+use crate::*;
+
+/// Write this value to the end of `buf` as a reverse varint, returning the number of bytes
+/// written.
+pub fn write_rev<T: VarInt>(val: T, buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    let len = val.write_varint(buf);
+    buf[start..].reverse();
+    len
+}
+
+/// Read one reverse varint from the end of this buffer, returning it & the rest of the buffer
+/// (i.e. everything before it).
+pub fn read_rev<T: VarInt>(buf: &[u8]) -> Result<(T, &[u8]), VartyIntError> {
+    if buf.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    // Any more than `MAX_LEN` bytes is an error, so don't look further than that
+    let start = match buf
+        .iter()
+        .rev()
+        .take(T::MAX_LEN + 1)
+        .position(|b| b >> 7 == 0)
+    {
+        Some(i) => buf.len() - 1 - i,
+        None if buf.len() <= T::MAX_LEN => {
+            return Err(VartyIntError::not_enough(buf.len(), T::MAX_LEN))
+        }
+        None => return Err(VartyIntError::TooManyBytesForType),
+    };
+    let mut bytes = buf[start..].to_vec();
+    bytes.reverse();
+    let (val, _) = T::read_varint(&bytes)?;
+    Ok((val, &buf[..start]))
+}
+
+/// Read all the reverse varints in this buffer, from the last to the first.
+///
+/// ```rust
+/// use vartyint::reverse;
+/// let mut buf = Vec::new();
+/// for num in [1u64, 1_000, 1_000_000] {
+///     reverse::write_rev(num, &mut buf);
+/// }
+/// let nums = reverse::read_many_rev::<u64>(&buf).collect::<Result<Vec<_>, _>>();
+/// assert_eq!(nums, Ok(vec![1_000_000, 1_000, 1]));
+/// ```
+pub fn read_many_rev<T: VarInt>(
+    mut buf: &[u8],
+) -> impl Iterator<Item = Result<T, VartyIntError>> + '_ {
+    let mut failed = false;
+    std::iter::from_fn(move || {
+        if failed || buf.is_empty() {
+            return None;
+        }
+        match read_rev(buf) {
+            Ok((val, rest)) => {
+                buf = rest;
+                Some(Ok(val))
+            }
+            Err(e) => {
+                failed = true;
+                Some(Err(e))
+            }
+        }
+    })
+}
@@ -0,0 +1,191 @@
+//! A simple record format. A `Schema` lists the type & encoding of each column, and rows of
+//! `Value`s are written column by column.
+//!
+//! ```rust
+//! use vartyint::dynamic::{Value, VarIntType};
+//! use vartyint::schema::{Column, Schema};
+//! let schema = Schema::new()
+//!     .column(Column::new(VarIntType::U64).delta())
+//!     .column(Column::new(VarIntType::I32));
+//! let rows = vec![
+//!     vec![Value::U64(1_000_000), Value::I32(-5)],
+//!     vec![Value::U64(1_000_001), Value::I32(7)],
+//! ];
+//! let mut buf = Vec::new();
+//! schema.encode_rows(&rows, &mut buf).unwrap();
+//! assert_eq!(schema.decode_rows(&buf).unwrap(), rows);
+//! ```
+//!
+//! The data is the number of rows, then each column, as the number of bytes in it followed by
+//! the values. Columns can be skipped without decoding them.
+use crate::dynamic::{Value, VarIntType};
+use crate::*;
+
+/// The type of a column, & how it's written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Column {
+    /// The type of the values
+    pub var_int_type: VarIntType,
+    /// Write the difference to the previous value (like `write_many_delta`)
+    pub delta: bool,
+    /// Zigzag encode signed values (the default). Otherwise they are written like `Raw`.
+    /// Unsigned values are never zigzag encoded.
+    pub zigzag: bool,
+}
+
+impl Column {
+    /// A column of this type, not delta encoded, with zigzag encoding
+    pub fn new(var_int_type: VarIntType) -> Self {
+        Column {
+            var_int_type,
+            delta: false,
+            zigzag: true,
+        }
+    }
+
+    /// Delta encode this column
+    pub fn delta(mut self) -> Self {
+        self.delta = true;
+        self
+    }
+
+    /// Don't zigzag encode this column
+    pub fn raw(mut self) -> Self {
+        self.zigzag = false;
+        self
+    }
+
+    fn write(
+        &self,
+        vals: impl Iterator<Item = Value>,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), VartyIntError> {
+        let mut last = Value::zero(self.var_int_type);
+        for val in vals {
+            if val.var_int_type() != self.var_int_type {
+                return Err(VartyIntError::TypeMismatch);
+            }
+            let out = if self.delta {
+                val.wrapping_sub(&last)?
+            } else {
+                val
+            };
+            last = val;
+            if self.zigzag {
+                out.write(buf);
+            } else {
+                out.write_raw(buf);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read exactly `num` values, which must be the whole buffer
+    fn read(&self, mut buf: &[u8], num: usize) -> Result<Vec<Value>, VartyIntError> {
+        let mut vals = Vec::with_capacity(num.min(buf.len()));
+        let mut last = Value::zero(self.var_int_type);
+        for _ in 0..num {
+            let (val, rest) = if self.zigzag {
+                Value::read(self.var_int_type, buf)
+            } else {
+                Value::read_raw(self.var_int_type, buf)
+            }
+            .map_err(|e| match e {
+                VartyIntError::EmptyBuffer => {
+                    VartyIntError::not_enough(0, self.var_int_type.max_len())
+                }
+                e => e,
+            })?;
+            buf = rest;
+            last = if self.delta {
+                last.wrapping_add(&val)?
+            } else {
+                val
+            };
+            vals.push(last);
+        }
+        if !buf.is_empty() {
+            return Err(VartyIntError::TrailingBytes);
+        }
+        Ok(vals)
+    }
+}
+
+/// An ordered list of columns
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Schema {
+    columns: Vec<Column>,
+}
+
+impl Schema {
+    /// A schema with no columns
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a column to the end
+    pub fn column(mut self, column: Column) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// The columns
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Write these rows to the end of `buf`, returning the number of bytes written. Every row must
+    /// have one value per column, of the right type, otherwise `TypeMismatch` is returned & `buf`
+    /// is unchanged. A schema with no columns can't be used to write any rows (`OutOfRange`), since
+    /// there would be nothing to check the number of rows against when reading.
+    pub fn encode_rows(
+        &self,
+        rows: &[Vec<Value>],
+        buf: &mut Vec<u8>,
+    ) -> Result<usize, VartyIntError> {
+        if rows.iter().any(|row| row.len() != self.columns.len()) {
+            return Err(VartyIntError::TypeMismatch);
+        }
+        if self.columns.is_empty() && !rows.is_empty() {
+            return Err(VartyIntError::OutOfRange);
+        }
+        let start = buf.len();
+        rows.len().write_varint(buf);
+        let mut column_buf = Vec::new();
+        for (i, column) in self.columns.iter().enumerate() {
+            column_buf.clear();
+            if let Err(e) = column.write(rows.iter().map(|row| row[i]), &mut column_buf) {
+                buf.truncate(start);
+                return Err(e);
+            }
+            column_buf.len().write_varint(buf);
+            buf.extend_from_slice(&column_buf);
+        }
+        Ok(buf.len() - start)
+    }
+
+    /// Read all the rows in this buffer, written by `encode_rows` with the same schema.
+    pub fn decode_rows(&self, buf: &[u8]) -> Result<Vec<Vec<Value>>, VartyIntError> {
+        let (num_rows, mut rest) = usize::read_varint(buf)?;
+        // Each row has at least 1 byte, unless there are no columns
+        if self.columns.is_empty() && num_rows > rest.len() {
+            return Err(VartyIntError::OutOfRange);
+        }
+        let mut columns = Vec::with_capacity(self.columns.len());
+        for column in self.columns.iter() {
+            let (len, newrest) = usize::read_varint(rest).map_err(empty_to_not_enough::<usize>)?;
+            if newrest.len() < len {
+                return Err(VartyIntError::not_enough(newrest.len(), len));
+            }
+            let (column_bytes, newrest) = newrest.split_at(len);
+            columns.push(column.read(column_bytes, num_rows)?.into_iter());
+            rest = newrest;
+        }
+        if !rest.is_empty() {
+            return Err(VartyIntError::TrailingBytes);
+        }
+        Ok((0..num_rows)
+            .map(|_| columns.iter_mut().map(|col| col.next().unwrap()).collect())
+            .collect())
+    }
+}
@@ -0,0 +1,98 @@
+//! Adaptors for `std::io::Write`, which keep track of what has been written through them.
+//!
+//! ```rust
+//! use vartyint::{CountingSink, HashingSink};
+//! use std::collections::hash_map::DefaultHasher;
+//!
+//! let mut sink = HashingSink::new(CountingSink::new(Vec::new()), DefaultHasher::new());
+//! vartyint::write_to(300u32, &mut sink).unwrap();
+//! vartyint::write_to(-1i64, &mut sink).unwrap();
+//! let digest = sink.finish();
+//! let (counter, _hasher) = sink.into_inner();
+//! assert_eq!(counter.count(), 3);
+//! assert_eq!(counter.into_inner(), vec![0xAC, 0x02, 0x01]);
+//! ```
+use std::hash::Hasher;
+use std::io::Write;
+
+/// Counts the number of bytes written to the inner writer
+#[derive(Debug, Clone, Default)]
+pub struct CountingSink<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingSink<W> {
+    pub fn new(inner: W) -> Self {
+        CountingSink { inner, count: 0 }
+    }
+
+    /// How many bytes have been written so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingSink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Feeds every byte written to the inner writer into a `Hasher`, so the digest of the output is
+/// calculated while writing.
+#[derive(Debug, Clone, Default)]
+pub struct HashingSink<W, H> {
+    inner: W,
+    hasher: H,
+}
+
+impl<W: Write, H: Hasher> HashingSink<W, H> {
+    pub fn new(inner: W, hasher: H) -> Self {
+        HashingSink { inner, hasher }
+    }
+
+    /// The hash of the bytes written so far
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> (W, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<W: Write, H: Hasher> Write for HashingSink<W, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        // Only the bytes which were accepted
+        self.hasher.write(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,375 @@
+//! Work with sorted, delta encoded (i.e. written with `write_many_delta`) integers, without
+//! decoding them all first.
+//!
+//! ```rust
+//! use vartyint::{sorted, write_many_delta_new};
+//! let a = write_many_delta_new(&[1u32, 3, 5, 7, 9]);
+//! let b = write_many_delta_new(&[3u32, 4, 5, 6]);
+//!
+//! let both = sorted::intersection::<u32>(&a, &b).collect::<Result<Vec<_>, _>>().unwrap();
+//! assert_eq!(both, vec![3, 5]);
+//! ```
+use crate::*;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetOp {
+    Intersection,
+    Union,
+    Difference,
+}
+
+/// The next value of this iterator, without advancing it (unless it's an error)
+fn peek<T: Copy>(
+    iter: &mut std::iter::Peekable<impl Iterator<Item = Result<T, VartyIntError>>>,
+) -> Result<Option<T>, VartyIntError> {
+    match iter.peek() {
+        None => Ok(None),
+        Some(Ok(val)) => Ok(Some(*val)),
+        Some(Err(_)) => iter.next().unwrap().map(Some),
+    }
+}
+
+/// Iterator over 2 sorted delta encoded streams
+struct SetIter<'a, T> {
+    op: SetOp,
+    a: std::iter::Peekable<Box<dyn Iterator<Item = Result<T, VartyIntError>> + 'a>>,
+    b: std::iter::Peekable<Box<dyn Iterator<Item = Result<T, VartyIntError>> + 'a>>,
+    finished: bool,
+}
+
+impl<'a, T> SetIter<'a, T>
+where
+    T: DeltaInt + Ord + 'a,
+{
+    fn new(op: SetOp, a: &'a [u8], b: &'a [u8]) -> Self {
+        let a: Box<dyn Iterator<Item = _>> = Box::new(read_many_delta_checked::<T>(a));
+        let b: Box<dyn Iterator<Item = _>> = Box::new(read_many_delta_checked::<T>(b));
+        SetIter {
+            op,
+            a: a.peekable(),
+            b: b.peekable(),
+            finished: false,
+        }
+    }
+
+    fn next_value(&mut self) -> Result<Option<T>, VartyIntError> {
+        loop {
+            let a = peek(&mut self.a)?;
+            let b = peek(&mut self.b)?;
+            let (a, b) = match (a, b) {
+                (None, None) => return Ok(None),
+                (Some(a), None) => {
+                    self.a.next();
+                    match self.op {
+                        SetOp::Intersection => {}
+                        SetOp::Union | SetOp::Difference => return Ok(Some(a)),
+                    }
+                    continue;
+                }
+                (None, Some(b)) => {
+                    self.b.next();
+                    match self.op {
+                        SetOp::Intersection | SetOp::Difference => {}
+                        SetOp::Union => return Ok(Some(b)),
+                    }
+                    continue;
+                }
+                (Some(a), Some(b)) => (a, b),
+            };
+            match a.cmp(&b) {
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                    match self.op {
+                        SetOp::Intersection | SetOp::Union => return Ok(Some(a)),
+                        SetOp::Difference => {}
+                    }
+                }
+                Ordering::Less => {
+                    self.a.next();
+                    match self.op {
+                        SetOp::Intersection => {}
+                        SetOp::Union | SetOp::Difference => return Ok(Some(a)),
+                    }
+                }
+                Ordering::Greater => {
+                    self.b.next();
+                    match self.op {
+                        SetOp::Intersection | SetOp::Difference => {}
+                        SetOp::Union => return Ok(Some(b)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for SetIter<'a, T>
+where
+    T: DeltaInt + Ord + 'a,
+{
+    type Item = Result<T, VartyIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let res = self.next_value().transpose();
+        if !matches!(res, Some(Ok(_))) {
+            self.finished = true;
+        }
+        res
+    }
+}
+
+/// Integers which are in both `a` & `b`
+pub fn intersection<'a, T>(
+    a: &'a [u8],
+    b: &'a [u8],
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
+where
+    T: DeltaInt + Ord + 'a,
+{
+    SetIter::new(SetOp::Intersection, a, b)
+}
+
+/// Integers which are in either `a` or `b`. Integers in both are only returned once.
+pub fn union<'a, T>(a: &'a [u8], b: &'a [u8]) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
+where
+    T: DeltaInt + Ord + 'a,
+{
+    SetIter::new(SetOp::Union, a, b)
+}
+
+/// Integers which are in `a`, but not in `b`
+pub fn difference<'a, T>(
+    a: &'a [u8],
+    b: &'a [u8],
+) -> impl Iterator<Item = Result<T, VartyIntError>> + 'a
+where
+    T: DeltaInt + Ord + 'a,
+{
+    SetIter::new(SetOp::Difference, a, b)
+}
+
+/// Read the next value of a delta encoded stream, from the rest of it & the previous value
+fn next_delta<T>(rest: &mut &[u8], last: T) -> Result<Option<T>, VartyIntError>
+where
+    T: DeltaInt,
+{
+    if rest.is_empty() {
+        return Ok(None);
+    }
+    let (num, newrest) = T::read_varint(rest)?;
+    *rest = newrest;
+    last.checked_add_delta(num)
+        .ok_or(VartyIntError::DeltaOverflow)
+        .map(Some)
+}
+
+fn merge_inner<T>(inputs: &[&[u8]], unique: bool, buf: &mut Vec<u8>) -> Result<usize, VartyIntError>
+where
+    T: DeltaInt + std::ops::Sub<T, Output = T> + Ord,
+{
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    // The rest of each input. The heap has the next value of each input, & its position.
+    let mut rests = inputs.to_vec();
+    let mut heap = BinaryHeap::with_capacity(inputs.len());
+    for (i, rest) in rests.iter_mut().enumerate() {
+        if let Some(val) = next_delta(rest, T::zero())? {
+            heap.push(Reverse((val, i)));
+        }
+    }
+
+    let mut last_written = None;
+    let mut count = 0;
+    while let Some(Reverse((val, i))) = heap.pop() {
+        if let Some(next) = next_delta(&mut rests[i], val)? {
+            heap.push(Reverse((next, i)));
+        }
+        if unique && last_written == Some(val) {
+            continue;
+        }
+        (val - last_written.unwrap_or_else(T::zero)).write_varint(buf);
+        last_written = Some(val);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Merge many sorted, delta encoded, buffers into one sorted, delta encoded output, added to the
+/// end of `buf`. Duplicates are kept. Only the next value of each input is held in memory. Returns
+/// the number of values written.
+///
+/// If any input is invalid, an error is returned, and `buf` is left unchanged.
+///
+/// ```rust
+/// use vartyint::{sorted, write_many_delta_new, read_many_delta_new};
+/// let a = write_many_delta_new(&[1u32, 5, 9]);
+/// let b = write_many_delta_new(&[2u32, 5]);
+/// let c = write_many_delta_new(&[10u32]);
+///
+/// let mut out = Vec::new();
+/// assert_eq!(sorted::merge::<u32>(&[&a, &b, &c], &mut out), Ok(6));
+/// assert_eq!(read_many_delta_new::<u32>(&out).unwrap(), vec![1, 2, 5, 5, 9, 10]);
+/// ```
+pub fn merge<T>(inputs: &[&[u8]], buf: &mut Vec<u8>) -> Result<usize, VartyIntError>
+where
+    T: DeltaInt + std::ops::Sub<T, Output = T> + Ord,
+{
+    let start = buf.len();
+    merge_inner::<T>(inputs, false, buf).inspect_err(|_| buf.truncate(start))
+}
+
+/// Like `merge`, but values which are in more than one input (or repeated in an input) are only
+/// written once.
+pub fn merge_unique<T>(inputs: &[&[u8]], buf: &mut Vec<u8>) -> Result<usize, VartyIntError>
+where
+    T: DeltaInt + std::ops::Sub<T, Output = T> + Ord,
+{
+    let start = buf.len();
+    merge_inner::<T>(inputs, true, buf).inspect_err(|_| buf.truncate(start))
+}
+
+/// An index of a sorted, delta encoded, buffer, so values can be found without decoding the
+/// whole buffer.
+///
+/// Every `every`th value, & where it is in the buffer, is stored. Finding a value is a binary
+/// search of those samples, then decoding at most `every` values.
+///
+/// ```rust
+/// use vartyint::{sorted::SortedIndex, write_many_delta_new};
+/// let nums = (0..1_000u64).map(|i| i * 10).collect::<Vec<_>>();
+/// let buf = write_many_delta_new(&nums);
+/// let index = SortedIndex::<u64>::new(&buf, 16).unwrap();
+///
+/// assert_eq!(index.find(5_000), Some(500));
+/// assert_eq!(index.find(5_001), None);
+/// assert_eq!(index.lower_bound(5_001), Some((501, 5_010)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SortedIndex<'a, T> {
+    buf: &'a [u8],
+    every: usize,
+    /// The value of every `every`th element, & the offset in `buf` just after it
+    samples: Vec<(T, usize)>,
+    len: usize,
+}
+
+impl<'a, T> SortedIndex<'a, T>
+where
+    T: DeltaInt + Ord,
+{
+    /// Build an index of this buffer, storing every `every`th value. This decodes the whole
+    /// buffer once, returning an error if it's invalid.
+    pub fn new(buf: &'a [u8], every: usize) -> Result<Self, VartyIntError> {
+        let every = every.max(1);
+        let mut samples = Vec::new();
+        let mut rest = buf;
+        let mut last = T::zero();
+        let mut len = 0;
+        while !rest.is_empty() {
+            let (num, newrest) = T::read_varint(rest)?;
+            last = last
+                .checked_add_delta(num)
+                .ok_or(VartyIntError::DeltaOverflow)?;
+            rest = newrest;
+            if len % every == 0 {
+                samples.push((last, buf.len() - rest.len()));
+            }
+            len += 1;
+        }
+        Ok(SortedIndex {
+            buf,
+            every,
+            samples,
+            len,
+        })
+    }
+
+    /// Number of values in the buffer
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True iff there are no values in the buffer
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the values from sample number `sample`, as (index, value)
+    fn scan_from(&self, sample: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let (first, offset) = self.samples[sample];
+        let start = sample * self.every;
+        let end = (start + self.every).min(self.len);
+        let mut rest = &self.buf[offset..];
+        let mut last = first;
+        std::iter::once((start, first)).chain(((start + 1)..end).map_while(move |idx| {
+            // The buffer was checked in `new`, so this can't fail
+            let (num, newrest) = T::read_varint(rest).ok()?;
+            rest = newrest;
+            last = last.checked_add_delta(num)?;
+            Some((idx, last))
+        }))
+    }
+
+    /// The first value which is greater than or equal to `value`, as (index, value)
+    pub fn lower_bound(&self, value: T) -> Option<(usize, T)> {
+        // Number of samples which are less than value
+        let num_less = self.samples.partition_point(|(s, _)| *s < value);
+        if num_less == 0 {
+            return self.samples.first().map(|(s, _)| (0, *s));
+        }
+        self.scan_from(num_less - 1)
+            .find(|(_, v)| *v >= value)
+            .or_else(|| {
+                self.samples
+                    .get(num_less)
+                    .map(|(s, _)| (num_less * self.every, *s))
+            })
+    }
+
+    /// The index of `value`, if it's in the buffer
+    pub fn find(&self, value: T) -> Option<usize> {
+        match self.lower_bound(value) {
+            Some((idx, v)) if v == value => Some(idx),
+            _ => None,
+        }
+    }
+
+    /// Number of values which are less than or equal to `value`
+    ///
+    /// ```rust
+    /// use vartyint::{sorted::SortedIndex, write_many_delta_new};
+    /// let buf = write_many_delta_new(&[2u32, 3, 3, 8]);
+    /// let index = SortedIndex::<u32>::new(&buf, 2).unwrap();
+    /// assert_eq!(index.rank(1), 0);
+    /// assert_eq!(index.rank(3), 3);
+    /// assert_eq!(index.rank(100), 4);
+    /// assert_eq!(index.select(3), Some(8));
+    /// ```
+    pub fn rank(&self, value: T) -> usize {
+        // Number of samples which are less than or equal to value
+        let num_le = self.samples.partition_point(|(s, _)| *s <= value);
+        if num_le == 0 {
+            return 0;
+        }
+        self.scan_from(num_le - 1)
+            .find(|(_, v)| *v > value)
+            .map_or((num_le * self.every).min(self.len), |(idx, _)| idx)
+    }
+
+    /// The value at index `idx` (i.e. the `idx + 1`th smallest value), decoding at most `every`
+    /// values
+    pub fn select(&self, idx: usize) -> Option<T> {
+        if idx >= self.len {
+            return None;
+        }
+        self.scan_from(idx / self.every)
+            .nth(idx % self.every)
+            .map(|(_, v)| v)
+    }
+}
@@ -0,0 +1,82 @@
+//! Encode values into fixed size output buffers (e.g. packets), a bit at a time.
+//!
+//! ```rust
+//! use vartyint::{EncodeStatus, StreamingEncoder};
+//! let mut encoder = StreamingEncoder::new();
+//! encoder.extend([1u32, 300, 70_000]);
+//!
+//! let mut packet = [0; 4];
+//! assert_eq!(encoder.encode(&mut packet), (3, EncodeStatus::OutputFull));
+//! assert_eq!(packet[..3], [1, 0xAC, 0x02]);
+//! assert_eq!(encoder.encode(&mut packet), (3, EncodeStatus::Done));
+//! assert_eq!(packet[..3], [0xF0, 0xA2, 0x04]);
+//! ```
+use crate::*;
+use std::collections::VecDeque;
+
+/// Result of `StreamingEncoder::encode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeStatus {
+    /// Every value has been written
+    Done,
+    /// The next value doesn't fit in the rest of the output. Call `encode` again with another
+    /// buffer.
+    OutputFull,
+}
+
+/// Queues values, and writes as many as fit into caller provided buffers. A value is never split
+/// across 2 buffers.
+#[derive(Debug, Clone, Default)]
+pub struct StreamingEncoder<T> {
+    pending: VecDeque<T>,
+    scratch: Vec<u8>,
+}
+
+impl<T: VarInt> StreamingEncoder<T> {
+    pub fn new() -> Self {
+        StreamingEncoder {
+            pending: VecDeque::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Add a value to be written
+    pub fn push(&mut self, val: T) {
+        self.pending.push_back(val);
+    }
+
+    /// Number of values which haven't been written yet
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// True iff every value has been written
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Write as many values as fit into `out`, returning the number of bytes written, and whether
+    /// there are more values to write.
+    ///
+    /// If a single value is bigger than `out`, nothing is written, and `OutputFull` is returned.
+    pub fn encode(&mut self, out: &mut [u8]) -> (usize, EncodeStatus) {
+        let mut written = 0;
+        while let Some(val) = self.pending.front() {
+            self.scratch.clear();
+            let len = val.write_varint(&mut self.scratch);
+            if written + len > out.len() {
+                return (written, EncodeStatus::OutputFull);
+            }
+            out[written..(written + len)].copy_from_slice(&self.scratch);
+            written += len;
+            self.pending.pop_front();
+        }
+        (written, EncodeStatus::Done)
+    }
+}
+
+impl<T: VarInt> Extend<T> for StreamingEncoder<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.pending.extend(iter);
+    }
+}
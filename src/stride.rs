@@ -0,0 +1,101 @@
+//! Runs of integers with a constant difference ("stride"), like auto-increment IDs or regularly
+//! sampled timestamps, are written as just a start, a stride & a count. Everything else is written
+//! as deltas.
+//!
+//! ```rust
+//! use vartyint::stride;
+//! let timestamps = (0..1000u64).map(|i| 1_700_000_000 + i * 60).collect::<Vec<_>>();
+//! let mut buf = Vec::new();
+//! stride::write_strided(&timestamps, &mut buf);
+//! assert_eq!(buf.len(), 10);
+//!
+//! let (decoded, rest) = stride::read_strided::<u64>(&buf).unwrap();
+//! assert_eq!(decoded, timestamps);
+//! assert!(rest.is_empty());
+//! ```
+//!
+//! The data is the number of integers, then segments. Each segment starts with `count << 1 |
+//! is_run`. A run is then the difference from the previous integer to its first integer, & the
+//! stride. Otherwise `count` differences follow. Differences wrap around (like
+//! `write_many_delta_wrapping`), so nothing can overflow.
+use crate::*;
+
+/// Runs shorter than this are written as deltas, since that's no bigger
+const MIN_RUN: usize = 4;
+
+/// Write these integers, with runs of a constant stride collapsed, to the end of `buf`. Returns the
+/// number of bytes written.
+pub fn write_strided<T: DeltaInt>(nums: &[T], buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    nums.len().write_varint(buf);
+
+    let mut deltas = Vec::with_capacity(nums.len());
+    let mut last = T::zero();
+    for num in nums {
+        deltas.push(num.wrapping_delta(last));
+        last = *num;
+    }
+
+    let mut literals_start = 0;
+    let mut i = 0;
+    while i < nums.len() {
+        // The run starting at i is i, & all following values with the same delta as i+1
+        let run_len = match deltas.get(i + 1) {
+            Some(stride) => 2 + deltas[i + 2..].iter().take_while(|d| *d == stride).count(),
+            None => 1,
+        };
+        if run_len < MIN_RUN {
+            i += 1;
+            continue;
+        }
+        write_literals(&deltas[literals_start..i], buf);
+        ((run_len << 1) | 1).write_varint(buf);
+        deltas[i].write_varint(buf);
+        deltas[i + 1].write_varint(buf);
+        i += run_len;
+        literals_start = i;
+    }
+    write_literals(&deltas[literals_start..], buf);
+    buf.len() - start
+}
+
+fn write_literals<D: VarInt>(deltas: &[D], buf: &mut Vec<u8>) {
+    if !deltas.is_empty() {
+        (deltas.len() << 1).write_varint(buf);
+        write_many(deltas, buf);
+    }
+}
+
+/// Read integers written by `write_strided`, returning them & the rest of the buffer. A segment
+/// which is empty, or has more integers than are left, is `OutOfRange`.
+pub fn read_strided<T: DeltaInt>(buf: &[u8]) -> Result<(Vec<T>, &[u8]), VartyIntError> {
+    let (len, mut buf) = usize::read_varint(buf)?;
+    let mut nums = Vec::with_capacity(len.min(buf.len()));
+    let mut last = T::zero();
+    while nums.len() < len {
+        let (header, rest) = usize::read_varint(buf).map_err(empty_to_not_enough::<usize>)?;
+        let count = header >> 1;
+        if count == 0 || count > len - nums.len() {
+            return Err(VartyIntError::OutOfRange);
+        }
+        if header & 1 == 1 {
+            let ((first, stride), rest) = <(T::Wrapping, T::Wrapping)>::read_varint(rest)
+                .map_err(empty_to_not_enough::<(T::Wrapping, T::Wrapping)>)?;
+            last = last.wrapping_add_delta(first);
+            nums.push(last);
+            for _ in 1..count {
+                last = last.wrapping_add_delta(stride);
+                nums.push(last);
+            }
+            buf = rest;
+        } else {
+            let (deltas, rest) = read_many_exact::<T::Wrapping>(rest, count)?;
+            for delta in deltas {
+                last = last.wrapping_add_delta(delta);
+                nums.push(last);
+            }
+            buf = rest;
+        }
+    }
+    Ok((nums, buf))
+}
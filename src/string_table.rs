@@ -0,0 +1,133 @@
+//! A table of strings, so columns of strings can be written as varint indexes into it.
+//!
+//! Data with lots of repeated strings (like OpenStreetMap tag keys & values) gets much smaller.
+//!
+//! ```rust
+//! use vartyint::string_table;
+//! let tags = ["highway", "primary", "name", "Main Street", "highway", "residential"];
+//! let mut buf = Vec::new();
+//! string_table::write_strings(&tags, &mut buf);
+//!
+//! let (decoded, rest) = string_table::read_strings(&buf).unwrap();
+//! assert_eq!(decoded, tags);
+//! assert!(rest.is_empty());
+//! ```
+use crate::*;
+use std::collections::HashMap;
+
+/// A list of unique strings. Each string is identified by its index.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, usize>,
+}
+
+impl StringTable {
+    /// Create a new empty table
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add this string to the table (if it's not already there), returning its index
+    pub fn insert(&mut self, string: &str) -> usize {
+        if let Some(idx) = self.indexes.get(string) {
+            return *idx;
+        }
+        let idx = self.strings.len();
+        self.strings.push(string.to_string());
+        self.indexes.insert(string.to_string(), idx);
+        idx
+    }
+
+    /// The string with this index
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.strings.get(idx).map(|s| s.as_str())
+    }
+
+    /// The index of this string, if it's in the table
+    pub fn index_of(&self, string: &str) -> Option<usize> {
+        self.indexes.get(string).copied()
+    }
+
+    /// Number of strings in the table
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// True iff there are no strings in the table
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Iterate over the strings, in index order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.strings.iter().map(|s| s.as_str())
+    }
+
+    /// Write this table to the end of the buf Vec. The number of strings is written, then each
+    /// string as its length in bytes followed by the UTF-8 bytes.
+    pub fn write(&self, buf: &mut Vec<u8>) {
+        write_usize(self.strings.len(), buf);
+        for string in self.strings.iter() {
+            write_usize(string.len(), buf);
+            buf.extend_from_slice(string.as_bytes());
+        }
+    }
+
+    /// Read a table from the start of this buffer, returning it & the rest of the buffer.
+    /// Invalid UTF-8 is `InvalidCharacter`.
+    pub fn read(buf: &[u8]) -> Result<(Self, &[u8]), VartyIntError> {
+        let (len, mut buf) = read_usize(buf)?;
+        let mut table = StringTable::new();
+        for _ in 0..len {
+            let (bytes, rest) = read_bytes(buf)?;
+            let string = std::str::from_utf8(bytes).map_err(|_| VartyIntError::InvalidCharacter)?;
+            // Don't use `insert`, a table with duplicates must keep the same indexes
+            let idx = table.strings.len();
+            table.indexes.entry(string.to_string()).or_insert(idx);
+            table.strings.push(string.to_string());
+            buf = rest;
+        }
+        Ok((table, buf))
+    }
+}
+
+/// Read a length prefixed slice of bytes
+fn read_bytes(buf: &[u8]) -> Result<(&[u8], &[u8]), VartyIntError> {
+    let (len, rest) = read_usize(buf).map_err(empty_to_not_enough::<usize>)?;
+    if rest.len() < len {
+        return Err(VartyIntError::not_enough(rest.len(), len));
+    }
+    Ok(rest.split_at(len))
+}
+
+/// Write a column of strings, adding to the end of the buf Vec. A `StringTable` of the unique
+/// strings is written, followed by the number of strings & the index of each one. Returns the
+/// number of bytes written.
+pub fn write_strings<S: AsRef<str>>(strings: &[S], buf: &mut Vec<u8>) -> usize {
+    let start = buf.len();
+    let mut table = StringTable::new();
+    let indexes = strings
+        .iter()
+        .map(|s| table.insert(s.as_ref()))
+        .collect::<Vec<_>>();
+    table.write(buf);
+    write_usize(indexes.len(), buf);
+    write_many(&indexes, buf);
+    buf.len() - start
+}
+
+/// Read a column of strings written by `write_strings`, returning them & the rest of the buffer.
+/// An index which isn't in the table is `OutOfRange`.
+pub fn read_strings(buf: &[u8]) -> Result<(Vec<String>, &[u8]), VartyIntError> {
+    let (table, buf) = StringTable::read(buf)?;
+    let (len, mut buf) = read_usize(buf).map_err(empty_to_not_enough::<usize>)?;
+    let mut strings = Vec::with_capacity(len.min(buf.len()));
+    for _ in 0..len {
+        let (idx, rest) = read_usize(buf).map_err(empty_to_not_enough::<usize>)?;
+        let string = table.get(idx).ok_or(VartyIntError::OutOfRange)?;
+        strings.push(string.to_string());
+        buf = rest;
+    }
+    Ok((strings, buf))
+}
@@ -5,7 +5,8 @@ macro_rules! test_write {
         #[test]
         fn $name() {
             let mut veccy = Vec::new();
-            $func($input, &mut veccy);
+            let len = $func($input, &mut veccy);
+            assert_eq!(len, veccy.len());
             assert_eq!(
                 veccy, $expected_output,
                 "Ouput was {:?} but expected {:?}",
@@ -158,12 +159,114 @@ fn siberia_boatable() {
 
 #[test]
 fn bad1() {
-    assert_eq!(read_u32(&[0b1010_1100]), Err(VartyIntError::NotEnoughBytes));
-    assert_eq!(read_i32(&[0b1010_1100]), Err(VartyIntError::NotEnoughBytes));
+    assert!(matches!(
+        read_u32(&[0b1010_1100]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        read_i32(&[0b1010_1100]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
 
     assert_eq!(read_i32(&[]), Err(VartyIntError::EmptyBuffer));
 }
 
+#[test]
+fn one_and_two_byte_fast_paths() {
+    for num in 0..=0x4000u32 {
+        let mut buf = Vec::new();
+        let len = write_u32(num, &mut buf);
+        assert_eq!(
+            len,
+            if num < 0x80 {
+                1
+            } else if num < 0x4000 {
+                2
+            } else {
+                3
+            }
+        );
+        assert_eq!(read_u32(&buf), Ok((num, &[] as &[u8])));
+        assert_eq!(read_u64(&buf).unwrap().0, num as u64);
+    }
+    for num in 0..=u8::MAX {
+        assert_eq!(read_u8(&num.as_varint()), Ok((num, &[] as &[u8])));
+    }
+    for num in i8::MIN..=i8::MAX {
+        assert_eq!(read_i8(&num.as_varint()), Ok((num, &[] as &[u8])));
+    }
+    // 2 bytes, but too big for a u8
+    assert_eq!(
+        read_u8(&[0x80, 0x02]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+    assert_eq!(read_u8(&[0xFF, 0x01, 7]), Ok((255, &[7u8] as &[u8])));
+    assert_eq!(read_u16(&[0xFF, 0x7F, 7]), Ok((0x3FFF, &[7u8] as &[u8])));
+    // Padded values still work
+    assert_eq!(read_u16(&[0x81, 0x00]), Ok((1, &[] as &[u8])));
+}
+
+#[test]
+fn zigzag_slices() {
+    let nums = (-1_000i64..1_000)
+        .chain([i64::MIN, i64::MAX])
+        .collect::<Vec<_>>();
+    let mut encoded = vec![0; nums.len()];
+    zigzag_encode_slice(&nums, &mut encoded);
+    assert!(nums
+        .iter()
+        .zip(encoded.iter())
+        .all(|(n, e)| n.zigzag_encode() == *e));
+    let mut decoded = vec![0; nums.len()];
+    zigzag_decode_slice(&encoded, &mut decoded);
+    assert_eq!(decoded, nums);
+
+    let mut out = [0u8; 0];
+    zigzag_encode_slice::<i8>(&[], &mut out);
+}
+
+#[test]
+#[should_panic]
+fn zigzag_slices_lengths() {
+    zigzag_encode_slice(&[1i16, 2], &mut [0u16; 3]);
+}
+
+#[test]
+fn not_enough_bytes_counts() {
+    assert_eq!(
+        read_u32(&[0x80]),
+        Err(VartyIntError::NotEnoughBytes {
+            read: 1,
+            max_more: Some(4)
+        })
+    );
+    assert_eq!(
+        read_u64(&[0xFF, 0xFF, 0xFF]),
+        Err(VartyIntError::NotEnoughBytes {
+            read: 3,
+            max_more: Some(7)
+        })
+    );
+    assert_eq!(
+        read_u8(&[0x80]),
+        Err(VartyIntError::NotEnoughBytes {
+            read: 1,
+            max_more: Some(1)
+        })
+    );
+    assert_eq!(u8::MAX_LEN, 2);
+    assert_eq!(i128::MAX_LEN, 19);
+    assert_eq!(<(u8, u64)>::MAX_LEN, 12);
+    // No limit on the length of big integers
+    assert_eq!(
+        crate::bigint::read_big_unsigned(&[0x80, 0x80]),
+        Err(VartyIntError::NotEnoughBytes {
+            read: 2,
+            max_more: None
+        })
+    );
+}
+
 #[test]
 fn bad2() {
     assert_eq!(
@@ -189,6 +292,149 @@ fn bad2() {
     );
 }
 
+#[test]
+fn overflowing_bits() {
+    // 0xFF, 0x7F is 16,383, which doesn't fit in a u8 or i8
+    assert_eq!(
+        read_u8(&[0xFF, 0x7F]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+    assert_eq!(
+        read_i8(&[0xFF, 0x7F]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+    assert_eq!(read_u8(&[0xFF, 0x01]), Ok((255, &[] as &[u8])));
+    assert_eq!(read_i8(&[0xFF, 0x01]), Ok((-128, &[] as &[u8])));
+}
+
+#[test]
+fn extremes() {
+    macro_rules! check {
+        ( $( $type:ty ),* ) => {
+            $(
+                for val in [<$type>::MIN, <$type>::MAX, 0, 1] {
+                    let buf = val.as_varint();
+                    assert!(buf.len() <= (<$type>::BITS as usize).div_ceil(7));
+                    assert_eq!(<$type>::read_varint(&buf), Ok((val, &[] as &[u8])));
+                }
+            )*
+        };
+    }
+    check!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+    assert_eq!(i128::MIN.as_varint(), u128::MAX.as_varint());
+}
+
+#[test]
+fn saturating() {
+    let buf = write_many_new(&[300u32, 5]);
+    assert_eq!(read_u8_saturating(&buf), Ok((255, &[5][..])));
+    assert_eq!(read_u16_saturating(&buf), Ok((300, &[5][..])));
+    let buf = write_many_new(&[-300i32, 300, 5]);
+    assert_eq!(read_i8_saturating(&buf), Ok((-128, &buf[2..])));
+    assert_eq!(read_i8_saturating(&buf[2..]), Ok((127, &[10][..])));
+    assert_eq!(
+        read_i64_saturating(&i64::MIN.as_varint()),
+        Ok((i64::MIN, &[] as &[u8]))
+    );
+
+    // Longer than any type
+    let mut huge = vec![0xFF; 30];
+    huge.extend_from_slice(&[0x7F, 1]);
+    assert_eq!(read_u128_saturating(&huge), Ok((u128::MAX, &[1][..])));
+    assert_eq!(read_i128_saturating(&huge), Ok((i128::MIN, &[1][..])));
+    huge[0] = 0xFE;
+    assert_eq!(read_i32_saturating(&huge), Ok((i32::MAX, &[1][..])));
+
+    assert_eq!(read_u8_saturating(&[]), Err(VartyIntError::EmptyBuffer));
+    assert!(matches!(
+        read_u8_saturating(&[0xFF, 0xFF]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+}
+
+#[test]
+fn truncating() {
+    let buf = write_many_new(&[0x1_2345u32, 5]);
+    assert_eq!(read_u8_truncating(&buf), Ok((0x45, &[5][..])));
+    assert_eq!(read_u16_truncating(&buf), Ok((0x2345, &[5][..])));
+    assert_eq!(read_u32_truncating(&buf), Ok((0x1_2345, &[5][..])));
+    for val in [0i64, 1, -1, 300, -300, 70_000, -70_000, i64::MIN, i64::MAX] {
+        let buf = val.as_varint();
+        assert_eq!(read_i8_truncating(&buf), Ok((val as i8, &[] as &[u8])));
+        assert_eq!(read_i16_truncating(&buf), Ok((val as i16, &[] as &[u8])));
+        assert_eq!(read_i64_truncating(&buf), Ok((val, &[] as &[u8])));
+    }
+    for val in [i128::MIN, i128::MAX, -1] {
+        assert_eq!(
+            read_i128_truncating(&val.as_varint()),
+            Ok((val, &[] as &[u8]))
+        );
+    }
+    // Wider than 128 bits: 2^140 - 1
+    let mut huge = vec![0xFF; 19];
+    huge.push(0x7F);
+    assert_eq!(read_u128_truncating(&huge), Ok((u128::MAX, &[] as &[u8])));
+    assert_eq!(read_u32_truncating(&huge), Ok((u32::MAX, &[] as &[u8])));
+    // Zigzag of 2^140 - 1 is -2^139, and 2^140 - 2 is 2^139 - 1
+    assert_eq!(read_i128_truncating(&huge), Ok((0, &[] as &[u8])));
+    huge[0] = 0xFE;
+    assert_eq!(read_i128_truncating(&huge), Ok((-1, &[] as &[u8])));
+    assert_eq!(read_i8_truncating(&huge), Ok((-1, &[] as &[u8])));
+
+    assert!(matches!(
+        read_u8_truncating(&[0x80]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+}
+
+#[test]
+fn zigzag() {
+    assert_eq!(zigzag_encode(0i8), 0u8);
+    assert_eq!(zigzag_encode(-1i8), 1u8);
+    assert_eq!(zigzag_encode(1i8), 2u8);
+    assert_eq!(zigzag_encode(i8::MAX), 254u8);
+    assert_eq!(zigzag_encode(i8::MIN), 255u8);
+    assert_eq!(zigzag_encode(i128::MIN), u128::MAX);
+    for val in [0i64, 1, -1, 1_000, -1_000, i64::MIN, i64::MAX] {
+        assert_eq!(zigzag_decode::<i64>(zigzag_encode(val)), val);
+        // Same as the varint encoding
+        assert_eq!(zigzag_encode(val).as_varint(), val.as_varint());
+    }
+    for val in 0..=u16::MAX {
+        assert_eq!(i16::zigzag_decode(val).zigzag_encode(), val);
+    }
+}
+
+#[test]
+fn wrappers() {
+    assert_eq!(Raw(-1i8).as_varint(), vec![0xFF, 0x01]);
+    assert_eq!(Raw(-1i64).as_varint().len(), 10);
+    assert_eq!(Raw(300u16).as_varint(), 300u16.as_varint());
+    assert_eq!(Zigzag(-300i16).as_varint(), (-300i16).as_varint());
+    for val in [0i64, 1, -1, i64::MIN, i64::MAX] {
+        assert_eq!(
+            Raw::<i64>::read_varint(&Raw(val).as_varint()),
+            Ok((Raw(val), &[] as &[u8]))
+        );
+        assert_eq!(
+            Zigzag::<i64>::read_varint(&Zigzag(val).as_varint()),
+            Ok((Zigzag(val), &[] as &[u8]))
+        );
+    }
+    // Mix encodings in one stream
+    let rows = [(Raw(-2i32), Zigzag(-2i32)), (Raw(5), Zigzag(5))];
+    let buf = write_many_new(&rows);
+    assert_eq!(buf, vec![0xFE, 0xFF, 0xFF, 0xFF, 0x0F, 3, 5, 10]);
+    assert_eq!(
+        read_many::<(Raw<i32>, Zigzag<i32>)>(&buf).collect::<Result<Vec<_>, _>>(),
+        Ok(rows.to_vec())
+    );
+    assert_eq!(
+        Raw::<i8>::read_varint(&[0xFF, 0x03]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+}
+
 #[test]
 fn traits1() {
     let x: i32 = 1;
@@ -210,6 +456,225 @@ fn vecs1() {
     );
 }
 
+#[test]
+fn for_each() {
+    let buf = write_many_new(&[(1u8, -1i32), (2, 300)]);
+    let mut seen = Vec::new();
+    assert_eq!(for_each_varint(&buf, |v: (u8, i32)| seen.push(v)), Ok(()));
+    assert_eq!(seen, vec![(1, -1), (2, 300)]);
+
+    let mut count = 0;
+    assert_eq!(
+        for_each_varint::<u8, _>(&[1, 2, 0x80], |_| count += 1),
+        Err(VartyIntError::NotEnoughBytes {
+            read: 1,
+            max_more: Some(1)
+        })
+    );
+    assert_eq!(count, 2);
+
+    // Custom error type
+    #[derive(Debug, PartialEq)]
+    enum MyError {
+        Decode(VartyIntError),
+        Zero,
+    }
+    impl From<VartyIntError> for MyError {
+        fn from(e: VartyIntError) -> Self {
+            MyError::Decode(e)
+        }
+    }
+    let check = |n: u8| if n == 0 { Err(MyError::Zero) } else { Ok(()) };
+    assert_eq!(try_for_each_varint(&[1, 0, 2], check), Err(MyError::Zero));
+    assert!(matches!(
+        try_for_each_varint(&[1, 0x80], check),
+        Err(MyError::Decode(VartyIntError::NotEnoughBytes { .. }))
+    ));
+    assert_eq!(try_for_each_varint(&[1, 2], check), Ok(()));
+}
+
+#[test]
+fn filter() {
+    let nums = [3i64, -10, 400, 7, -500];
+    let buf = write_many_new(&nums);
+    assert_eq!(
+        filter_values::<i64, _>(&buf, |n| *n < 0).collect::<Result<Vec<_>, _>>(),
+        Ok(vec![-10, -500])
+    );
+    assert_eq!(
+        filter_offsets::<i64, _>(&buf, |n| n.abs() > 100).collect::<Result<Vec<_>, _>>(),
+        Ok(vec![(2, 2, 400), (4, 5, -500)])
+    );
+    let buf = write_many_delta_new(&nums);
+    assert_eq!(
+        filter_values_delta::<i64, _>(&buf, |n| *n < 0).collect::<Result<Vec<_>, _>>(),
+        Ok(vec![-10, -500])
+    );
+
+    // Errors are returned, even though they don't "match"
+    let mut iter = filter_values::<u8, _>(&[1, 2, 0x80], |_| false);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(VartyIntError::NotEnoughBytes { .. }))
+    ));
+    assert_eq!(iter.next(), None);
+    let mut iter = filter_offsets::<u8, _>(&[0x80], |_| false);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(VartyIntError::NotEnoughBytes { .. }))
+    ));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn padded() {
+    let mut buf = vec![9];
+    assert_eq!(write_padded(1u64, 4, &mut buf), Ok(1));
+    assert_eq!(buf, vec![9, 0x81, 0x80, 0x80, 0x00]);
+    assert_eq!(read_u64(&buf[1..]), Ok((1, &[] as &[u8])));
+    patch_padded(300u64, &mut buf[1..]).unwrap();
+    assert_eq!(buf, vec![9, 0xAC, 0x82, 0x80, 0x00]);
+    assert_eq!(read_u64(&buf[1..]), Ok((300, &[] as &[u8])));
+    // Exact width
+    patch_padded(1u64 << 27, &mut buf[1..]).unwrap();
+    assert_eq!(read_u64(&buf[1..]), Ok((1 << 27, &[] as &[u8])));
+    assert_eq!(
+        patch_padded(1u64 << 28, &mut buf[1..]),
+        Err(VartyIntError::DoesNotFit)
+    );
+    assert_eq!(read_u64(&buf[1..]), Ok((1 << 27, &[] as &[u8])));
+
+    let mut buf = Vec::new();
+    write_padded(-1i32, 5, &mut buf).unwrap();
+    assert_eq!(buf, vec![0x81, 0x80, 0x80, 0x80, 0x00]);
+    assert_eq!(read_i32(&buf), Ok((-1, &[] as &[u8])));
+
+    // Too wide for the type, too small, or not one varint
+    let mut buf = vec![9];
+    assert_eq!(
+        write_padded(1u8, 3, &mut buf),
+        Err(VartyIntError::DoesNotFit)
+    );
+    assert_eq!(
+        write_padded(300u32, 1, &mut buf),
+        Err(VartyIntError::DoesNotFit)
+    );
+    assert_eq!(
+        write_padded(0u32, 0, &mut buf),
+        Err(VartyIntError::DoesNotFit)
+    );
+    assert_eq!(
+        write_padded((1u8, 2u8), 4, &mut buf),
+        Err(VartyIntError::DoesNotFit)
+    );
+    assert_eq!(buf, vec![9]);
+}
+
+#[test]
+fn single() {
+    assert_eq!(read_single::<i64>(&[0x01]), Ok(-1));
+    assert_eq!(read_single::<(u8, u8)>(&[1, 2]), Ok((1, 2)));
+    assert_eq!(
+        read_single::<u8>(&[1, 2]),
+        Err(VartyIntError::TrailingBytes)
+    );
+    assert_eq!(read_single::<u8>(&[]), Err(VartyIntError::EmptyBuffer));
+    assert!(matches!(
+        read_single::<u8>(&[0x80]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+}
+
+#[test]
+fn in_range() {
+    let buf = write_many_new(&[-5i32, 0, 5]);
+    assert_eq!(read_in_range::<i32, _>(&buf, -5..=5), Ok((-5, &buf[1..])));
+    assert_eq!(
+        read_in_range::<i32, _>(&buf, -4..=5),
+        Err(VartyIntError::OutOfRange)
+    );
+    assert_eq!(
+        read_in_range::<i32, _>(&buf[2..], ..5),
+        Err(VartyIntError::OutOfRange)
+    );
+    assert_eq!(
+        read_in_range::<i32, _>(&buf[2..], 5..),
+        Ok((5, &[] as &[u8]))
+    );
+    assert_eq!(
+        read_in_range::<i32, _>(&[], ..),
+        Err(VartyIntError::EmptyBuffer)
+    );
+}
+
+#[test]
+fn lossy() {
+    let buf = [
+        1, 0xFF, 0xFF, 0x7F, 2, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 3, 0x80,
+    ];
+    assert_eq!(
+        read_many_lossy::<u16>(&buf).collect::<Vec<_>>(),
+        vec![Ok(1), Err(1..4), Ok(2), Err(5..11), Ok(3), Err(12..13)]
+    );
+    assert_eq!(
+        read_many_lossy::<u64>(&buf).collect::<Vec<_>>(),
+        vec![
+            Ok(1),
+            Ok(0x1F_FFFF),
+            Ok(2),
+            Ok((1 << 36) - 1),
+            Ok(3),
+            Err(12..13)
+        ]
+    );
+    assert_eq!(read_many_lossy::<u8>(&[]).count(), 0);
+}
+
+#[test]
+fn write_returns_len() {
+    let mut buf = vec![9, 9];
+    assert_eq!(write_u32(300, &mut buf), 2);
+    assert_eq!((1u8, -300i32, 0u64).write_varint(&mut buf), 4);
+    assert_eq!(std::time::Duration::new(1, 300).write_varint(&mut buf), 3);
+    assert_eq!(write_many(&[1u64, 128, 1 << 14], &mut buf), 6);
+    assert_eq!(write_many_delta(&[100i32, 99], &mut buf), 3);
+    assert_eq!(buf.len(), 2 + 2 + 4 + 3 + 6 + 3);
+}
+
+#[test]
+fn delta_parallel() {
+    let nums = (0..10_000i32)
+        .map(|i| (i * 7919) % 10_007 - 5_000)
+        .collect::<Vec<_>>();
+    let buf = write_many_delta_new(&nums);
+    for threads in [0, 1, 2, 3, 8, 100, 50_000] {
+        assert_eq!(
+            read_many_delta_parallel::<i32>(&buf, threads),
+            Ok(nums.clone())
+        );
+    }
+    assert_eq!(read_many_delta_parallel::<u8>(&[], 4), Ok(vec![]));
+    assert_eq!(read_many_delta_parallel::<u8>(&[1, 2], 4), Ok(vec![1, 3]));
+    // The total of one chunk overflows, but the real total doesn't
+    let mut nums = vec![-i64::MAX; 11];
+    nums.extend([0, i64::MAX]);
+    let buf = write_many_delta_new(&nums);
+    assert_eq!(read_many_delta_checked_new::<i64>(&buf), Ok(nums.clone()));
+    for threads in [1, 2, 3, 13] {
+        assert_eq!(
+            read_many_delta_parallel::<i64>(&buf, threads),
+            Ok(nums.clone())
+        );
+    }
+
+    let mut bad = write_many_delta_new(&[1i32, 2, 3]);
+    bad.push(0x80);
+    assert!(matches!(
+        read_many_delta_parallel::<i32>(&bad, 4),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+}
+
 mod delta_enc {
     use super::*;
 
@@ -264,3 +729,3932 @@ mod delta_enc {
         vec![10_000_i64, 10_001, 10_002]
     );
 }
+
+mod transcode {
+    use super::*;
+
+    #[test]
+    fn le_u64_to_varint() {
+        let mut input = Vec::new();
+        for num in [0u64, 300, u64::MAX] {
+            input.extend_from_slice(&num.to_le_bytes());
+        }
+        let mut output = Vec::new();
+        transcode_le_u64_to_varint(&input, &mut output).unwrap();
+        assert_eq!(output, write_many_new(&[0u64, 300, u64::MAX]));
+
+        let mut back = Vec::new();
+        transcode_varint_to_le_u64(&output, &mut back).unwrap();
+        assert_eq!(back, input);
+    }
+
+    #[test]
+    fn le_u32_roundtrip() {
+        let input = [1u8, 0, 0, 0, 0x2c, 0x01, 0, 0];
+        let mut output = Vec::new();
+        transcode_le_u32_to_varint(&input, &mut output).unwrap();
+        assert_eq!(output, vec![1, 0b1010_1100, 0b0000_0010]);
+
+        let mut back = Vec::new();
+        transcode_varint_to_le_u32(&output, &mut back).unwrap();
+        assert_eq!(back, input);
+    }
+
+    #[test]
+    fn bad_input() {
+        let mut output = Vec::new();
+        assert!(matches!(
+            transcode_le_u64_to_varint(&[1, 2, 3], &mut output),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            transcode_varint_to_le_u32(&[0x80], &mut output),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod explain {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let expl = explain::<i32>(&[0x18, 0x01, 0xBF, 0xBB, 0x01])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(expl.len(), 3);
+        assert_eq!(expl[0].value, 12);
+        assert_eq!(expl[0].range, 0..1);
+        assert_eq!(expl[2].index, 2);
+        assert_eq!(expl[2].value, -12_000);
+        assert_eq!(expl[2].range, 2..5);
+        assert_eq!(expl[2].bytes, &[0xBF, 0xBB, 0x01]);
+        assert_eq!(expl[2].continuation_pattern(), "110");
+        assert!(expl.iter().all(|e| e.minimal));
+    }
+
+    #[test]
+    fn not_minimal() {
+        let expl = explain::<u32>(&[0x81, 0x80, 0x00])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(expl[0].value, 1);
+        assert!(!expl[0].minimal);
+    }
+
+    #[test]
+    fn error_stops() {
+        let mut iter = explain::<u8>(&[0x01, 0x80]);
+        assert!(iter.next().unwrap().is_ok());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+    }
+}
+
+mod tuples {
+    use super::*;
+
+    #[test]
+    fn write() {
+        assert_eq!(
+            (300u64, -1i32).as_varint(),
+            vec![0b1010_1100, 0b0000_0010, 0x01]
+        );
+        assert_eq!((1u8,).as_varint(), vec![1]);
+        assert_eq!(
+            write_many_new(&[(1u8, 2i8, 3u16), (4, 5, 6)]),
+            vec![1, 4, 3, 4, 10, 6]
+        );
+    }
+
+    #[test]
+    fn read() {
+        assert_eq!(
+            <(u64, i32)>::read_varint(&[0b1010_1100, 0b0000_0010, 0x01, 0x09]),
+            Ok(((300, -1), &[0x09_u8] as &[u8]))
+        );
+        assert_eq!(
+            read_many::<(u8, i8)>(&[1, 1, 2, 2])
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(1, -1), (2, 1)]
+        );
+        assert_eq!(<(u8, u8)>::zero(), (0, 0));
+    }
+
+    #[test]
+    fn bad() {
+        assert_eq!(
+            <(u8, u8)>::read_varint(&[]),
+            Err(VartyIntError::EmptyBuffer)
+        );
+        assert!(matches!(
+            <(u8, u8)>::read_varint(&[1]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            read_many::<(u8, u8)>(&[1, 2, 3])
+                .take(2)
+                .collect::<Vec<_>>(),
+            vec![
+                Ok((1, 2)),
+                Err(VartyIntError::NotEnoughBytes {
+                    read: 0,
+                    max_more: Some(2)
+                })
+            ]
+        );
+    }
+}
+
+mod ip_addr {
+    use crate::*;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn round_trip() {
+        let addrs: Vec<IpAddr> = vec![
+            Ipv4Addr::new(127, 0, 0, 1).into(),
+            Ipv4Addr::UNSPECIFIED.into(),
+            Ipv4Addr::BROADCAST.into(),
+            Ipv6Addr::LOCALHOST.into(),
+            "2001:db8::ff00:42:8329".parse().unwrap(),
+        ];
+        let buf = write_many_new(&addrs);
+        assert_eq!(
+            read_many::<IpAddr>(&buf).collect::<Result<Vec<_>, _>>(),
+            Ok(addrs)
+        );
+        assert_eq!(Ipv4Addr::new(0, 0, 1, 44).as_varint(), vec![0xAC, 0x02]);
+        assert_eq!(Ipv6Addr::LOCALHOST.as_varint(), vec![1]);
+        assert_eq!(
+            read_single::<Ipv4Addr>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            Ok(Ipv4Addr::BROADCAST)
+        );
+    }
+
+    #[test]
+    fn bad() {
+        assert_eq!(
+            read_single::<IpAddr>(&[5, 1]),
+            Err(VartyIntError::UnknownDiscriminant)
+        );
+        assert_eq!(
+            read_single::<IpAddr>(&[4]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 0,
+                max_more: Some(5)
+            })
+        );
+        // Too big for IPv4
+        assert_eq!(
+            read_single::<IpAddr>(&[4, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+    }
+}
+
+mod duration {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn roundtrip() {
+        for dur in [
+            Duration::ZERO,
+            Duration::from_millis(1_500),
+            Duration::new(u64::MAX, 999_999_999),
+        ] {
+            let mut buf = Vec::new();
+            write_duration(dur, &mut buf);
+            assert_eq!(read_duration(&buf), Ok((dur, &[] as &[u8])));
+        }
+    }
+
+    #[test]
+    fn write() {
+        let mut buf = Vec::new();
+        write_duration(Duration::from_millis(1_500), &mut buf);
+        assert_eq!(buf, vec![1, 0x80, 0xCA, 0xB5, 0xEE, 0x01]);
+        assert_eq!(
+            write_many_delta_new(&[Duration::from_secs(10), Duration::from_secs(11)]),
+            vec![10, 0, 1, 0]
+        );
+    }
+
+    #[test]
+    fn bad() {
+        assert_eq!(read_duration(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read_duration(&[1]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        // 1_000_000_000 nanoseconds
+        assert_eq!(
+            read_duration(&[0, 0x80, 0x94, 0xEB, 0xDC, 0x03]),
+            Err(VartyIntError::OutOfRange)
+        );
+    }
+}
+
+mod timestamps {
+    use crate::timestamp::*;
+    use crate::VartyIntError;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn resolutions() {
+        let time = UNIX_EPOCH + Duration::new(1_000, 123_456_789);
+        assert_eq!(to_epoch(time, Resolution::Seconds), 1_000);
+        assert_eq!(to_epoch(time, Resolution::Millis), 1_000_123);
+        assert_eq!(to_epoch(time, Resolution::Micros), 1_000_123_456);
+        assert_eq!(to_epoch(time, Resolution::Nanos), 1_000_123_456_789);
+
+        let before = UNIX_EPOCH - Duration::from_millis(1_500);
+        assert_eq!(to_epoch(before, Resolution::Seconds), -2);
+        assert_eq!(to_epoch(before, Resolution::Millis), -1_500);
+        assert_eq!(from_epoch(-1_500, Resolution::Millis), Some(before));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let mut buf = Vec::new();
+        assert_eq!(
+            write_timestamp(time, Resolution::Millis, &mut buf),
+            buf.len()
+        );
+        assert_eq!(
+            read_timestamp(&buf, Resolution::Millis),
+            Ok((time, &[] as &[u8]))
+        );
+
+        // Precision is lost
+        let mut buf = Vec::new();
+        write_timestamp(time, Resolution::Seconds, &mut buf);
+        assert_eq!(
+            read_timestamp(&buf, Resolution::Seconds),
+            Ok((
+                UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+                &[] as &[u8]
+            ))
+        );
+    }
+
+    #[test]
+    fn delta() {
+        let times = (0..10)
+            .map(|i| UNIX_EPOCH + Duration::from_secs(1_700_000_000 + i))
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        assert_eq!(
+            write_timestamps_delta(&times, Resolution::Seconds, &mut buf),
+            5 + 9
+        );
+        assert_eq!(buf.len(), 5 + 9);
+        assert_eq!(
+            read_timestamps_delta(&buf, Resolution::Seconds)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            times
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut buf = Vec::new();
+        crate::write_i128(1 << 100, &mut buf);
+        assert_eq!(
+            read_timestamp(&buf, Resolution::Seconds),
+            Err(VartyIntError::OutOfRange)
+        );
+
+        let buf = crate::write_many_new(&[i128::MAX, i128::MAX]);
+        assert_eq!(
+            read_timestamps_delta(&buf, Resolution::Seconds).collect::<Vec<_>>(),
+            vec![
+                Err(VartyIntError::OutOfRange),
+                Err(VartyIntError::DeltaOverflow)
+            ]
+        );
+    }
+}
+
+mod geo {
+    use crate::geo::*;
+    use crate::VartyIntError;
+
+    #[test]
+    fn write() {
+        let mut buf = Vec::new();
+        assert_eq!(write_coords(&[(1., 2.), (1.5, 1.)], 0.5, &mut buf), Ok(5));
+        assert_eq!(buf, vec![2, 4, 2, 8, 3]);
+
+        let mut buf = Vec::new();
+        assert_eq!(write_coords(&[], DEFAULT_PRECISION, &mut buf), Ok(1));
+        assert_eq!(buf, vec![0]);
+
+        for bad in [f64::NAN, f64::INFINITY, 1e300] {
+            assert_eq!(
+                write_coords(&[(1., 2.), (bad, 1.)], DEFAULT_PRECISION, &mut buf),
+                Err(VartyIntError::OutOfRange)
+            );
+        }
+        assert_eq!(
+            write_coords(&[(-9e18, 0.), (9e18, 0.)], 1., &mut buf),
+            Err(VartyIntError::DeltaOverflow)
+        );
+        assert_eq!(buf, vec![0]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let points = vec![(51.5007, -0.1246), (-33.8568, 151.2153), (0., 0.)];
+        let mut buf = Vec::new();
+        write_coords(&points, DEFAULT_PRECISION, &mut buf).unwrap();
+        buf.push(0xFF);
+        let (decoded, rest) = read_coords(&buf, DEFAULT_PRECISION).unwrap();
+        assert_eq!(decoded, points);
+        assert_eq!(rest, &[0xFF]);
+    }
+
+    #[test]
+    fn bad() {
+        assert!(matches!(
+            read_coords(&[2, 4, 2, 8], 0.5),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        // The latitudes overflow
+        let mut buf = vec![2];
+        crate::write_many(&[i64::MAX, i64::MAX, 0, 0], &mut buf);
+        assert_eq!(read_coords(&buf, 0.5), Err(VartyIntError::DeltaOverflow));
+    }
+
+    #[test]
+    fn morton() {
+        assert_eq!(morton_encode(0, 0), 0);
+        assert_eq!(morton_encode(u32::MAX, 0), 0x5555_5555_5555_5555);
+        assert_eq!(morton_encode(0, u32::MAX), 0xAAAA_AAAA_AAAA_AAAA);
+        for (x, y) in [(0, 0), (1, 2), (u32::MAX, 7), (123_456_789, 987_654_321)] {
+            assert_eq!(morton_decode(morton_encode(x, y)), (x, y));
+        }
+
+        let points = [(u32::MAX, u32::MAX), (0, 0), (5, 5)];
+        let mut buf = Vec::new();
+        assert_eq!(write_morton(&points, &mut buf), buf.len());
+        assert_eq!(buf[..3], [3, 0x01, 0x02]);
+        assert_eq!(read_morton(&buf), Ok((points.to_vec(), &[][..])));
+        assert!(matches!(
+            read_morton(&buf[..2]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+
+    #[test]
+    fn coords_morton() {
+        let points = [
+            (-90.0, -180.0),
+            (90.0, 180.0),
+            (0.0, 0.0),
+            (-0.0000001, 0.0000001),
+        ];
+        let mut buf = Vec::new();
+        write_coords_morton(&points, DEFAULT_PRECISION, &mut buf).unwrap();
+        let (decoded, rest) = read_coords_morton(&buf, DEFAULT_PRECISION).unwrap();
+        assert!(rest.is_empty());
+        for (p, d) in points.iter().zip(decoded.iter()) {
+            assert!((p.0 - d.0).abs() < 1e-9 && (p.1 - d.1).abs() < 1e-9);
+        }
+
+        // Doesn't fit in an i32
+        for bad in [(0., 215.), (-215., 0.), (f64::NAN, 0.)] {
+            assert_eq!(
+                write_coords_morton(&[(0., 0.), bad], DEFAULT_PRECISION, &mut buf),
+                Err(VartyIntError::OutOfRange)
+            );
+        }
+        assert!(write_coords_morton(&[(0., 215.)], 1e-6, &mut Vec::new()).is_ok());
+    }
+}
+
+mod polyline {
+    use crate::polyline::*;
+    use crate::VartyIntError;
+
+    #[test]
+    fn google_example() {
+        let points = vec![(38.5, -120.2), (40.7, -120.95), (43.252, -126.453)];
+        assert_eq!(
+            encode(&points, 5),
+            Ok("_p~iF~ps|U_ulLnnqC_mqNvxq`@".to_string())
+        );
+        assert_eq!(decode("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5), Ok(points));
+    }
+
+    #[test]
+    fn precision6() {
+        let points = vec![(53.349805, -6.26031), (53.349905, -6.26021)];
+        assert_eq!(decode(&encode(&points, 6).unwrap(), 6), Ok(points));
+        assert_eq!(encode(&[], 6), Ok(String::new()));
+        assert_eq!(decode("", 6), Ok(vec![]));
+    }
+
+    #[test]
+    fn bad() {
+        assert!(matches!(
+            decode("_p~iF", 5),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            decode("_p~i", 5),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            decode("_p~iF ps|U", 5),
+            Err(VartyIntError::InvalidCharacter)
+        );
+        assert_eq!(decode("é", 5), Err(VartyIntError::InvalidCharacter));
+        assert_eq!(
+            decode("~~~~~~~~~~~~~~~~~", 5),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        // Only 4 bits of the 13th character fit in a u64
+        assert_eq!(
+            decode("~~~~~~~~~~~~O?", 5),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert!(decode("~~~~~~~~~~~~N?", 5).is_ok());
+        // i64::MIN twice
+        assert_eq!(
+            decode("~~~~~~~~~~~~N?~~~~~~~~~~~~N?", 5),
+            Err(VartyIntError::DeltaOverflow)
+        );
+        assert_eq!(
+            decode("?~~~~~~~~~~~~N?~~~~~~~~~~~~N", 5),
+            Err(VartyIntError::DeltaOverflow)
+        );
+    }
+
+    #[test]
+    fn bad_encode() {
+        assert_eq!(encode(&[(f64::NAN, 0.)], 5), Err(VartyIntError::OutOfRange));
+        assert_eq!(
+            encode(&[(0., f64::INFINITY)], 5),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(encode(&[(1e300, 0.)], 5), Err(VartyIntError::OutOfRange));
+        // Each value fits, but the difference doesn't
+        assert_eq!(
+            encode(&[(9e13, 0.), (-9e13, 0.)], 5),
+            Err(VartyIntError::DeltaOverflow)
+        );
+    }
+}
+
+mod osm {
+    use crate::osm::*;
+    use crate::VartyIntError;
+
+    fn node(id: i64, lat: i64, lon: i64, timestamp: i64) -> DenseNode {
+        DenseNode {
+            id,
+            lat,
+            lon,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn write() {
+        let mut buf = Vec::new();
+        assert_eq!(
+            write_dense_nodes(&[node(1, 10, -10, 5), node(2, 11, -12, 5)], &mut buf),
+            12
+        );
+        assert_eq!(buf, vec![2, 2, 2, 2, 20, 2, 2, 19, 3, 2, 10, 0]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let nodes = (0..100)
+            .map(|i| {
+                node(
+                    1_000 + i,
+                    500_000_000 + i * 3,
+                    -60_000_000 - i,
+                    1_600_000_000,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        write_dense_nodes(&nodes, &mut buf);
+        buf.push(0xAA);
+        let (decoded, rest) = read_dense_nodes(&buf).unwrap();
+        assert_eq!(decoded, nodes);
+        assert_eq!(rest, &[0xAA]);
+
+        let (dense, _) = DenseNodes::new(&buf).unwrap();
+        assert_eq!(dense.iter().nth(99), Some(Ok(nodes[99])));
+    }
+
+    #[test]
+    fn bad() {
+        assert_eq!(read_dense_nodes(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read_dense_nodes(&[2, 2]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_dense_nodes(&[2, 2, 2, 1, 20, 1, 19, 1, 10]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+
+        // The ids overflow
+        let mut buf = vec![20];
+        crate::write_many(&[i64::MAX, i64::MAX], &mut buf);
+        buf.extend_from_slice(&[2, 0, 0, 2, 0, 0, 2, 0, 0]);
+        let (nodes, _) = DenseNodes::new(&buf).unwrap();
+        assert_eq!(
+            nodes.iter().collect::<Vec<_>>(),
+            vec![
+                Ok(node(i64::MAX, 0, 0, 0)),
+                Err(VartyIntError::DeltaOverflow)
+            ]
+        );
+    }
+}
+
+mod stride {
+    use crate::stride::*;
+    use crate::*;
+
+    fn roundtrip<T: DeltaInt + PartialEq>(nums: &[T]) -> Vec<u8> {
+        let mut buf = vec![];
+        let len = write_strided(nums, &mut buf);
+        assert_eq!(len, buf.len());
+        buf.push(0xAA);
+        let (decoded, rest) = read_strided::<T>(&buf).unwrap();
+        assert_eq!(decoded, nums);
+        assert_eq!(rest, &[0xAA]);
+        buf.pop();
+        buf
+    }
+
+    #[test]
+    fn runs() {
+        assert_eq!(roundtrip::<u32>(&[]), vec![0]);
+        assert_eq!(roundtrip(&[5u32]), vec![1, 2, 10]);
+        // Too short for a run
+        assert_eq!(roundtrip(&[1i32, 2, 3]), vec![3, 6, 2, 2, 2]);
+        assert_eq!(roundtrip(&[1i32, 2, 3, 4]), vec![4, 9, 2, 2]);
+        // Literals, a run, & literals
+        assert_eq!(
+            roundtrip(&[7u8, 0, 10, 20, 30, 40, 50, 51]),
+            vec![8, 2, 14, 13, 13, 20, 2, 2]
+        );
+        // Descending, & wrapping around
+        roundtrip(&[3u8, 2, 1, 0, 255, 254, 253]);
+        roundtrip(&[i64::MIN, 0, i64::MAX, -2, i64::MAX, i64::MIN]);
+
+        let ids = (0..10_000u64).map(|i| 1_000 + i).collect::<Vec<_>>();
+        assert_eq!(roundtrip(&ids).len(), 8);
+
+        let mixed = (0..1000i64)
+            .map(|i| if i % 100 < 50 { i * 3 } else { i * i % 17 })
+            .collect::<Vec<_>>();
+        assert!(roundtrip(&mixed).len() < write_many_delta_new(&mixed).len());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(read_strided::<u8>(&[]), Err(VartyIntError::EmptyBuffer));
+        // Empty segment
+        assert_eq!(read_strided::<u8>(&[1, 0]), Err(VartyIntError::OutOfRange));
+        // Run longer than the data
+        assert_eq!(
+            read_strided::<u8>(&[3, 9, 0, 2]),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert!(matches!(
+            read_strided::<u8>(&[4, 9, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_strided::<u8>(&[4, 8, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_strided::<u8>(&[4]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod string_table {
+    use crate::string_table::*;
+    use crate::VartyIntError;
+
+    #[test]
+    fn table() {
+        let mut table = StringTable::new();
+        assert!(table.is_empty());
+        assert_eq!(table.insert("a"), 0);
+        assert_eq!(table.insert("bc"), 1);
+        assert_eq!(table.insert("a"), 0);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(1), Some("bc"));
+        assert_eq!(table.get(2), None);
+        assert_eq!(table.index_of("bc"), Some(1));
+
+        let mut buf = Vec::new();
+        table.write(&mut buf);
+        assert_eq!(buf, vec![2, 1, b'a', 2, b'b', b'c']);
+        assert_eq!(StringTable::read(&buf), Ok((table, &[] as &[u8])));
+    }
+
+    #[test]
+    fn strings() {
+        let mut buf = Vec::new();
+        assert_eq!(write_strings(&["x", "y", "x", "x"], &mut buf), 10);
+        assert_eq!(buf, vec![2, 1, b'x', 1, b'y', 4, 0, 1, 0, 0]);
+        assert_eq!(
+            read_strings(&buf),
+            Ok((
+                vec!["x".to_string(), "y".into(), "x".into(), "x".into()],
+                &[] as &[u8]
+            ))
+        );
+    }
+
+    #[test]
+    fn bad() {
+        assert!(matches!(
+            StringTable::read(&[1, 3, b'a']),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            StringTable::read(&[1, 1, 0xFF]),
+            Err(VartyIntError::InvalidCharacter)
+        );
+        assert_eq!(
+            read_strings(&[1, 1, b'x', 1, 1]),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert!(matches!(
+            read_strings(&[1, 1, b'x', 2, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod sorted {
+    use crate::sorted::*;
+    use crate::*;
+
+    #[test]
+    fn merging() {
+        let a = write_many_delta_new(&[-5i64, 0, 0, 100]);
+        let b = write_many_delta_new(&[-10i64, 0, 50]);
+        let c = write_many_delta_new::<i64>(&[]);
+        let mut out = vec![9];
+        assert_eq!(merge::<i64>(&[&a, &b, &c], &mut out), Ok(7));
+        assert_eq!(
+            read_many_delta_new::<i64>(&out[1..]),
+            Ok(vec![-10, -5, 0, 0, 0, 50, 100])
+        );
+        out.truncate(1);
+        assert_eq!(merge_unique::<i64>(&[&a, &b, &c], &mut out), Ok(5));
+        assert_eq!(
+            read_many_delta_new::<i64>(&out[1..]),
+            Ok(vec![-10, -5, 0, 50, 100])
+        );
+        assert_eq!(merge::<i64>(&[], &mut out), Ok(0));
+
+        // Error leaves the output unchanged
+        let mut out = vec![9];
+        assert!(matches!(
+            merge::<i64>(&[&a, &[2, 0x80]], &mut out),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(out, vec![9]);
+    }
+
+    fn collect(iter: impl Iterator<Item = Result<u64, VartyIntError>>) -> Vec<u64> {
+        iter.collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    #[test]
+    fn set_ops() {
+        let a = write_many_delta_new(&[1u64, 2, 4, 8, 16, 32]);
+        let b = write_many_delta_new(&[2u64, 3, 4, 5, 32, 33]);
+        assert_eq!(collect(intersection(&a, &b)), vec![2, 4, 32]);
+        assert_eq!(collect(union(&a, &b)), vec![1, 2, 3, 4, 5, 8, 16, 32, 33]);
+        assert_eq!(collect(difference(&a, &b)), vec![1, 8, 16]);
+        assert_eq!(collect(difference(&b, &a)), vec![3, 5, 33]);
+    }
+
+    #[test]
+    fn empty() {
+        let a = write_many_delta_new(&[1u64, 2]);
+        assert_eq!(collect(intersection(&a, &[])), vec![]);
+        assert_eq!(collect(union(&[], &a)), vec![1, 2]);
+        assert_eq!(collect(difference(&a, &[])), vec![1, 2]);
+        assert_eq!(collect(difference(&[], &a)), vec![]);
+    }
+
+    #[test]
+    fn bad() {
+        let a = write_many_delta_new(&[1u64, 2]);
+        let mut iter = union::<u64>(&a, &[0x80]);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn overflow() {
+        // u64::MAX + 5
+        let a = write_many_new(&[u64::MAX, 5]);
+        let b = write_many_delta_new(&[1u64]);
+        let mut out = vec![];
+        assert_eq!(
+            merge::<u64>(&[&a, &b], &mut out),
+            Err(VartyIntError::DeltaOverflow)
+        );
+        assert_eq!(out, vec![]);
+        let mut iter = union::<u64>(&a, &b);
+        assert_eq!(iter.next(), Some(Ok(1)));
+        assert_eq!(iter.next(), Some(Ok(u64::MAX)));
+        assert_eq!(iter.next(), Some(Err(VartyIntError::DeltaOverflow)));
+        assert_eq!(iter.next(), None);
+    }
+}
+
+mod sorted_index {
+    use crate::sorted::*;
+    use crate::*;
+
+    #[test]
+    fn find() {
+        let nums = (0..100u32).map(|i| i * 3 + 1).collect::<Vec<_>>();
+        let buf = write_many_delta_new(&nums);
+        for every in [1, 2, 7, 100, 1000] {
+            let index = SortedIndex::new(&buf, every).unwrap();
+            assert_eq!(index.len(), 100);
+            for (i, n) in nums.iter().enumerate() {
+                assert_eq!(index.find(*n), Some(i));
+                assert_eq!(index.find(*n + 1), None);
+                assert_eq!(index.lower_bound(*n - 1), Some((i, *n)));
+            }
+            assert_eq!(index.lower_bound(0), Some((0, 1)));
+            assert_eq!(index.lower_bound(298), Some((99, 298)));
+            assert_eq!(index.lower_bound(299), None);
+        }
+    }
+
+    #[test]
+    fn rank_select() {
+        // With duplicates
+        let nums = (0..200u64).map(|i| i / 3 * 5).collect::<Vec<_>>();
+        let buf = write_many_delta_new(&nums);
+        for every in [1, 2, 3, 7, 1000] {
+            let index = SortedIndex::new(&buf, every).unwrap();
+            for (i, n) in nums.iter().enumerate() {
+                assert_eq!(index.select(i), Some(*n));
+            }
+            assert_eq!(index.select(200), None);
+            for x in 0..1_000 {
+                let expected = nums.iter().filter(|n| **n <= x).count();
+                assert_eq!(index.rank(x), expected, "{} {}", every, x);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_bad() {
+        let index = SortedIndex::<u32>::new(&[], 4).unwrap();
+        assert!(index.is_empty());
+        assert_eq!(index.lower_bound(0), None);
+        assert_eq!(index.rank(10), 0);
+        assert_eq!(index.select(0), None);
+        assert!(matches!(
+            SortedIndex::<u32>::new(&[1, 0x80], 4).unwrap_err(),
+            VartyIntError::NotEnoughBytes { .. }
+        ));
+        assert_eq!(
+            SortedIndex::<u64>::new(&write_many_new(&[u64::MAX, 5]), 4).unwrap_err(),
+            VartyIntError::DeltaOverflow
+        );
+    }
+}
+
+mod map {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn roundtrip() {
+        let map = (0..50u64)
+            .map(|i| (i * 7, i % 3))
+            .collect::<BTreeMap<_, _>>();
+        let mut buf = Vec::new();
+        assert_eq!(write_map(&map, &mut buf), 1 + 50 + 50);
+        assert_eq!(buf.len(), 1 + 50 + 50);
+        assert_eq!(read_map(&buf), Ok((map, &[] as &[u8])));
+
+        let map = BTreeMap::from([(-5i32, 1u8), (5, 2)]);
+        let mut buf = Vec::new();
+        write_map(&map, &mut buf);
+        assert_eq!(buf, vec![2, 9, 20, 1, 2]);
+        assert_eq!(read_map(&buf), Ok((map, &[] as &[u8])));
+    }
+
+    #[test]
+    fn bad() {
+        assert_eq!(read_map::<u64, u64>(&[]), Err(VartyIntError::EmptyBuffer));
+        assert_eq!(
+            read_map::<u64, u64>(&[2, 1, 1, 5]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 0,
+                max_more: Some(10)
+            })
+        );
+        // The keys go past u8::MAX
+        assert_eq!(
+            read_map::<u8, u8>(&[3, 100, 100, 100, 1, 2, 3]),
+            Err(VartyIntError::DeltaOverflow)
+        );
+    }
+}
+
+mod varint_vec {
+    use super::*;
+
+    #[test]
+    fn collect_and_iterate() {
+        let nums: VarIntVec<i32> = vec![1, -1, 300].into_iter().collect();
+        assert_eq!(nums.len(), 3);
+        assert_eq!(nums.as_bytes(), &[2, 1, 0xD8, 0x04]);
+        assert_eq!(nums.iter().collect::<Vec<_>>(), vec![1, -1, 300]);
+        assert_eq!(nums.iter().len(), 3);
+
+        let mut total = 0;
+        for num in &nums {
+            total += num;
+        }
+        assert_eq!(total, 300);
+        assert_eq!(
+            nums.into_iter().map(|n| n * 2).collect::<Vec<_>>(),
+            vec![2, -2, 600]
+        );
+    }
+
+    #[test]
+    fn extend() {
+        let mut nums = VarIntVec::<u64>::new();
+        assert!(nums.is_empty());
+        nums.extend([1, 2]);
+        nums.extend(&[3, 4]);
+        nums.push(5);
+        assert_eq!(nums.len(), 5);
+        assert_eq!(nums.clone().into_bytes(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            nums.iter().map(|n| n * 10).collect::<VarIntVec<_>>().len(),
+            5
+        );
+    }
+
+    #[test]
+    fn slice() {
+        let vec: VarIntVec<i64> = (-500..500).collect();
+        let slice = vec.as_slice();
+        assert_eq!(slice.len(), 1_000);
+        assert!(!slice.is_empty());
+        assert_eq!(slice.get(0), Some(-500));
+        assert_eq!(slice.get(999), Some(499));
+        assert_eq!(slice.get(1_000), None);
+        assert_eq!(slice.iter().len(), 1_000);
+        assert_eq!(slice.into_iter().sum::<i64>(), -500);
+        assert_eq!(slice.to_vec(), vec);
+
+        let same = VarIntSlice::<i64>::new(vec.as_bytes()).unwrap();
+        assert_eq!(same, slice);
+        for every in [1, 7, 1_000, 2_000] {
+            let index = slice.build_index(every);
+            for i in [0, 1, 6, 7, 500, 998, 999] {
+                assert_eq!(slice.get_indexed(&index, i), Some(i as i64 - 500));
+            }
+            assert_eq!(slice.get_indexed(&index, 1_000), None);
+        }
+
+        let empty = VarIntSlice::<u8>::new(&[]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.get_indexed(&empty.build_index(4), 0), None);
+        assert_eq!(
+            VarIntSlice::<u8>::new(&[1, 0xFF, 0xFF]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+    }
+
+    #[test]
+    fn from_bytes() {
+        let nums = VarIntVec::<u8>::from_bytes(vec![1, 2, 3]).unwrap();
+        assert_eq!(nums.len(), 3);
+        assert!(matches!(
+            VarIntVec::<u8>::from_bytes(vec![1, 0x80]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod owned {
+    use super::*;
+
+    fn decode_later(buf: Vec<u8>) -> impl Iterator<Item = Result<u32, VartyIntError>> {
+        read_many_delta_owned(buf)
+    }
+
+    #[test]
+    fn owned() {
+        let iter = read_many_owned::<i32, _>(vec![2, 1, 0xD8, 0x04]);
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>(), Ok(vec![1, -1, 300]));
+
+        let iter = decode_later(write_many_delta_new(&[10u32, 11, 15]));
+        let handle = std::thread::spawn(move || iter.collect::<Result<Vec<_>, _>>());
+        assert_eq!(handle.join().unwrap(), Ok(vec![10, 11, 15]));
+    }
+
+    #[test]
+    fn error_stops() {
+        let mut iter = read_many_owned::<u8, _>(vec![1, 0x80]);
+        assert_eq!(iter.next(), Some(Ok(1)));
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn shared() {
+        let buf: std::sync::Arc<[u8]> = write_many_delta_new(&[5i64, 3, 10]).into();
+        let a = read_many_delta_owned::<i64, _>(buf.clone());
+        let b = read_many_delta_owned::<i64, _>(buf.clone());
+        assert_eq!(std::sync::Arc::strong_count(&buf), 3);
+        assert_eq!(a.collect::<Result<Vec<_>, _>>(), Ok(vec![5, 3, 10]));
+        assert_eq!(b.count(), 3);
+
+        let buf: std::rc::Rc<[u8]> = vec![1u8, 2].into();
+        assert_eq!(read_many_owned::<u8, _>(buf).count(), 2);
+    }
+
+    #[test]
+    fn delta_overflow() {
+        let mut iter = read_many_delta_owned::<u64, _>(write_many_new(&[u64::MAX, 5, 1]));
+        assert_eq!(iter.next(), Some(Ok(u64::MAX)));
+        assert_eq!(iter.next(), Some(Err(VartyIntError::DeltaOverflow)));
+        assert_eq!(iter.next(), None);
+    }
+}
+
+mod segments {
+    use super::*;
+
+    #[test]
+    fn split() {
+        let nums = [1u64, 300, u64::MAX, 0, 1 << 40, 7];
+        let buf = write_many_new(&nums);
+        // Try every way of splitting the buffer into 3
+        for i in 0..=buf.len() {
+            for j in i..=buf.len() {
+                let segments = [&buf[..i], &buf[i..j], &[] as &[u8], &buf[j..]];
+                assert_eq!(
+                    read_many_segments::<u64>(&segments).collect::<Result<Vec<_>, _>>(),
+                    Ok(nums.to_vec()),
+                    "split at {} {}",
+                    i,
+                    j
+                );
+            }
+        }
+        // Tuples can be split too
+        let segments: [&[u8]; 3] = [&[1], &[0xAC], &[0x02, 2, 3]];
+        assert_eq!(
+            read_many_segments::<(u8, u16)>(&segments).collect::<Result<Vec<_>, _>>(),
+            Ok(vec![(1, 300), (2, 3)])
+        );
+    }
+
+    #[test]
+    fn bad() {
+        let segments: [&[u8]; 2] = [&[1, 0x80], &[0x80]];
+        let mut iter = read_many_segments::<u32>(&segments);
+        assert_eq!(iter.next(), Some(Ok(1)));
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+        assert_eq!(read_many_segments::<u32>(&[]).count(), 0);
+    }
+}
+
+mod varint_log {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("vartyint-test-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn append_and_read() {
+        let path = temp_path("log-append");
+        let mut log = VarIntLog::<i32>::open(&path).unwrap();
+        // Count, payload length, payload
+        assert_eq!(log.append(&[1, -2, 300]).unwrap(), 1 + 1 + 4);
+        assert_eq!(log.append(&[]).unwrap(), 2);
+        log.sync().unwrap();
+        drop(log);
+
+        // Reopening appends to the end
+        let mut log = VarIntLog::<i32>::open(&path).unwrap();
+        log.append(&[7]).unwrap();
+        log.sync().unwrap();
+
+        let mut reader = VarIntLogReader::<i32>::open(&path).unwrap();
+        assert_eq!(reader.next(), Some(Ok(vec![1, -2, 300])));
+        assert_eq!(reader.next(), Some(Ok(vec![])));
+        assert_eq!(reader.next(), Some(Ok(vec![7])));
+        assert_eq!(reader.next(), None);
+        assert!(!reader.is_torn());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn torn() {
+        let path = temp_path("log-torn");
+        let mut log = VarIntLog::<u64>::open(&path).unwrap();
+        log.append(&[1, 2]).unwrap();
+        log.append(&[1 << 40, 5]).unwrap();
+        log.sync().unwrap();
+        drop(log);
+        let full = std::fs::read(&path).unwrap();
+        assert_eq!(full[..4], [2, 2, 1, 2]);
+
+        // Every truncation of the 2nd batch is skipped
+        for len in 5..full.len() {
+            let mut reader = VarIntLogReader::<u64>::from_bytes(full[..len].to_vec());
+            assert_eq!(reader.next(), Some(Ok(vec![1, 2])));
+            assert_eq!(reader.next(), None);
+            assert!(reader.is_torn());
+        }
+
+        // Opening a torn log removes the torn batch before appending
+        std::fs::write(&path, &full[..full.len() - 1]).unwrap();
+        let mut log = VarIntLog::<u64>::open(&path).unwrap();
+        log.append(&[3]).unwrap();
+        drop(log);
+        let reader = VarIntLogReader::<u64>::open(&path).unwrap();
+        assert_eq!(
+            reader.collect::<Result<Vec<_>, _>>(),
+            Ok(vec![vec![1, 2], vec![3]])
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_corrupt() {
+        let path = temp_path("log-open-corrupt");
+        let mut log = VarIntLog::<u8>::open(&path).unwrap();
+        log.append(&[1, 2]).unwrap();
+        log.append(&[3]).unwrap();
+        drop(log);
+        let mut bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes, vec![2, 2, 1, 2, 1, 1, 3]);
+
+        // The 1st batch's count is wrong
+        bytes[0] = 3;
+        // The 1st batch's length is too long, so the rest is misread
+        let mut longer = bytes.clone();
+        longer[0] = 2;
+        longer[1] = 3;
+        // Not a log at all
+        let not_a_log = vec![0xFF; 30];
+        for bytes in [bytes, longer, not_a_log] {
+            std::fs::write(&path, &bytes).unwrap();
+            assert_eq!(
+                VarIntLog::<u8>::open(&path).unwrap_err().kind(),
+                std::io::ErrorKind::InvalidData
+            );
+            // Nothing is removed
+            assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt() {
+        // Count is 3, but there are only 2 integers
+        let mut reader = VarIntLogReader::<u8>::from_bytes(vec![3, 2, 1, 2, 1, 1, 1]);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(reader.next(), None);
+        // Count is 1, but there are 2 integers
+        let mut reader = VarIntLogReader::<u8>::from_bytes(vec![1, 2, 1, 2]);
+        assert_eq!(reader.next(), Some(Err(VartyIntError::TrailingBytes)));
+        assert_eq!(reader.next(), None);
+        assert!(!reader.is_torn());
+    }
+}
+
+mod records {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let payloads: [&[u8]; 4] = [b"abc", b"", &[0; 128], &[0x80, 0x80]];
+        let mut buf = Vec::new();
+        for payload in payloads.iter() {
+            let start = buf.len();
+            assert_eq!(write_record(payload, &mut buf), buf.len() - start);
+        }
+        assert_eq!(buf[..4], [3, b'a', b'b', b'c']);
+        let records = read_records(&buf).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            records.iter().map(|r| r.0).collect::<Vec<_>>(),
+            vec![0, 4, 5, 135]
+        );
+        assert_eq!(
+            records.iter().map(|r| r.1).collect::<Vec<_>>(),
+            payloads.to_vec()
+        );
+        assert_eq!(read_records(&[]).count(), 0);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut iter = read_records(&[1, 9, 3, 1, 2]);
+        assert_eq!(iter.next(), Some(Ok((0, &[9][..]))));
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+        let mut iter = read_records(&[0x80]);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntError::NotEnoughBytes { .. }))
+        ));
+        assert_eq!(iter.next(), None);
+    }
+}
+
+mod varint_file {
+    use super::*;
+
+    #[test]
+    fn read_from_reader() {
+        let buf = write_many_new(&[(1u8, 300u16), (2, 3)]);
+        let mut reader: &[u8] = &buf;
+        assert_eq!(
+            read_from::<(u8, u16), _>(&mut reader).unwrap(),
+            Some((1, 300))
+        );
+        assert_eq!(
+            read_from::<(u8, u16), _>(&mut reader).unwrap(),
+            Some((2, 3))
+        );
+        assert_eq!(read_from::<(u8, u16), _>(&mut reader).unwrap(), None);
+
+        let mut reader: &[u8] = &[0xFF, 0x80];
+        assert!(matches!(
+            read_from::<u32, _>(&mut reader),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::NotEnoughBytes {
+                    read: 2,
+                    max_more: Some(3)
+                }
+            ))
+        ));
+        let mut reader: &[u8] = &[0xFF, 0xFF, 0x01];
+        assert!(matches!(
+            read_from::<u8, _>(&mut reader),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::TooManyBytesForType
+            ))
+        ));
+
+        // An endless stream of continuation bytes
+        let mut reader = std::io::repeat(0x80);
+        assert!(matches!(
+            read_from::<u64, _>(&mut reader),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::TooManyBytesForType
+            ))
+        ));
+        let mut reader = std::io::repeat(0x80);
+        assert!(matches!(
+            read_from::<(u8, u16), _>(&mut reader),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::TooManyBytesForType
+            ))
+        ));
+    }
+
+    #[test]
+    fn values() {
+        let path = std::env::temp_dir().join(format!("vartyint-test-file-{}", std::process::id()));
+        let nums: Vec<u64> = (0..10_000).map(|i| i * i).collect();
+        std::fs::write(&path, write_many_new(&nums)).unwrap();
+        let read = VarIntFile::open(&path)
+            .unwrap()
+            .values::<u64>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read, nums);
+
+        // Truncated
+        std::fs::write(&path, [1, 0x80]).unwrap();
+        let mut iter = VarIntFile::open(&path).unwrap().deltas::<i64>();
+        assert_eq!(iter.next().unwrap().unwrap(), -1);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        // Too many continuation bytes
+        std::fs::write(&path, [0x80; 100]).unwrap();
+        let mut iter = VarIntFile::open(&path).unwrap().values::<u32>();
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntReadError::VartyIntError(
+                VartyIntError::TooManyBytesForType
+            )))
+        ));
+        assert!(iter.next().is_none());
+
+        // i8::MAX + 1
+        std::fs::write(&path, write_many_new(&[i8::MAX, 1, 1])).unwrap();
+        let mut iter = VarIntFile::open(&path).unwrap().deltas::<i8>();
+        assert_eq!(iter.next().unwrap().unwrap(), i8::MAX);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(VartyIntReadError::VartyIntError(
+                VartyIntError::DeltaOverflow
+            )))
+        ));
+        assert!(iter.next().is_none());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(VarIntFile::open(&path).is_err());
+    }
+
+    #[test]
+    fn into_reader() {
+        let path =
+            std::env::temp_dir().join(format!("vartyint-test-file-seek-{}", std::process::id()));
+        let nums: Vec<u64> = (0..100_000).map(|i| i * 7).collect();
+        std::fs::write(&path, write_many_new(&nums)).unwrap();
+        let mut reader = VarIntFile::open(&path).unwrap().into_reader();
+        assert_eq!(reader.skip(90_000).unwrap(), 90_000);
+        let pos = reader.position();
+        assert_eq!(reader.read_u64().unwrap(), Some(630_000));
+        reader.seek_to(1).unwrap();
+        assert_eq!(reader.read_u64().unwrap(), Some(7));
+        reader.seek_to(pos).unwrap();
+        assert_eq!(reader.read_u64().unwrap(), Some(630_000));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+mod adaptive_set {
+    use crate::adaptive_set::*;
+    use crate::*;
+
+    fn round_trip(set: &AdaptiveSet) {
+        let mut buf = vec![];
+        let len = set.write(&mut buf);
+        assert_eq!(len, buf.len());
+        buf.push(0xAA);
+        let (read, rest) = AdaptiveSet::read(&buf).unwrap();
+        assert_eq!(&read, set);
+        assert_eq!(read.container_kinds(), set.container_kinds());
+        assert_eq!(rest, &[0xAA]);
+    }
+
+    #[test]
+    fn kinds() {
+        let set: AdaptiveSet = [1, 5, 1_000]
+            .into_iter()
+            .chain(1 << 16..(1 << 16) + 50_000)
+            .chain((2 << 16..3 << 16).step_by(3))
+            .collect();
+        assert_eq!(
+            set.container_kinds(),
+            vec![
+                (0, ContainerKind::Array),
+                (1, ContainerKind::Runs),
+                (2, ContainerKind::Bitmap)
+            ]
+        );
+        assert_eq!(set.len(), 3 + 50_000 + 21_846);
+        assert!(set.contains(1_000));
+        assert!(!set.contains(999));
+        assert!(set.contains((1 << 16) + 49_999));
+        assert!(!set.contains((1 << 16) + 50_000));
+        assert!(set.contains((2 << 16) + 3));
+        assert!(!set.contains((2 << 16) + 4));
+        assert!(!set.contains(u32::MAX));
+        round_trip(&set);
+        round_trip(&AdaptiveSet::new());
+        round_trip(&[u32::MAX, 0].into_iter().collect());
+        round_trip(&(u32::MAX - 100..=u32::MAX).collect());
+    }
+
+    #[test]
+    fn insert() {
+        let mut set = AdaptiveSet::new();
+        assert!(set.is_empty());
+        for val in (0..20_000u32).rev() {
+            assert!(set.insert(val * 2));
+        }
+        assert!(!set.insert(0));
+        assert_eq!(set.len(), 20_000);
+        assert_eq!(set.container_kinds(), vec![(0, ContainerKind::Bitmap)]);
+        assert_eq!(set.iter().take(3).collect::<Vec<_>>(), vec![0, 2, 4]);
+        // Fill in the gaps
+        for val in 0..40_000 {
+            set.insert(val);
+        }
+        assert_eq!(set, (0..40_000).collect());
+        round_trip(&set);
+    }
+
+    #[test]
+    fn insert_runs() {
+        let mut set = AdaptiveSet::new();
+        for val in 0..65_536 {
+            set.insert(val);
+        }
+        assert_eq!(set.container_kinds(), vec![(0, ContainerKind::Runs)]);
+        assert_eq!(set.len(), 65_536);
+
+        let mut set: AdaptiveSet = (0..100).chain(200..300).collect();
+        assert_eq!(set.container_kinds(), vec![(0, ContainerKind::Runs)]);
+        for val in [150, 100, 199, 160, 161, 159, 1_000] {
+            assert!(set.insert(val));
+        }
+        assert_eq!(
+            set,
+            (0..101)
+                .chain([150])
+                .chain(159..162)
+                .chain(199..300)
+                .chain([1_000])
+                .collect()
+        );
+        round_trip(&set);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a: AdaptiveSet = (0..100_000).step_by(2).collect();
+        let b: AdaptiveSet = (50_000..200_000).step_by(3).collect();
+        let union = a.union(&b);
+        let intersection = a.intersection(&b);
+        for val in 0..210_000 {
+            let (in_a, in_b) = (
+                val % 2 == 0 && val < 100_000,
+                val % 3 == 2 && (50_000..200_000).contains(&val),
+            );
+            assert_eq!(union.contains(val), in_a || in_b, "{}", val);
+            assert_eq!(intersection.contains(val), in_a && in_b, "{}", val);
+        }
+        assert_eq!(a.union(&a), a);
+        assert_eq!(a.intersection(&AdaptiveSet::new()), AdaptiveSet::new());
+        round_trip(&union);
+        round_trip(&intersection);
+    }
+
+    #[test]
+    fn bad() {
+        // Unknown kind
+        assert_eq!(
+            AdaptiveSet::read(&[1, 0, 3]),
+            Err(VartyIntError::UnknownDiscriminant)
+        );
+        // Values not increasing
+        assert_eq!(
+            AdaptiveSet::read(&[1, 0, 0, 2, 5, 0]),
+            Err(VartyIntError::OutOfRange)
+        );
+        // Runs past the end of the chunk
+        assert_eq!(
+            AdaptiveSet::read(&[1, 0, 1, 1, 0xFF, 0xFF, 0x03, 1]),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert!(matches!(
+            AdaptiveSet::read(&[1, 0, 2, 0xFF]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        // Empty chunk
+        assert_eq!(
+            AdaptiveSet::read(&[1, 0, 0, 0]),
+            Err(VartyIntError::OutOfRange)
+        );
+    }
+}
+
+mod dictionary {
+    use crate::dictionary::*;
+    use crate::*;
+
+    #[test]
+    fn low_cardinality() {
+        let nums = (0..1000)
+            .map(|i| [70_000u32, 1_000_000, 5][i % 3])
+            .collect::<Vec<_>>();
+        let mut buf = vec![];
+        let len = write_dictionary(&nums, &mut buf);
+        assert_eq!(len, buf.len());
+        assert_eq!(encoding(&buf), Ok(Encoding::Dictionary));
+        // 1 byte per value, & a bit for the dictionary
+        assert!(len < 1015);
+        assert!(len < write_many_new(&nums).len());
+        buf.push(0xAA);
+        assert_eq!(read_dictionary::<u32>(&buf), Ok((nums, &[0xAA][..])));
+    }
+
+    #[test]
+    fn fallback_to_plain() {
+        let nums = (0..100i64).collect::<Vec<_>>();
+        let mut buf = vec![];
+        write_dictionary(&nums, &mut buf);
+        assert_eq!(encoding(&buf), Ok(Encoding::Plain));
+        assert_eq!(buf[1], 100);
+        assert_eq!(buf[2..], write_many_new(&nums)[..]);
+        assert_eq!(read_dictionary::<i64>(&buf), Ok((nums, &[][..])));
+
+        // Not worth it for only a couple of small values
+        let mut buf = vec![];
+        write_dictionary(&[1u8, 1], &mut buf);
+        assert_eq!(buf, vec![0, 2, 1, 1]);
+
+        let mut buf = vec![];
+        write_dictionary::<u8>(&[], &mut buf);
+        assert_eq!(buf, vec![0, 0]);
+        assert_eq!(read_dictionary::<u8>(&buf), Ok((vec![], &[][..])));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(read_dictionary::<u8>(&[]), Err(VartyIntError::EmptyBuffer));
+        assert_eq!(
+            read_dictionary::<u8>(&[2, 0]),
+            Err(VartyIntError::UnknownDiscriminant)
+        );
+        // Index 1 but only 1 value in the dictionary
+        assert_eq!(
+            read_dictionary::<u8>(&[1, 1, 5, 2, 0, 1]),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert!(matches!(
+            read_dictionary::<u8>(&[1, 1, 5, 3, 0, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod header {
+    use crate::header::*;
+    use crate::*;
+
+    #[test]
+    fn roundtrip() {
+        let header = Header::new(300).flags(u64::MAX);
+        assert!(header.has_flag(0b101));
+        assert!(!Header::new(1).flags(0b100).has_flag(0b101));
+        let mut buf = vec![];
+        assert_eq!(write_header(b"\x89VT", &header, &mut buf), 15);
+        buf.push(7);
+        assert_eq!(read_header(&buf, b"\x89VT", ..), Ok((header, &[7][..])));
+        assert_eq!(read_header(&buf, b"\x89VT", 300..), Ok((header, &[7][..])));
+        assert_eq!(
+            read_header(&buf, b"\x89VT", ..300),
+            Err(VartyIntError::UnsupportedVersion { version: 300 })
+        );
+
+        // Empty magic is allowed
+        let mut buf = vec![];
+        write_header(b"", &Header::default(), &mut buf);
+        assert_eq!(buf, vec![0, 0]);
+        assert_eq!(
+            read_header(&buf, b"", 0..1),
+            Ok((Header::default(), &[][..]))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            read_header(b"ABCD\x01\x00", b"ABCE", ..),
+            Err(VartyIntError::WrongMagic)
+        );
+        assert_eq!(
+            read_header(b"AC", b"ABCE", ..),
+            Err(VartyIntError::WrongMagic)
+        );
+        // Could be the start of the magic
+        assert_eq!(
+            read_header(b"AB", b"ABCE", ..),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 2,
+                max_more: Some(22)
+            })
+        );
+        assert_eq!(
+            read_header(b"", b"ABCE", ..),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 0,
+                max_more: Some(24)
+            })
+        );
+        assert!(matches!(
+            read_header(b"ABCE", b"ABCE", ..),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_header(b"ABCE\x01", b"ABCE", ..),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod mtf {
+    use crate::mtf::*;
+    use crate::*;
+
+    #[test]
+    fn stage() {
+        let mut enc = MoveToFront::new();
+        let tokens = [7u32, 8, 7, 9, 8, 8]
+            .into_iter()
+            .map(|x| enc.encode(x))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                MtfToken::New(7),
+                MtfToken::New(8),
+                MtfToken::Recent(1),
+                MtfToken::New(9),
+                MtfToken::Recent(2),
+                MtfToken::Recent(0),
+            ]
+        );
+        assert_eq!(enc.recent(), &[8, 9, 7]);
+
+        let mut dec = MoveToFront::new();
+        let decoded = tokens
+            .into_iter()
+            .map(|t| dec.decode(t))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(decoded, Ok(vec![7, 8, 7, 9, 8, 8]));
+        assert_eq!(dec, enc);
+        assert_eq!(
+            dec.decode(MtfToken::Recent(3)),
+            Err(VartyIntError::OutOfRange)
+        );
+
+        dec.reset();
+        assert!(dec.recent().is_empty());
+    }
+
+    #[test]
+    fn max_len() {
+        let mut mtf = MoveToFront::with_max_len(2);
+        assert_eq!(mtf.encode(1u8), MtfToken::New(1));
+        assert_eq!(mtf.encode(2), MtfToken::New(2));
+        assert_eq!(mtf.encode(3), MtfToken::New(3));
+        assert_eq!(mtf.encode(1), MtfToken::New(1));
+        assert_eq!(mtf.encode(3), MtfToken::Recent(1));
+        assert_eq!(mtf.recent(), &[3, 1]);
+
+        let mut mtf = MoveToFront::with_max_len(0);
+        assert_eq!(mtf.encode(1u8), MtfToken::New(1));
+        assert_eq!(mtf.encode(1u8), MtfToken::New(1));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let ids = (0..1000i64)
+            .map(|i| (i % 7) * 1_000_003 - 5)
+            .collect::<Vec<_>>();
+        let mut buf = vec![];
+        let len = write_mtf(&ids, &mut buf);
+        assert_eq!(len, buf.len());
+        assert!(len < write_many_new(&ids).len() / 2);
+        buf.push(1);
+        assert_eq!(read_mtf::<i64>(&buf), Ok((ids, &[1][..])));
+
+        assert_eq!(
+            read_mtf::<i64>(&[2, 127, 0, 1, 2]),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert!(matches!(
+            read_mtf::<i64>(&[2, 127, 0, 1]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_mtf::<i64>(&[1, 127, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_mtf::<i64>(&[1]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+
+    #[test]
+    fn roundtrip_max_len() {
+        // Many different values
+        let ids = (0..100_000u32).map(|i| i % 1_000).collect::<Vec<_>>();
+        let buf = write_mtf_new(&ids, DEFAULT_MAX_LEN);
+        assert_eq!(buf[..4], [0xA0, 0x8D, 0x06, 127]);
+        assert_eq!(read_mtf::<u32>(&buf), Ok((ids.clone(), &[][..])));
+
+        // Only the last 2 values are remembered
+        let buf = write_mtf_new(&[1u8, 2, 3, 1, 3], 2);
+        assert_eq!(buf, vec![5, 2, 0, 1, 0, 2, 0, 3, 0, 1, 2]);
+        assert_eq!(read_mtf::<u8>(&buf), Ok((vec![1, 2, 3, 1, 3], &[][..])));
+        // The position is past the max_len
+        assert_eq!(
+            read_mtf::<u8>(&[4, 2, 0, 1, 0, 2, 0, 3, 3]),
+            Err(VartyIntError::OutOfRange)
+        );
+    }
+
+    fn write_mtf_new<T: VarInt + PartialEq>(nums: &[T], max_len: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_mtf_with_max_len(nums, max_len, &mut buf);
+        buf
+    }
+}
+
+mod fixed_point {
+    use crate::fixed_point;
+    use crate::*;
+
+    #[test]
+    fn quantize() {
+        assert_eq!(fixed_point::quantize(1.005, 100.), Ok(100));
+        assert_eq!(fixed_point::quantize(-0.125, 1e3), Ok(-125));
+        assert_eq!(fixed_point::quantize(53.3498053, 1e7), Ok(533_498_053));
+        assert_eq!(
+            fixed_point::quantize(f64::NAN, 1.),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(
+            fixed_point::quantize(f64::INFINITY, 1.),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(
+            fixed_point::quantize(9.3e18, 1.),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(fixed_point::quantize(i64::MIN as f64, 1.), Ok(i64::MIN));
+        assert_eq!(fixed_point::dequantize(-125, 1e3), -0.125);
+
+        let mut buf = vec![];
+        assert_eq!(fixed_point::write(-1.5, 2., &mut buf), Ok(1));
+        assert_eq!(
+            fixed_point::write(1e20, 2., &mut buf),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(buf, vec![5]);
+        assert_eq!(fixed_point::read(&buf, 2.), Ok((-1.5, &[] as &[u8])));
+
+        let mut buf = vec![];
+        assert_eq!(
+            fixed_point::write_many(&[0.01, -0.02, 1.], 100., &mut buf),
+            Ok(4)
+        );
+        assert_eq!(
+            fixed_point::write_many(&[0.01, f64::NEG_INFINITY], 100., &mut buf),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(buf, vec![2, 3, 200, 1]);
+        assert_eq!(
+            fixed_point::read_many(&buf, 100.),
+            Ok(vec![0.01, -0.02, 1.])
+        );
+    }
+
+    #[test]
+    fn delta() {
+        let vals = (0..100).map(|i| i as f64 * 0.25 - 10.).collect::<Vec<_>>();
+        let mut buf = vec![0xAA];
+        fixed_point::write_many_delta(&vals, 4., &mut buf).unwrap();
+        assert_eq!(buf.len(), 101);
+        assert_eq!(fixed_point::read_many_delta(&buf[1..], 4.).unwrap(), vals);
+
+        // The difference is too big
+        assert_eq!(
+            fixed_point::write_many_delta(&[-9e18, 9e18], 1., &mut buf),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(
+            fixed_point::write_many_delta(&[1., f64::NAN], 1., &mut buf),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(buf.len(), 101);
+        assert_eq!(
+            fixed_point::read_many_delta(&write_many_new(&[i64::MAX, 1]), 1.),
+            Err(VartyIntError::OutOfRange)
+        );
+    }
+}
+
+mod zonemap {
+    use crate::zonemap::*;
+    use crate::*;
+
+    #[test]
+    fn range_queries() {
+        let nums = (0..1_000i64)
+            .map(|i| (i * 37) % 1_000 - 500)
+            .collect::<Vec<_>>();
+        let mut buf = vec![];
+        let len = write_zoned(&nums, 64, &mut buf);
+        assert_eq!(len, buf.len());
+        buf.push(0xAA);
+        let (zones, rest) = ZoneMap::<i64>::new(&buf).unwrap();
+        assert_eq!(rest, &[0xAA]);
+        assert_eq!(zones.num_blocks(), 16);
+        assert_eq!(zones.len(), 1_000);
+        assert_eq!(zones.zones()[15].count, 1_000 - 15 * 64);
+        for (i, chunk) in nums.chunks(64).enumerate() {
+            assert_eq!(zones.block(i).unwrap(), chunk);
+            assert_eq!(zones.zones()[i].min, *chunk.iter().min().unwrap());
+        }
+        assert_eq!(zones.range(..).unwrap(), nums);
+        assert_eq!(
+            zones.range(-10..10).unwrap(),
+            nums.iter()
+                .copied()
+                .filter(|n| (-10..10).contains(n))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(zones.range(500..).unwrap(), vec![]);
+        assert_eq!(zones.blocks_overlapping(500..).count(), 0);
+    }
+
+    #[test]
+    fn skipping() {
+        let nums = (0..100u32).collect::<Vec<_>>();
+        let mut buf = vec![];
+        write_zoned(&nums, 10, &mut buf);
+        let (zones, _) = ZoneMap::<u32>::new(&buf).unwrap();
+        assert_eq!(
+            zones.blocks_overlapping(15..=20).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            zones.blocks_overlapping(10..20).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(zones.blocks_overlapping(..=0).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(zones.range(15..=20).unwrap(), (15..=20).collect::<Vec<_>>());
+
+        let mut empty = vec![];
+        write_zoned::<u32>(&[], 10, &mut empty);
+        let (zones, _) = ZoneMap::<u32>::new(&empty).unwrap();
+        assert!(zones.is_empty());
+        assert_eq!(zones.range(..).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn bad() {
+        // min > max
+        assert_eq!(
+            ZoneMap::<u8>::new(&[1, 1, 5, 4, 1, 5]).unwrap_err(),
+            VartyIntError::OutOfRange
+        );
+        // Value outside min & max
+        let (zones, _) = ZoneMap::<u8>::new(&[1, 1, 4, 5, 1, 6]).unwrap();
+        assert_eq!(zones.block(0), Err(VartyIntError::OutOfRange));
+        assert!(matches!(
+            ZoneMap::<u8>::new(&[1, 1, 4, 5, 2, 6]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        // More integers than bytes
+        assert_eq!(
+            ZoneMap::<u8>::new(&[1, 2, 4, 5, 1, 4]).unwrap_err(),
+            VartyIntError::OutOfRange
+        );
+        // The totals don't fit in a usize
+        let mut buf = vec![];
+        2usize.write_varint(&mut buf);
+        for _ in 0..2 {
+            (usize::MAX, 0u8, 0u8, usize::MAX).write_varint(&mut buf);
+        }
+        assert_eq!(
+            ZoneMap::<u8>::new(&buf).unwrap_err(),
+            VartyIntError::OutOfRange
+        );
+    }
+}
+
+mod block_cache {
+    use crate::block_cache::*;
+    use crate::block_file::*;
+    use crate::varint_map::*;
+    use crate::zonemap::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn lru() {
+        let cache = BlockCache::new(2);
+        let mut decoded = vec![];
+        let mut get = |i: usize| {
+            cache
+                .get_or_insert_with(i, |i| {
+                    decoded.push(i);
+                    Ok::<_, ()>(i * 10)
+                })
+                .unwrap()
+        };
+        assert_eq!(get(1), 10);
+        assert_eq!(get(2), 20);
+        assert_eq!(get(1), 10);
+        // 2 is the least recently used
+        assert_eq!(get(3), 30);
+        assert_eq!(get(1), 10);
+        assert_eq!(get(2), 20);
+        assert_eq!(decoded, vec![1, 2, 3, 2]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!((cache.hits(), cache.misses()), (2, 4));
+
+        // Errors aren't cached
+        assert_eq!(cache.get_or_insert_with(5, |_| Err("bad")), Err("bad"));
+        assert_eq!(cache.get_or_insert_with(5, |_| Ok::<_, ()>(50)), Ok(50));
+        assert_eq!(cache.misses(), 6);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.clone().capacity(), 2);
+
+        let cache = BlockCache::new(0);
+        assert_eq!(cache.get_or_insert_with(1, |_| Ok::<_, ()>(1)), Ok(1));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn containers() {
+        let nums = (0..100u32).collect::<Vec<_>>();
+
+        let mut buf = vec![];
+        write_zoned(&nums, 10, &mut buf);
+        let (zones, _) = ZoneMap::<u32>::new(&buf).unwrap();
+        let zones = zones.with_cache(2);
+        for _ in 0..3 {
+            assert_eq!(zones.block(4).unwrap(), (40..50).collect::<Vec<_>>());
+        }
+        assert_eq!(zones.range(41..43).unwrap(), vec![41, 42]);
+        let cache = zones.cache().unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (3, 1));
+
+        let mut writer = BlockFileWriter::new(vec![]);
+        for block in nums.chunks(10) {
+            writer.write_block(block).unwrap();
+        }
+        let file = writer.finish().unwrap();
+        let mut reader = BlockFileReader::<_, u32>::open(Cursor::new(file))
+            .unwrap()
+            .with_cache(4);
+        for i in [1, 2, 1, 1, 9] {
+            assert_eq!(reader.read_block(i).unwrap()[0], i as u32 * 10);
+        }
+        assert_eq!(reader.cache().unwrap().hits(), 2);
+
+        let mut builder = VarIntMapBuilder::new().block_len(8);
+        for num in nums.iter() {
+            builder.insert(*num, &num.to_le_bytes()).unwrap();
+        }
+        let buf = builder.finish();
+        let map = VarIntMap::<u32>::new(&buf).unwrap().with_cache(1);
+        for key in 16..24 {
+            assert_eq!(map.get(key).unwrap(), Some(&key.to_le_bytes()[..]));
+        }
+        assert_eq!(map.cache().unwrap().hits(), 7);
+        assert!(VarIntMap::<u32>::new(&buf).unwrap().cache().is_none());
+    }
+}
+
+mod block_file {
+    use crate::block_file::*;
+    use crate::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() {
+        let blocks: Vec<Vec<i32>> = vec![
+            (0..1_000).collect(),
+            vec![],
+            vec![i32::MIN, i32::MAX],
+            (-50..50).map(|n| n * 1_000).collect(),
+        ];
+        let mut writer = BlockFileWriter::new(vec![0xAA, 0xBB]);
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(writer.write_block(block).unwrap(), i);
+        }
+        assert_eq!(writer.num_blocks(), 4);
+        let file = writer.finish().unwrap();
+
+        // Offsets are from where the writer started
+        let mut reader = BlockFileReader::<_, i32>::open(Cursor::new(&file[2..])).unwrap();
+        assert_eq!(reader.num_blocks(), 4);
+        assert_eq!(reader.len(), 1_102);
+        assert!(!reader.is_empty());
+        assert_eq!(reader.blocks()[1].len, 0);
+        assert_eq!(reader.blocks()[2].offset, reader.blocks()[1].offset);
+        for i in [3, 0, 2, 1] {
+            assert_eq!(reader.read_block(i).unwrap(), blocks[i]);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let file = BlockFileWriter::<_, u8>::new(Vec::new()).finish().unwrap();
+        assert_eq!(file.len(), 13);
+        let reader = BlockFileReader::<_, u8>::open(Cursor::new(file)).unwrap();
+        assert_eq!(reader.num_blocks(), 0);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn bad() {
+        let mut writer = BlockFileWriter::new(Vec::new());
+        writer.write_block(&[300u16, 1]).unwrap();
+        let file = writer.finish().unwrap();
+        assert!(BlockFileReader::<_, u16>::open(Cursor::new(&file[1..])).is_err());
+        assert!(BlockFileReader::<_, u16>::open(Cursor::new(&file[..file.len() - 1])).is_err());
+        assert!(BlockFileReader::<_, u16>::open(Cursor::new(&file[..5])).is_err());
+        // Values too big for the type
+        let mut reader = BlockFileReader::<_, u8>::open(Cursor::new(&file)).unwrap();
+        assert!(matches!(
+            reader.read_block(0),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::TooManyBytesForType
+            ))
+        ));
+    }
+}
+
+mod delta_stream {
+    use crate::delta_stream::*;
+    use crate::*;
+
+    #[test]
+    fn round_trip() {
+        let nums = (0..20_000i64).map(|n| n * n - 5_000).collect::<Vec<_>>();
+        let mut writer = DeltaWriter::new(CountingSink::new(Vec::new()));
+        let len = writer.extend(nums.iter().copied()).unwrap();
+        assert_eq!(writer.bytes_written(), len as u64);
+        let sink = writer.finish().unwrap();
+        assert_eq!(sink.count(), len as u64);
+        let out = sink.into_inner();
+        assert_eq!(out, write_many_delta_new(&nums));
+
+        let mut reader = DeltaReader::<_, i64>::new(&out[..]);
+        assert_eq!(reader.next().unwrap().unwrap(), -5_000);
+        assert_eq!(reader.position(), 2);
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), nums[1..]);
+    }
+
+    #[test]
+    fn errors() {
+        let mut reader = DeltaReader::<_, u8>::new(&[1, 2, 0xFF, 0xFF][..]);
+        assert_eq!(reader.next().unwrap().unwrap(), 1);
+        assert_eq!(reader.next().unwrap().unwrap(), 3);
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+
+        // 250 + 10
+        let buf = write_many_new(&[250u8, 10, 1]);
+        let mut reader = DeltaReader::<_, u8>::new(&buf[..]);
+        assert_eq!(reader.next().unwrap().unwrap(), 250);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(VartyIntReadError::VartyIntError(
+                VartyIntError::DeltaOverflow
+            )))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
+
+#[test]
+fn delta_checked_overflow() {
+    let nums = [i64::MIN, -5, 0, i64::MAX];
+    let buf = write_many_delta_new(&nums);
+    assert_eq!(read_many_delta_checked_new::<i64>(&buf), Ok(nums.to_vec()));
+
+    // i64::MAX + 1
+    let buf = write_many_new(&[i64::MAX, 1, 1]);
+    assert_eq!(
+        read_many_delta_checked::<i64>(&buf).collect::<Vec<_>>(),
+        vec![Ok(i64::MAX), Err(VartyIntError::DeltaOverflow)]
+    );
+    assert_eq!(
+        read_many_delta_checked_new::<i64>(&buf),
+        Err(VartyIntError::DeltaOverflow)
+    );
+    let buf = write_many_new(&[-1i8, i8::MIN]);
+    assert_eq!(
+        read_many_delta_checked_new::<i8>(&buf),
+        Err(VartyIntError::DeltaOverflow)
+    );
+    let buf = write_many_new(&[u32::MAX, 0, 0]);
+    assert_eq!(
+        read_many_delta_checked_new::<u32>(&buf),
+        Ok(vec![u32::MAX; 3])
+    );
+
+    // Other errors also stop it
+    assert_eq!(
+        read_many_delta_checked::<u8>(&[1, 0xFF]).collect::<Vec<_>>(),
+        vec![
+            Ok(1),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 1,
+                max_more: Some(1)
+            })
+        ]
+    );
+}
+
+#[test]
+fn delta_wrapping() {
+    let counters = [u16::MAX - 10, u16::MAX, 3, 20, 0, u16::MAX];
+    let buf = write_many_delta_wrapping_new(&counters);
+    assert_eq!(buf.len(), 6);
+    assert_eq!(
+        read_many_delta_wrapping_new::<u16>(&buf),
+        Ok(counters.to_vec())
+    );
+    // Normal delta encoding is much bigger
+    assert_eq!(write_many_delta_new(&counters.map(|c| c as i32)).len(), 12);
+
+    let nums = [i64::MIN, i64::MAX, 0, i64::MIN + 1, -1];
+    let mut buf = vec![];
+    assert_eq!(write_many_delta_wrapping(&nums, &mut buf), buf.len());
+    assert_eq!(read_many_delta_wrapping_new::<i64>(&buf), Ok(nums.to_vec()));
+    // i64::MIN to i64::MAX is -1
+    assert_eq!(buf[10], 1);
+
+    for nums in [vec![], vec![0u8], vec![255, 0, 128, 127, 255]] {
+        let buf = write_many_delta_wrapping_new(&nums);
+        assert_eq!(read_many_delta_wrapping_new::<u8>(&buf), Ok(nums));
+    }
+
+    assert_eq!(
+        read_many_delta_wrapping::<u8>(&[2, 0xFF]).collect::<Vec<_>>(),
+        vec![
+            Ok(1),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 1,
+                max_more: Some(1)
+            })
+        ]
+    );
+}
+
+#[test]
+fn signed_deltas_unsigned_gaps() {
+    let nums = [0i64, -1_000, 1_000, i64::MIN / 2, i64::MAX / 2];
+    let mut buf = vec![1];
+    assert_eq!(write_deltas(&nums, &mut buf), Ok(buf.len() - 1));
+    assert_eq!(buf[1..], write_many_delta_new(&nums)[..]);
+    assert_eq!(read_deltas::<i64>(&buf[1..]), Ok(nums.to_vec()));
+    assert_eq!(
+        write_deltas(&[i64::MAX, -2], &mut buf),
+        Err(VartyIntError::DeltaOverflow)
+    );
+    assert_eq!(buf[1..], write_many_delta_new(&nums)[..]);
+    assert_eq!(
+        read_deltas::<i8>(&write_many_new(&[100i8, 100])),
+        Err(VartyIntError::DeltaOverflow)
+    );
+
+    let nums = [0u64, 0, 5, 1 << 40, u64::MAX];
+    let mut buf = vec![];
+    assert_eq!(write_gaps(&nums, &mut buf), Ok(buf.len()));
+    assert_eq!(buf, write_many_delta_new(&nums));
+    assert_eq!(read_gaps::<u64>(&buf), Ok(nums.to_vec()));
+    assert_eq!(
+        write_gaps(&[1u8, 2, 0], &mut buf),
+        Err(VartyIntError::OutOfRange)
+    );
+    assert_eq!(buf, write_many_delta_new(&nums));
+    assert_eq!(
+        read_gaps::<u8>(&write_many_new(&[200u8, 100])),
+        Err(VartyIntError::DeltaOverflow)
+    );
+    assert_eq!(read_gaps::<u8>(&[]), Ok(vec![]));
+}
+
+#[test]
+fn read_many_size_hint_fused() {
+    let buf = write_many_new(&[1u32, 300, u32::MAX]);
+    let mut iter = read_many::<u32>(&buf);
+    assert_eq!(iter.size_hint(), (1, Some(8)));
+    assert_eq!(iter.next(), Some(Ok(1)));
+    assert_eq!(iter.size_hint(), (1, Some(7)));
+    assert_eq!(iter.remaining(), &buf[1..]);
+    assert_eq!(iter.by_ref().count(), 2);
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+    assert_eq!(iter.next(), None);
+
+    let buf = write_many_delta_new(&[5i64, 10, 15]);
+    let iter = read_many_delta::<i64>(&buf);
+    assert_eq!(iter.size_hint(), (1, Some(3)));
+    assert_eq!(
+        iter.clone().collect::<Result<Vec<_>, _>>(),
+        Ok(vec![5, 10, 15])
+    );
+
+    // The lower bound is never more than what's returned, even for invalid data
+    for buf in [&[0xFF; 20][..], &[0x80; 3]] {
+        let iter = read_many::<u8>(buf);
+        assert_eq!(iter.size_hint(), (1, Some(buf.len())));
+        assert_eq!(iter.count(), 1);
+        let iter = read_many_delta::<u8>(buf);
+        assert_eq!(iter.size_hint(), (1, Some(buf.len())));
+        assert_eq!(iter.count(), 1);
+    }
+
+    // Only 1 error, then nothing
+    let mut iter = read_many::<u8>(&[1, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(iter.next(), Some(Ok(1)));
+    assert_eq!(iter.next(), Some(Err(VartyIntError::TooManyBytesForType)));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.remaining(), &[]);
+    let mut iter = read_many_delta::<u8>(&[1, 0xFF]);
+    assert_eq!(iter.next(), Some(Ok(1)));
+    assert!(matches!(
+        iter.next(),
+        Some(Err(VartyIntError::NotEnoughBytes { .. }))
+    ));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn hex() {
+    assert_eq!(to_hex(&[]), "");
+    assert_eq!(to_hex(&[0, 0x0F, 0xFF]), "00 0f ff");
+    assert_eq!(from_hex("00 0f FF"), Ok(vec![0, 0x0F, 0xFF]));
+    assert_eq!(from_hex(" 000f\nff\t"), Ok(vec![0, 0x0F, 0xFF]));
+    assert_eq!(from_hex(""), Ok(vec![]));
+    assert_eq!(from_hex("00 0f f"), Err(VartyIntError::InvalidCharacter));
+    assert_eq!(from_hex("0x00"), Err(VartyIntError::InvalidCharacter));
+    assert_eq!(from_hex("éé"), Err(VartyIntError::InvalidCharacter));
+
+    let nums = [i64::MIN, -1, 0, 1, i64::MAX];
+    let hex = to_hex(&write_many_new(&nums));
+    assert_eq!(decode_hex_varints::<i64>(&hex), Ok(nums.to_vec()));
+    assert!(matches!(
+        decode_hex_varints::<i64>("01 ff"),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+}
+
+#[test]
+fn transcode_widths() {
+    let nums = [0u32, 1, 300, u32::MAX];
+    let input = write_many_new(&nums);
+    let mut output = vec![9];
+    assert_eq!(transcode::<u32, u64>(&input, &mut output), Ok(4));
+    assert_eq!(output[1..], input[..]);
+    assert_eq!(
+        transcode::<u32, u16>(&input, &mut output),
+        Err(TranscodeError {
+            index: 3,
+            offset: 4,
+            error: VartyIntError::OutOfRange
+        })
+    );
+    assert_eq!(output[1..], input[..]);
+
+    // Unsigned to signed changes the bytes
+    let mut output = vec![];
+    assert_eq!(transcode::<u32, i64>(&input, &mut output), Ok(4));
+    assert_eq!(
+        read_many::<i64>(&output).collect::<Result<Vec<_>, _>>(),
+        Ok(nums.map(i64::from).to_vec())
+    );
+    assert_eq!(
+        transcode::<i64, u8>(&write_many_new(&[-1i64]), &mut output)
+            .unwrap_err()
+            .to_string(),
+        "OutOfRange at value 0 (byte 0)"
+    );
+
+    let err = transcode::<u32, u64>(&[1, 0x80], &mut output).unwrap_err();
+    assert_eq!((err.index, err.offset), (1, 1));
+    assert!(matches!(err.error, VartyIntError::NotEnoughBytes { .. }));
+    assert_eq!(transcode::<u32, u64>(&[], &mut output), Ok(0));
+}
+
+#[test]
+fn usize_isize_portable() {
+    assert_eq!(usize::MAX_LEN, u64::MAX_LEN);
+    assert_eq!(isize::MAX_LEN, i64::MAX_LEN);
+    for val in [0usize, 1, 300, u32::MAX as usize, usize::MAX] {
+        assert_eq!(val.as_varint(), (val as u64).as_varint());
+    }
+    for val in [0isize, -1, 300, i32::MIN as isize, isize::MIN, isize::MAX] {
+        assert_eq!(val.as_varint(), (val as i64).as_varint());
+    }
+    // Padded values are read the same as u64
+    let padded = [0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
+    assert_eq!(read_usize(&padded), Ok((1, &[][..])));
+    assert_eq!(read_u64(&padded), Ok((1, &[][..])));
+    let too_long = [
+        0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01,
+    ];
+    assert_eq!(
+        read_usize(&too_long),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+    assert_eq!(
+        read_isize(&too_long),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+}
+
+#[cfg(target_pointer_width = "32")]
+#[test]
+fn usize_isize_too_big() {
+    let buf = write_many_new(&[u32::MAX as u64 + 1]);
+    assert_eq!(read_usize(&buf), Err(VartyIntError::OutOfRange));
+    assert_eq!(read_usize_saturating(&buf), Ok((usize::MAX, &[][..])));
+    let buf = write_many_new(&[i32::MIN as i64 - 1]);
+    assert_eq!(read_isize(&buf), Err(VartyIntError::OutOfRange));
+    assert_eq!(read_isize_saturating(&buf), Ok((isize::MIN, &[][..])));
+}
+
+mod reverse {
+    use crate::reverse::*;
+    use crate::*;
+
+    #[test]
+    fn round_trip() {
+        let nums = [0i64, -1, 63, -64, 64, i64::MAX, i64::MIN, 1_000_000];
+        let mut buf = Vec::new();
+        for num in nums {
+            assert_eq!(write_rev(num, &mut buf), num.as_varint().len());
+        }
+        let mut rest = &buf[..];
+        for num in nums.iter().rev() {
+            let (got, newrest) = read_rev::<i64>(rest).unwrap();
+            assert_eq!(got, *num);
+            rest = newrest;
+        }
+        assert!(rest.is_empty());
+        assert_eq!(read_many_rev::<i64>(&buf).count(), nums.len());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(read_rev::<u32>(&[]), Err(VartyIntError::EmptyBuffer));
+        assert_eq!(
+            read_rev::<u32>(&[0x80, 0x80]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 2,
+                max_more: Some(3)
+            })
+        );
+        assert_eq!(
+            read_rev::<u8>(&[0x00, 0x80, 0x80, 0x80]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            read_rev::<u8>(&[0x7F, 0xFF]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            read_many_rev::<u8>(&[0x80, 1]).collect::<Vec<_>>(),
+            vec![
+                Ok(1),
+                Err(VartyIntError::NotEnoughBytes {
+                    read: 1,
+                    max_more: Some(1)
+                })
+            ]
+        );
+    }
+}
+
+mod varint_reader {
+    use super::*;
+
+    /// Returns at most 1 byte per `read`
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn small_buffer() {
+        let nums = [1u64, 300, u64::MAX, 0, 1 << 40];
+        let buf = write_many_new(&nums);
+        let mut reader = VarIntReader::with_capacity(2, Trickle(&buf));
+        let mut got = Vec::new();
+        while let Some(num) = reader.read_u64().unwrap() {
+            got.push(num);
+        }
+        assert_eq!(got, nums);
+        assert_eq!(reader.position(), buf.len() as u64);
+        assert_eq!(reader.read_u64().unwrap(), None);
+    }
+
+    #[test]
+    fn small_buffer_tuples() {
+        let mut reader = VarIntReader::with_capacity(1, &[1u8, 1][..]);
+        assert_eq!(reader.read_value::<(u8, i32)>().unwrap(), Some((1, -1)));
+        assert_eq!(reader.read_value::<(u8, i32)>().unwrap(), None);
+
+        let vals = [(1u32, 300u64, -5i8), (u32::MAX, 0, 7)];
+        let buf = write_many_new(&vals);
+        let mut reader = VarIntReader::with_capacity(2, Trickle(&buf));
+        assert_eq!(reader.read_value().unwrap(), Some(vals[0]));
+        assert_eq!(reader.read_value().unwrap(), Some(vals[1]));
+        assert_eq!(reader.read_value::<(u32, u64, i8)>().unwrap(), None);
+
+        // Ends part way through
+        let mut reader = VarIntReader::with_capacity(1, &buf[..buf.len() - 1]);
+        assert_eq!(reader.read_value().unwrap(), Some(vals[0]));
+        assert!(matches!(
+            reader.read_value::<(u32, u64, i8)>(),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::NotEnoughBytes { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn writer_round_trip() {
+        let mut writer = VarIntWriter::new(Vec::new());
+        for num in [5i64, -100, i64::MAX, i64::MIN] {
+            writer.write_delta(num).unwrap();
+        }
+        writer.write_i16(-300).unwrap();
+        let out = writer.into_inner().unwrap();
+        let mut reader = VarIntReader::new(&out[..]);
+        for num in [5i64, -100, i64::MAX, i64::MIN] {
+            assert_eq!(reader.read_delta().unwrap(), Some(num));
+        }
+        assert_eq!(reader.peek::<i16>().unwrap(), Some(-300));
+        assert_eq!(reader.peek::<i16>().unwrap(), Some(-300));
+        assert_eq!(reader.read_i16().unwrap(), Some(-300));
+        assert_eq!(reader.read_delta().unwrap(), None);
+    }
+
+    #[test]
+    fn errors() {
+        let mut reader = VarIntReader::new(&[1, 0xFF, 0x7F, 0xAC][..]);
+        assert_eq!(reader.read_u8().unwrap(), Some(1));
+        // Too big for a u8. Nothing is read, so it can be read as a u16
+        assert!(matches!(
+            reader.read_u8(),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::TooManyBytesForType
+            ))
+        ));
+        assert_eq!(reader.position(), 1);
+        assert_eq!(reader.read_u16().unwrap(), Some(0x3FFF));
+        assert!(matches!(
+            reader.read_u32(),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::NotEnoughBytes {
+                    read: 1,
+                    max_more: Some(4)
+                }
+            ))
+        ));
+        assert_eq!(reader.position(), 3);
+        assert_eq!(reader.buffer(), &[0xAC]);
+    }
+}
+
+mod varint_map {
+    use crate::varint_map::*;
+    use crate::*;
+
+    fn build(block_len: usize, n: u32) -> Vec<u8> {
+        let mut builder = VarIntMapBuilder::new().block_len(block_len);
+        for i in 0..n {
+            builder.insert(i * 3 + 1, i.to_string().as_bytes()).unwrap();
+        }
+        assert_eq!(builder.len(), n as usize);
+        builder.finish()
+    }
+
+    #[test]
+    fn get_range() {
+        for block_len in [1, 2, 7, 64, 1000] {
+            let buf = build(block_len, 500);
+            let map = VarIntMap::<u32>::new(&buf).unwrap();
+            assert_eq!(map.len(), 500);
+            assert_eq!(map.num_blocks(), 500usize.div_ceil(block_len));
+            for i in 0..500u32 {
+                assert_eq!(map.get(i * 3 + 1), Ok(Some(i.to_string().as_bytes())));
+                assert_eq!(map.get(i * 3), Ok(None));
+                assert_eq!(map.contains_key(i * 3 + 2), Ok(false));
+            }
+            assert_eq!(map.get(u32::MAX), Ok(None));
+            let range = map.range(10..=22).unwrap();
+            assert_eq!(
+                range.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+                vec![10, 13, 16, 19, 22]
+            );
+            assert_eq!(map.range(10..22).unwrap().len(), 4);
+            assert_eq!(map.range(..2).unwrap(), vec![(1, &b"0"[..])]);
+            assert_eq!(map.range(1_496..).unwrap(), vec![(1_498, &b"499"[..])]);
+            assert_eq!(map.range(5_000..).unwrap(), vec![]);
+            assert_eq!(map.to_vec().unwrap().len(), 500);
+        }
+    }
+
+    #[test]
+    fn empty_values() {
+        let mut builder = VarIntMapBuilder::<u64>::new();
+        assert!(builder.is_empty());
+        let buf = builder.finish();
+        let map = VarIntMap::<u64>::new(&buf).unwrap();
+        assert!(map.is_empty());
+        assert_eq!(map.get(0), Ok(None));
+        assert_eq!(map.to_vec(), Ok(vec![]));
+
+        builder.insert(0, b"").unwrap();
+        builder.insert(u64::MAX, &[0xFF; 300]).unwrap();
+        assert_eq!(
+            builder.insert(u64::MAX, b""),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(builder.insert(5, b""), Err(VartyIntError::OutOfRange));
+        let buf = builder.finish();
+        let map = VarIntMap::<u64>::new(&buf).unwrap();
+        assert_eq!(map.get(0), Ok(Some(&b""[..])));
+        assert_eq!(map.get(u64::MAX), Ok(Some(&[0xFF; 300][..])));
+    }
+
+    #[test]
+    fn invalid() {
+        let buf = build(4, 10);
+        assert!(VarIntMap::<u32>::new(&buf).is_ok());
+        assert_eq!(
+            VarIntMap::<u32>::new(b"VTKX\x01\x00").err(),
+            Some(VartyIntError::WrongMagic)
+        );
+        assert_eq!(
+            VarIntMap::<u32>::new(b"VTKV\x02\x00").err(),
+            Some(VartyIntError::UnsupportedVersion { version: 2 })
+        );
+        assert!(matches!(
+            VarIntMap::<u32>::new(&buf[..buf.len() - 1]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        let mut longer = buf.clone();
+        longer.push(0);
+        assert_eq!(
+            VarIntMap::<u32>::new(&longer).err(),
+            Some(VartyIntError::TrailingBytes)
+        );
+        // Keys too big for the type
+        let mut builder = VarIntMapBuilder::<u64>::new();
+        builder.insert(1 << 40, b"").unwrap();
+        assert!(VarIntMap::<u16>::new(&builder.finish()).is_err());
+
+        // One block which claims to have more entries than bytes
+        let mut buf = b"VTKV\x01\x00".to_vec();
+        (1u64 << 50, 1u8, 0u8, 1u64 << 50, 2u8).write_varint(&mut buf);
+        buf.extend_from_slice(&[0, 0]);
+        assert_eq!(
+            VarIntMap::<u32>::new(&buf).err(),
+            Some(VartyIntError::OutOfRange)
+        );
+    }
+}
+
+mod varint_reader_seek {
+    use crate::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn skip() {
+        let nums = (0..1000u32).map(|i| i * 1_000).collect::<Vec<_>>();
+        let buf = write_many_new(&nums);
+        let mut reader = VarIntReader::with_capacity(16, &buf[..]);
+        assert_eq!(reader.skip(0).unwrap(), 0);
+        assert_eq!(reader.skip(1).unwrap(), 1);
+        assert_eq!(reader.position(), 1);
+        assert_eq!(reader.skip(500).unwrap(), 500);
+        assert_eq!(reader.read_u32().unwrap(), Some(501_000));
+        assert_eq!(reader.skip(1000).unwrap(), 498);
+        assert_eq!(reader.position(), buf.len() as u64);
+        assert_eq!(reader.read_u32().unwrap(), None);
+        assert_eq!(reader.skip(1).unwrap(), 0);
+
+        let mut reader = VarIntReader::with_capacity(1, &[1, 0xAC, 0x02, 0x80, 0x80][..]);
+        assert!(matches!(
+            reader.skip(3),
+            Err(VartyIntReadError::VartyIntError(
+                VartyIntError::NotEnoughBytes {
+                    read: 2,
+                    max_more: None
+                }
+            ))
+        ));
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn seek_to() {
+        let nums = (0..1000i64).map(|i| i * i - 500).collect::<Vec<_>>();
+        let buf = write_many_new(&nums);
+        let mut positions = vec![];
+        let mut reader = VarIntReader::with_capacity(32, Cursor::new(&buf));
+        while reader.read_i64().unwrap().is_some() {
+            positions.push(reader.position());
+        }
+
+        // In the buffer, & not
+        for idx in [999, 998, 10, 0, 500, 501, 20, 999] {
+            let start = if idx == 0 { 0 } else { positions[idx - 1] };
+            reader.seek_to(start).unwrap();
+            assert_eq!(reader.read_i64().unwrap(), Some(nums[idx]));
+            assert_eq!(reader.position(), positions[idx]);
+        }
+        reader.seek_to(0).unwrap();
+        reader.skip(998).unwrap();
+        assert_eq!(reader.read_i64().unwrap(), Some(nums[998]));
+        reader.seek_to(buf.len() as u64).unwrap();
+        assert_eq!(reader.read_i64().unwrap(), None);
+
+        // Positions are from where the inner reader started
+        let mut inner = Cursor::new(&buf);
+        inner.set_position(positions[99]);
+        let mut reader = VarIntReader::with_capacity(8, inner);
+        reader.skip(100).unwrap();
+        reader.seek_to(0).unwrap();
+        assert_eq!(reader.read_i64().unwrap(), Some(nums[100]));
+    }
+}
+
+mod varint_writer {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn staging() {
+        let mut writer = VarIntWriter::with_capacity(4, Vec::new());
+        assert_eq!(writer.write_u32(300).unwrap(), 2);
+        assert_eq!(writer.write_i8(-1).unwrap(), 1);
+        // Not written yet
+        assert!(writer.get_ref().is_empty());
+        assert_eq!(writer.buffer(), &[0xAC, 0x02, 0x01]);
+        writer.write_u128(u128::MAX).unwrap();
+        assert_eq!(writer.get_ref().len(), 22);
+        assert!(writer.buffer().is_empty());
+        writer.write_all(&[1, 2]).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.bytes_written(), 24);
+        let out = writer.into_inner().unwrap();
+        assert_eq!(
+            read_single::<(u32, i8, u128)>(&out[..22]),
+            Ok((300, -1, u128::MAX))
+        );
+        assert_eq!(&out[22..], &[1, 2]);
+    }
+
+    #[test]
+    fn delta() {
+        let nums = [100i64, 90, 1_000_000, i64::MIN, i64::MAX];
+        let mut writer = VarIntWriter::new(Vec::new());
+        for num in nums {
+            writer.write_delta(num).unwrap();
+        }
+        writer.reset_delta();
+        writer.write_delta(5).unwrap();
+        let out = writer.into_inner().unwrap();
+        let deltas = read_many::<i64>(&out)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            deltas,
+            vec![100, -10, 999_910, i64::MIN.wrapping_sub(1_000_000), -1, 5]
+        );
+    }
+
+    #[test]
+    fn flush_on_drop() {
+        let mut out = Vec::new();
+        {
+            let mut writer = VarIntWriter::new(&mut out);
+            writer.write_u64(1).unwrap();
+        }
+        assert_eq!(out, vec![1]);
+    }
+}
+
+mod sink {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+    use std::io::Write;
+
+    #[test]
+    fn counting() {
+        let mut sink = CountingSink::new(Vec::new());
+        assert_eq!(write_to(1u8, &mut sink).unwrap(), 1);
+        assert_eq!(write_to((300u16, u64::MAX), &mut sink).unwrap(), 12);
+        sink.write_all(&[1, 2, 3]).unwrap();
+        assert_eq!(sink.count(), 16);
+        assert_eq!(sink.get_ref().len(), 16);
+    }
+
+    #[test]
+    fn hashing() {
+        let nums = [1u64, 1_000, 1 << 50];
+        let mut sink = HashingSink::new(Vec::new(), DefaultHasher::new());
+        for num in nums.iter() {
+            write_to(*num, &mut sink).unwrap();
+        }
+        // Same as hashing the output afterwards
+        let mut expected = DefaultHasher::new();
+        expected.write(&write_many_new(&nums));
+        assert_eq!(sink.finish(), expected.finish());
+        let (out, _) = sink.into_inner();
+        assert_eq!(out, write_many_new(&nums));
+    }
+
+    #[test]
+    fn partial_writes() {
+        // A writer which only accepts 1 byte at a time
+        struct Slow(Vec<u8>);
+        impl Write for Slow {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.extend_from_slice(&buf[..1.min(buf.len())]);
+                Ok(1.min(buf.len()))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut sink = HashingSink::new(CountingSink::new(Slow(vec![])), DefaultHasher::new());
+        write_to(u32::MAX, &mut sink).unwrap();
+        let mut expected = DefaultHasher::new();
+        expected.write(&u32::MAX.as_varint());
+        assert_eq!(sink.finish(), expected.finish());
+        assert_eq!(sink.get_ref().count(), 5);
+    }
+}
+
+mod bigint {
+    use super::*;
+    use crate::bigint::*;
+
+    fn trimmed(bytes: &[u8]) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        bytes
+    }
+
+    #[test]
+    fn same_as_u128() {
+        for num in [
+            0u128,
+            1,
+            127,
+            128,
+            300,
+            u64::MAX as u128,
+            1 << 100,
+            u128::MAX,
+        ] {
+            let mut buf = Vec::new();
+            assert_eq!(
+                write_big_unsigned(&num.to_le_bytes(), &mut buf),
+                num.as_varint().len()
+            );
+            assert_eq!(buf, num.as_varint(), "{}", num);
+            assert_eq!(
+                read_big_unsigned(&buf),
+                Ok((trimmed(&num.to_le_bytes()), &[] as &[u8]))
+            );
+        }
+    }
+
+    #[test]
+    fn same_as_i64() {
+        for num in [
+            0i64,
+            1,
+            -1,
+            63,
+            -64,
+            64,
+            -65,
+            1 << 40,
+            i64::MIN + 1,
+            i64::MAX,
+        ] {
+            let mut buf = Vec::new();
+            write_big_signed(num < 0, &num.unsigned_abs().to_le_bytes(), &mut buf);
+            assert_eq!(buf, num.as_varint(), "{}", num);
+            assert_eq!(
+                read_big_signed(&buf),
+                Ok((
+                    (num < 0, trimmed(&num.unsigned_abs().to_le_bytes())),
+                    &[] as &[u8]
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn huge() {
+        // 2^255 - 1, & -(2^255 - 1)
+        let mut num = vec![0xFF; 32];
+        num[31] = 0x7F;
+        for negative in [false, true] {
+            let mut buf = Vec::new();
+            write_big_signed(negative, &num, &mut buf);
+            buf.push(9);
+            assert_eq!(buf.len(), 38);
+            assert_eq!(
+                read_big_signed(&buf),
+                Ok(((negative, num.clone()), &[9][..]))
+            );
+        }
+        // Negative zero is zero
+        let mut buf = Vec::new();
+        write_big_signed(true, &[0, 0], &mut buf);
+        assert_eq!(buf, vec![0]);
+        assert_eq!(read_big_signed(&buf), Ok(((false, vec![]), &[] as &[u8])));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(read_big_unsigned(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read_big_signed(&[0xFF; 40]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod aggregate {
+    use super::*;
+    use crate::aggregate::*;
+
+    #[test]
+    fn plain() {
+        let nums = [5i32, -300, 7, 0, 1_000];
+        let buf = write_many_new(&nums);
+        assert_eq!(count::<i32>(&buf), Ok(5));
+        assert_eq!(sum::<i32>(&buf), Ok(712));
+        assert_eq!(min::<i32>(&buf), Ok(Some(-300)));
+        assert_eq!(max::<i32>(&buf), Ok(Some(1_000)));
+
+        assert_eq!(count::<u8>(&[]), Ok(0));
+        assert_eq!(sum::<u8>(&[]), Ok(0));
+        assert_eq!(min::<u8>(&[]), Ok(None));
+        assert!(matches!(
+            max::<u8>(&[0x80]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+
+        assert_eq!(
+            sum::<u8>(&write_many_new(&[200u8, 100])),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(
+            sum::<i8>(&write_many_new(&[-100i8, -100])),
+            Err(VartyIntError::OutOfRange)
+        );
+        // The total comes back into range, but the running sum overflowed
+        assert_eq!(
+            sum::<i8>(&write_many_new(&[100i8, 100, -100])),
+            Err(VartyIntError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn delta() {
+        let nums = [10i64, 5, 20, -3];
+        let buf = write_many_delta_new(&nums);
+        assert_eq!(count::<i64>(&buf), Ok(4));
+        assert_eq!(sum_delta::<i64>(&buf), Ok(32));
+        assert_eq!(min_delta::<i64>(&buf), Ok(Some(-3)));
+        assert_eq!(max_delta::<i64>(&buf), Ok(Some(20)));
+        assert_eq!(max_delta::<i64>(&[]), Ok(None));
+
+        let buf = write_many_new(&[u64::MAX, 5]);
+        assert_eq!(min_delta::<u64>(&buf), Err(VartyIntError::DeltaOverflow));
+        assert_eq!(max_delta::<u64>(&buf), Err(VartyIntError::DeltaOverflow));
+
+        // Every value fits, but the sum doesn't
+        let buf = write_many_delta_new(&[100i8, 100]);
+        assert_eq!(sum_delta::<i8>(&buf), Err(VartyIntError::OutOfRange));
+        assert_eq!(max_delta::<i8>(&buf), Ok(Some(100)));
+    }
+}
+
+mod streaming {
+    use super::*;
+
+    #[test]
+    fn packets() {
+        let nums = (0..1_000u64).map(|i| i * i * i).collect::<Vec<_>>();
+        let mut encoder = StreamingEncoder::new();
+        encoder.extend(nums.iter().copied());
+        assert_eq!(encoder.len(), 1_000);
+        let mut out = Vec::new();
+        let mut packet = [0; 7];
+        loop {
+            let (len, status) = encoder.encode(&mut packet);
+            // Every packet holds whole values
+            assert_eq!(
+                read_many::<u64>(&packet[..len]).count(),
+                read_many::<u64>(&packet[..len])
+                    .filter(|r| r.is_ok())
+                    .count()
+            );
+            out.extend_from_slice(&packet[..len]);
+            if status == EncodeStatus::Done {
+                break;
+            }
+        }
+        assert!(encoder.is_empty());
+        assert_eq!(out, write_many_new(&nums));
+        assert_eq!(encoder.encode(&mut packet), (0, EncodeStatus::Done));
+    }
+
+    #[test]
+    fn too_small() {
+        let mut encoder = StreamingEncoder::new();
+        encoder.push(u32::MAX);
+        let mut packet = [0; 4];
+        assert_eq!(encoder.encode(&mut packet), (0, EncodeStatus::OutputFull));
+        assert_eq!(encoder.len(), 1);
+        let mut packet = [0; 5];
+        assert_eq!(encoder.encode(&mut packet), (5, EncodeStatus::Done));
+    }
+}
+
+mod leb128 {
+    use crate::leb128::*;
+    use crate::*;
+
+    #[test]
+    fn minimal() {
+        // Examples from the DWARF spec
+        for (val, bytes) in [
+            (2i64, vec![2]),
+            (-2, vec![0x7E]),
+            (127, vec![0xFF, 0]),
+            (-127, vec![0x81, 0x7F]),
+            (128, vec![0x80, 1]),
+            (-128, vec![0x80, 0x7F]),
+            (129, vec![0x81, 1]),
+            (-129, vec![0xFF, 0x7E]),
+        ] {
+            let mut buf = Vec::new();
+            assert_eq!(write_sleb128(val, &mut buf), bytes.len());
+            assert_eq!(buf, bytes, "{}", val);
+            assert_eq!(read_sleb128_i64(&buf), Ok((val, &[] as &[u8])));
+            assert_eq!(read_sleb128_i32(&buf), Ok((val as i32, &[] as &[u8])));
+        }
+        for val in [i64::MIN, i64::MAX, 0, -1] {
+            let mut buf = Vec::new();
+            write_sleb128(val, &mut buf);
+            assert_eq!(read_sleb128_i64(&buf), Ok((val, &[] as &[u8])));
+        }
+        let mut buf = Vec::new();
+        write_uleb128(624_485, &mut buf);
+        assert_eq!(buf, vec![0xE5, 0x8E, 0x26]);
+        assert_eq!(read_uleb128_u32(&buf), Ok((624_485, &[] as &[u8])));
+    }
+
+    #[test]
+    fn padded() {
+        let mut buf = Vec::new();
+        write_uleb128_padded(u32::MAX as u64, PADDED_32, &mut buf).unwrap();
+        assert_eq!(buf, vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(read_uleb128_u32(&buf), Ok((u32::MAX, &[] as &[u8])));
+        for val in [i32::MIN, i32::MAX, 0, -1, 1, -64, 64] {
+            let mut buf = vec![9];
+            assert_eq!(write_sleb128_padded(val as i64, PADDED_32, &mut buf), Ok(1));
+            assert_eq!(buf.len(), 6);
+            assert_eq!(read_sleb128_i32(&buf[1..]), Ok((val, &[] as &[u8])));
+        }
+        for val in [i64::MIN, i64::MAX, -1] {
+            let mut buf = Vec::new();
+            write_sleb128_padded(val, PADDED_64, &mut buf).unwrap();
+            assert_eq!(read_sleb128_i64(&buf), Ok((val, &[] as &[u8])));
+        }
+        let mut buf = Vec::new();
+        write_uleb128_padded(u64::MAX, PADDED_64, &mut buf).unwrap();
+        assert_eq!(read_uleb128_u64(&buf), Ok((u64::MAX, &[] as &[u8])));
+
+        let mut buf = vec![9];
+        assert_eq!(write_uleb128_padded(1 << 34, PADDED_32, &mut buf), Ok(1));
+        assert_eq!(
+            write_uleb128_padded(1 << 35, PADDED_32, &mut buf),
+            Err(VartyIntError::DoesNotFit)
+        );
+        assert_eq!(buf.len(), 6);
+        assert_eq!(
+            write_sleb128_padded(64, 1, &mut buf),
+            Err(VartyIntError::DoesNotFit)
+        );
+        assert_eq!(
+            write_uleb128_padded(1, 11, &mut buf),
+            Err(VartyIntError::DoesNotFit)
+        );
+        let mut slot = [0x80, 0x00];
+        assert_eq!(
+            patch_uleb128(1 << 14, &mut slot),
+            Err(VartyIntError::DoesNotFit)
+        );
+        assert_eq!(slot, [0x80, 0x00]);
+        patch_sleb128(-8192, &mut slot).unwrap();
+        assert_eq!(read_sleb128_i32(&slot), Ok((-8192, &[] as &[u8])));
+    }
+
+    #[test]
+    fn invalid() {
+        // 6 bytes is too long for 32 bits
+        assert_eq!(
+            read_uleb128_u32(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        // Unused bits set
+        assert_eq!(
+            read_uleb128_u32(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            read_sleb128_i32(&[0xFF, 0xFF, 0xFF, 0xFF, 0x4F]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            read_sleb128_i32(&[0xFF, 0xFF, 0xFF, 0xFF, 0x7F]),
+            Ok((-1, &[] as &[u8]))
+        );
+        assert_eq!(read_uleb128_u64(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read_sleb128_i64(&[0x80]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod metrics {
+    use crate::metrics::*;
+    use crate::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        // (encoded values, encoded bytes, decoded values, decoded bytes, errors) on this thread,
+        // since other tests run at the same time
+        static COUNTS: Cell<(usize, usize, usize, usize, usize)> = const { Cell::new((0, 0, 0, 0, 0)) };
+    }
+
+    struct ThreadCounts;
+    impl Metrics for ThreadCounts {
+        fn encoded(&self, values: usize, bytes: usize) {
+            COUNTS.with(|c| {
+                let (ev, eb, dv, db, e) = c.get();
+                c.set((ev + values, eb + bytes, dv, db, e));
+            });
+        }
+        fn decoded(&self, values: usize, bytes: usize) {
+            COUNTS.with(|c| {
+                let (ev, eb, dv, db, e) = c.get();
+                c.set((ev, eb, dv + values, db + bytes, e));
+            });
+        }
+        fn error(&self, _error: &VartyIntError) {
+            COUNTS.with(|c| {
+                let (ev, eb, dv, db, e) = c.get();
+                c.set((ev, eb, dv, db, e + 1));
+            });
+        }
+    }
+
+    #[test]
+    fn counts() {
+        static METRICS: ThreadCounts = ThreadCounts;
+        // Other tests don't set it
+        assert!(set_metrics(&METRICS).is_ok());
+        assert!(set_metrics(&METRICS).is_err());
+
+        let buf = write_many_new(&[1u32, 300]);
+        let delta = write_many_delta_new(&[1u32, 300]);
+        assert_eq!(COUNTS.with(|c| c.get()), (4, 6, 0, 0, 0));
+
+        assert_eq!(aggregate::sum::<u32>(&buf), Ok(301));
+        read_many_delta_new::<u32>(&delta).unwrap();
+        assert_eq!(COUNTS.with(|c| c.get()), (4, 6, 4, 6, 0));
+
+        assert!(for_each_varint::<u32, _>(&[1, 0x80], |_| {}).is_err());
+        assert!(read_many_delta_new::<u32>(&[0x80]).is_err());
+        assert_eq!(COUNTS.with(|c| c.get()), (4, 6, 5, 7, 2));
+    }
+}
+
+#[cfg(feature = "ffi")]
+mod ffi {
+    use crate::ffi::*;
+
+    #[test]
+    fn single() {
+        let mut buf = [0u8; 10];
+        unsafe {
+            assert_eq!(vartyint_encode_i64(-300, buf.as_mut_ptr(), buf.len()), 2);
+            let mut val = 0;
+            assert_eq!(vartyint_decode_i64(buf.as_ptr(), 2, &mut val), 2);
+            assert_eq!(val, -300);
+            assert_eq!(
+                vartyint_encode_u64(u64::MAX, buf.as_mut_ptr(), 9),
+                VARTYINT_ERR_OUTPUT_TOO_SMALL
+            );
+            let mut val = 0u32;
+            assert_eq!(
+                vartyint_decode_u32(buf.as_ptr(), 1, &mut val),
+                VARTYINT_ERR_NOT_ENOUGH_BYTES
+            );
+            assert_eq!(
+                vartyint_decode_u32(std::ptr::null(), 0, &mut val),
+                VARTYINT_ERR_EMPTY_BUFFER
+            );
+            assert_eq!(
+                vartyint_decode_u32(std::ptr::null(), 1, &mut val),
+                VARTYINT_ERR_NULL
+            );
+        }
+    }
+
+    #[test]
+    fn arrays() {
+        let vals = [1u32, 300, 70_000];
+        let mut buf = [0u8; 6];
+        let mut out = [0u32; 3];
+        unsafe {
+            assert_eq!(
+                vartyint_encode_u32_array(vals.as_ptr(), 3, buf.as_mut_ptr(), buf.len()),
+                6
+            );
+            assert_eq!(
+                vartyint_decode_u32_array(buf.as_ptr(), 6, out.as_mut_ptr(), out.len()),
+                3
+            );
+            assert_eq!(out, vals);
+            assert_eq!(
+                vartyint_encode_u32_array(vals.as_ptr(), 3, buf.as_mut_ptr(), 5),
+                VARTYINT_ERR_OUTPUT_TOO_SMALL
+            );
+            assert_eq!(
+                vartyint_decode_u32_array(buf.as_ptr(), 6, out.as_mut_ptr(), 2),
+                VARTYINT_ERR_OUTPUT_TOO_SMALL
+            );
+            assert_eq!(
+                vartyint_decode_u32_array(std::ptr::null(), 0, std::ptr::null_mut(), 0),
+                0
+            );
+        }
+    }
+}
+
+mod dynamic {
+    use crate::dynamic::*;
+    use crate::*;
+
+    #[test]
+    fn types() {
+        for ty in VarIntType::ALL {
+            assert_eq!(VarIntType::try_from(*ty as u8), Ok(*ty));
+            assert_eq!(VarIntType::from_name(ty.name()), Some(*ty));
+        }
+        assert_eq!(
+            VarIntType::try_from(10),
+            Err(VartyIntError::UnknownDiscriminant)
+        );
+        assert_eq!(VarIntType::from_name("usize"), None);
+        assert_eq!(Value::from(-3i16).var_int_type(), VarIntType::I16);
+        assert_eq!(Value::from(u128::MAX).to_string(), u128::MAX.to_string());
+    }
+
+    #[test]
+    fn codecs() {
+        let codec: Box<dyn DynCodec> = Box::new(VarIntType::I32);
+        let mut buf = Vec::new();
+        assert_eq!(codec.write_value(&Value::I32(-1), &mut buf), Ok(1));
+        assert_eq!(
+            codec.write_value(&Value::U32(1), &mut buf),
+            Err(VartyIntError::TypeMismatch)
+        );
+        assert_eq!(buf, vec![1]);
+        assert_eq!(codec.read_value(&buf), Ok((Value::I32(-1), &[] as &[u8])));
+
+        let vals = [Value::U64(u64::MAX), Value::U64(0), Value::U64(300)];
+        let codec: Box<dyn DynCodec> = Box::new(DeltaCodec(VarIntType::U64));
+        let mut buf = Vec::new();
+        codec.write_values(&vals, &mut buf).unwrap();
+        assert_eq!(codec.read_values(&buf), Ok(vals.to_vec()));
+        assert_eq!(
+            codec.write_values(&[Value::U64(1), Value::I64(1)], &mut buf),
+            Err(VartyIntError::TypeMismatch)
+        );
+        // Same as the generic functions
+        let buf = write_many_delta_new(&[5i8, -3, 100]);
+        assert_eq!(
+            DeltaCodec(VarIntType::I8).read_values(&buf),
+            Ok(vec![Value::I8(5), Value::I8(-3), Value::I8(100)])
+        );
+        assert!(matches!(
+            VarIntType::U8.read_values(&[1, 0x80]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+    #[test]
+    fn tagged() {
+        let vals = [
+            Value::U8(255),
+            Value::I128(i128::MIN),
+            Value::U16(0),
+            Value::I8(-1),
+        ];
+        let mut buf = Vec::new();
+        let len = write_many_tagged(&vals, &mut buf);
+        assert_eq!(len, buf.len());
+        assert_eq!(buf[0..3], [0, 0xFF, 0x01]);
+        assert_eq!(
+            read_tagged(&buf).collect::<Result<Vec<_>, _>>(),
+            Ok(vals.to_vec())
+        );
+
+        assert_eq!(read_one_tagged(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read_one_tagged(&[2]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            read_tagged(&[0, 1, 99, 1, 0, 1]).collect::<Vec<_>>(),
+            vec![Ok(Value::U8(1)), Err(VartyIntError::UnknownDiscriminant)]
+        );
+    }
+}
+
+mod schema {
+    use crate::dynamic::{Value, VarIntType};
+    use crate::schema::*;
+    use crate::*;
+
+    #[test]
+    fn encodings() {
+        let schema = Schema::new()
+            .column(Column::new(VarIntType::I8))
+            .column(Column::new(VarIntType::I8).raw())
+            .column(Column::new(VarIntType::U32).delta())
+            .column(Column::new(VarIntType::I16).delta().raw());
+        let rows = vec![
+            vec![
+                Value::I8(-1),
+                Value::I8(-1),
+                Value::U32(100),
+                Value::I16(10),
+            ],
+            vec![Value::I8(1), Value::I8(1), Value::U32(101), Value::I16(9)],
+        ];
+        let mut buf = Vec::new();
+        assert_eq!(schema.encode_rows(&rows, &mut buf), Ok(buf.len()));
+        assert_eq!(
+            buf,
+            vec![
+                2, // rows
+                2, 0x01, 0x02, // zigzag
+                3, 0xFF, 0x01, 0x01, // raw
+                2, 100, 1, // delta
+                4, 10, 0xFF, 0xFF, 0x03, // delta, raw
+            ]
+        );
+        assert_eq!(schema.decode_rows(&buf), Ok(rows));
+    }
+
+    #[test]
+    fn empty() {
+        let schema = Schema::new().column(Column::new(VarIntType::U8));
+        let mut buf = Vec::new();
+        schema.encode_rows(&[], &mut buf).unwrap();
+        assert_eq!(buf, vec![0, 0]);
+        assert_eq!(schema.decode_rows(&buf), Ok(vec![]));
+
+        let schema = Schema::new();
+        let mut buf = Vec::new();
+        schema.encode_rows(&[], &mut buf).unwrap();
+        assert_eq!(buf, vec![0]);
+        assert_eq!(schema.decode_rows(&buf), Ok(vec![]));
+        assert_eq!(
+            schema.encode_rows(&[vec![]], &mut buf),
+            Err(VartyIntError::OutOfRange)
+        );
+        assert_eq!(
+            schema.decode_rows(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]),
+            Err(VartyIntError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let schema = Schema::new()
+            .column(Column::new(VarIntType::U8))
+            .column(Column::new(VarIntType::U8));
+        let mut buf = vec![9];
+        assert_eq!(
+            schema.encode_rows(&[vec![Value::U8(1)]], &mut buf),
+            Err(VartyIntError::TypeMismatch)
+        );
+        assert_eq!(
+            schema.encode_rows(&[vec![Value::U8(1), Value::I8(1)]], &mut buf),
+            Err(VartyIntError::TypeMismatch)
+        );
+        assert_eq!(buf, vec![9]);
+
+        assert_eq!(schema.decode_rows(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            schema.decode_rows(&[1, 1, 5]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            schema.decode_rows(&[1, 1, 5, 2, 5]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            schema.decode_rows(&[1, 1, 5, 2, 5, 6]),
+            Err(VartyIntError::TrailingBytes)
+        );
+        assert_eq!(
+            schema.decode_rows(&[1, 1, 5, 1, 5, 0]),
+            Err(VartyIntError::TrailingBytes)
+        );
+        assert!(matches!(
+            schema.decode_rows(&[2, 1, 5, 1, 5]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+}
+
+mod columns {
+    use crate::columns::*;
+    use crate::*;
+
+    #[test]
+    fn pairs() {
+        let pairs = vec![(10u64, -1i32), (20, 1), (30, -1)];
+        let mut buf = Vec::new();
+        assert_eq!(
+            write_columns2(pairs.iter().copied(), [true, false], &mut buf),
+            3
+        );
+        assert_eq!(buf, vec![3, 3, 10, 10, 10, 3, 1, 2, 1]);
+        assert_eq!(read_columns2::<u64, i32>(&buf, [true, false]), Ok(pairs));
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            write_columns2(std::iter::empty::<(u8, u8)>(), [false, false], &mut buf),
+            0
+        );
+        assert_eq!(buf, vec![0, 0, 0]);
+        assert_eq!(read_columns2::<u8, u8>(&buf, [false, false]), Ok(vec![]));
+    }
+
+    #[test]
+    fn triples() {
+        let triples = (0..1_000i64)
+            .map(|i| (i * 7, (i % 3) as u8, -i as i16))
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        write_columns3(triples.iter().copied(), [true, false, true], &mut buf);
+        assert_eq!(
+            read_columns3::<i64, u8, i16>(&buf, [true, false, true]),
+            Ok(triples)
+        );
+    }
+
+    #[test]
+    fn interleaved() {
+        let points = [[0u64, 1_000], [1, 1_000], [300, 1_001]];
+        let mut buf = Vec::new();
+        assert_eq!(write_interleaved_delta(&points, &mut buf), 8);
+        assert_eq!(buf, vec![0, 0xE8, 0x07, 1, 0, 0xAB, 0x02, 1]);
+        assert_eq!(read_interleaved_delta::<u64, 2>(&buf), Ok(points.to_vec()));
+        assert_eq!(
+            read_interleaved_delta::<u64, 2>(&buf[..buf.len() - 1]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 0,
+                max_more: Some(10)
+            })
+        );
+        assert_eq!(read_interleaved_delta::<u64, 2>(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn planar() {
+        let points = [[5i16, -5], [6, -6], [4, -4]];
+        let mut buf = Vec::new();
+        assert_eq!(write_planar_delta(&points, &mut buf), 3);
+        assert_eq!(buf, vec![3, 3, 10, 2, 3, 3, 9, 1, 4]);
+        assert_eq!(read_planar_delta::<i16, 2>(&buf), Ok(points.to_vec()));
+        assert_eq!(
+            read_planar_delta::<i16, 2>(&buf[..buf.len() - 1]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 2,
+                max_more: Some(1)
+            })
+        );
+        // A huge number of rows, but no columns
+        assert!(matches!(
+            read_planar_delta::<u64, 3>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        buf.push(0);
+        assert_eq!(
+            read_planar_delta::<i16, 2>(&buf),
+            Err(VartyIntError::TrailingBytes)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            read_columns2::<u8, u8>(&[], [false, false]),
+            Err(VartyIntError::EmptyBuffer)
+        );
+        assert_eq!(
+            read_columns2::<u8, u8>(&[1, 1, 5], [false, false]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 0,
+                max_more: Some(10)
+            })
+        );
+        assert_eq!(
+            read_columns2::<u8, u8>(&[1, 1, 5, 2, 5], [false, false]),
+            Err(VartyIntError::NotEnoughBytes {
+                read: 1,
+                max_more: Some(1)
+            })
+        );
+        assert_eq!(
+            read_columns2::<u8, u8>(&[1, 1, 5, 2, 5, 6], [false, false]),
+            Err(VartyIntError::TrailingBytes)
+        );
+        assert_eq!(
+            read_columns2::<u8, u8>(&[1, 1, 5, 1, 5, 0], [false, false]),
+            Err(VartyIntError::TrailingBytes)
+        );
+        assert_eq!(
+            read_columns2::<u8, u8>(&[3, 3, 100, 100, 100, 3, 1, 1, 1], [true, false]),
+            Err(VartyIntError::DeltaOverflow)
+        );
+        assert_eq!(
+            read_interleaved_delta::<u8, 1>(&[100, 100, 100]),
+            Err(VartyIntError::DeltaOverflow)
+        );
+    }
+}
+
+mod text {
+    use crate::text::*;
+    use crate::*;
+
+    #[test]
+    fn roundtrip() {
+        let nums = [0u128, 1, 31, 32, u64::MAX as u128, u128::MAX];
+        let s = encode(&nums);
+        assert!(s
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        assert_eq!(decode::<u128>(&s), Ok(nums.to_vec()));
+
+        let nums = [i8::MIN, -1, 0, 1, i8::MAX];
+        assert_eq!(decode::<i8>(&encode(&nums)), Ok(nums.to_vec()));
+        let nums = [i128::MIN, i128::MAX];
+        assert_eq!(decode::<i128>(&encode(&nums)), Ok(nums.to_vec()));
+    }
+
+    #[test]
+    fn single() {
+        let mut s = String::new();
+        assert_eq!(write(31u32, &mut s), 1);
+        assert_eq!(write(32u32, &mut s), 2);
+        assert_eq!(s, "fgB");
+        assert_eq!(read::<u32>(&s), Ok((31, "gB")));
+        assert_eq!(read::<u32>("gB"), Ok((32, "")));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(read::<u8>(""), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read::<u8>("g"),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(read::<u8>("A="), Ok((0, "=")));
+        assert_eq!(read::<u8>("="), Err(VartyIntError::InvalidCharacter));
+        // 256
+        assert_eq!(read::<u8>("gI"), Err(VartyIntError::TooManyBytesForType));
+        assert_eq!(read::<u16>("gI"), Ok((256, "")));
+        assert_eq!(
+            decode::<u128>(&"_".repeat(26)),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            decode::<u128>(&format!("{}I", "_".repeat(25))),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+    }
+}
+
+#[test]
+fn read_with_len_and_at() {
+    let buf = write_many_new(&[-1i64, 1_000, i64::MIN]);
+    assert_eq!(read_with_len::<i64>(&buf), Ok((-1, 1)));
+    assert_eq!(read_at::<i64>(&buf, 1), Ok((1_000, 2)));
+    assert_eq!(read_at::<i64>(&buf, 3), Ok((i64::MIN, 10)));
+    assert_eq!(read_at::<i64>(&buf, 13), Err(VartyIntError::EmptyBuffer));
+    assert_eq!(read_at::<i64>(&buf, 100), Err(VartyIntError::EmptyBuffer));
+    assert!(matches!(
+        read_at::<i64>(&buf[..12], 3),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert_eq!(read_with_len::<i64>(&[]), Err(VartyIntError::EmptyBuffer));
+}
+
+#[test]
+fn transform_values() {
+    let input = write_many_delta_new(&[10u32, 20, 35]);
+    let mut output = vec![0xFF];
+    assert_eq!(transform(&input, |d: u32| d * 2, &mut output), Ok(3));
+    assert_eq!(output, vec![0xFF, 20, 20, 30]);
+    // Bad input
+    assert!(matches!(
+        transform(&[1, 0x80], |d: u32| d, &mut output),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert_eq!(output, vec![0xFF, 20, 20, 30]);
+    assert_eq!(
+        transform(&[0xFF, 0x7F], |d: u8| d, &mut output),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+
+    let input = write_many_new(&(0..10_000u64).collect::<Vec<_>>());
+    let mut output = Vec::new();
+    let count = transform_io(&input[..], |n: u64| n as i32 - 5_000, &mut output).unwrap();
+    assert_eq!(count, 10_000);
+    assert_eq!(
+        read_many::<i32>(&output)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        (-5_000..5_000).collect::<Vec<_>>()
+    );
+    assert!(transform_io(&[0x80][..], |n: u64| n, Vec::new()).is_err());
+}
+
+#[test]
+fn try_read_partial() {
+    let buf = write_many_new(&[u64::MAX]);
+    for len in 0..buf.len() {
+        assert_eq!(try_read::<u64>(&buf[..len]), Ok(None));
+    }
+    assert_eq!(try_read::<u64>(&buf), Ok(Some((u64::MAX, 10))));
+    // Malformed data is still an error, even though more bytes could follow
+    assert_eq!(
+        try_read::<u8>(&[0xFF, 0xFF]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+    assert_eq!(
+        try_read::<u8>(&[0xFF, 0xFF, 0x01]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+}
+
+#[test]
+fn read_array_lengths() {
+    let buf = write_many_new(&[1i16, -2, 3]);
+    assert_eq!(read_array::<i16, 3>(&buf), Ok(([1, -2, 3], &[] as &[u8])));
+    assert_eq!(read_array::<i16, 0>(&buf), Ok(([], &buf[..])));
+    assert_eq!(
+        read_array::<i16, 4>(&buf),
+        Err(VartyIntError::NotEnoughBytes {
+            read: 0,
+            max_more: Some(3)
+        })
+    );
+    assert_eq!(read_array::<i16, 1>(&[]), Err(VartyIntError::EmptyBuffer));
+    assert_eq!(
+        read_array::<u8, 2>(&[0xFF, 0x7F]),
+        Err(VartyIntError::TooManyBytesForType)
+    );
+}
+
+mod cursor {
+    use crate::cursor::*;
+    use crate::*;
+
+    #[test]
+    fn snapshots() {
+        let buf = write_many_new(&[1u64, 1_000, 2]);
+        let mut cursor = ReadCursor::new(&buf);
+        assert_eq!(cursor.read::<u64>(), Ok(1));
+        let after_first = cursor.snapshot();
+        assert_eq!(cursor.read::<u64>(), Ok(1_000));
+        assert_eq!(cursor.remaining(), &[2]);
+        cursor.restore(after_first);
+        assert_eq!(cursor.position(), 1);
+        assert_eq!(cursor.read::<u64>(), Ok(1_000));
+        assert_eq!(cursor.read::<u64>(), Ok(2));
+        assert_eq!(cursor.read::<u64>(), Err(VartyIntError::EmptyBuffer));
+        assert!(cursor.is_empty());
+
+        let far = ReadCursor::new(&[0; 10]).attempt(|c| {
+            for _ in 0..10 {
+                c.read::<u8>()?;
+            }
+            Ok::<_, VartyIntError>(c.snapshot())
+        });
+        cursor.restore(far.unwrap());
+        assert_eq!(cursor.position(), 4);
+    }
+
+    #[test]
+    fn attempt() {
+        let mut cursor = ReadCursor::new(&[0xAC, 0x02, 0x01]);
+        assert_eq!(
+            cursor.attempt(|c| c.read::<u8>()),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(cursor.position(), 0);
+        assert_eq!(
+            cursor.attempt(|c| Ok::<_, VartyIntError>((c.read::<u16>()?, c.read::<u16>()?))),
+            Ok((300, 1))
+        );
+        assert!(cursor.is_empty());
+    }
+
+    #[test]
+    fn write_cursor() {
+        let mut frame = [0; 12];
+        let mut cursor = WriteCursor::new(&mut frame);
+        assert_eq!(cursor.write(i64::MIN), Ok(10));
+        let snapshot = cursor.snapshot();
+        assert_eq!(cursor.write(0u8), Ok(1));
+        assert_eq!(cursor.write(200u8), Err(VartyIntError::BufferFull));
+        assert_eq!(cursor.write_bytes(&[1, 2]), Err(VartyIntError::BufferFull));
+        assert_eq!(cursor.remaining_len(), 1);
+        cursor.restore(snapshot);
+        assert_eq!(cursor.write(200u8), Ok(2));
+        assert!(cursor.is_full());
+        assert_eq!(cursor.write_bytes(&[]), Ok(()));
+        assert_eq!(cursor.write(0u8), Err(VartyIntError::BufferFull));
+        let written = cursor.into_written();
+        assert_eq!(read_single::<(i64, u8)>(written), Ok((i64::MIN, 200)));
+
+        // Same bytes as `write_varint`
+        for num in [0i128, -1, 1 << 100, i128::MIN, i128::MAX] {
+            let mut buf = [0; 19];
+            let len = num.write_varint_slice(&mut buf).unwrap();
+            assert_eq!(buf[..len], num.as_varint());
+            assert_eq!(num.write_varint_slice(&mut buf[..len - 1]), None);
+        }
+        let mut buf = [0; 3];
+        let mut cursor = WriteCursor::new(&mut buf);
+        assert_eq!(cursor.write((1u8, 300u16)), Ok(3));
+        assert_eq!(cursor.written(), &[1, 0xAC, 0x02]);
+    }
+}
+
+mod config {
+    use crate::config::*;
+    use crate::*;
+
+    #[test]
+    fn default_is_crate() {
+        let config = EncodingConfig::new();
+        let nums = [0i64, -1, 300, i64::MIN, i64::MAX];
+        let mut buf = Vec::new();
+        config.encode_many(&nums, &mut buf).unwrap();
+        assert_eq!(buf, write_many_new(&nums));
+        assert_eq!(config.decode_many::<i64>(&buf), Ok(nums.to_vec()));
+        // Padded values are allowed
+        assert_eq!(config.decode::<u32>(&[0x81, 0x00]), Ok((1, &[] as &[u8])));
+    }
+
+    #[test]
+    fn protobuf() {
+        let config = EncodingConfig::protobuf();
+        let mut buf = Vec::new();
+        assert_eq!(config.encode(-1i64, &mut buf), Ok(10));
+        assert_eq!(buf, Raw(-1i64).as_varint());
+        assert_eq!(config.decode::<i64>(&buf), Ok((-1, &[] as &[u8])));
+        assert_eq!(
+            config.encode(u128::MAX, &mut buf),
+            Err(VartyIntError::DoesNotFit)
+        );
+        assert_eq!(buf.len(), 10);
+        assert_eq!(
+            config.decode::<u128>(&[0xFF; 11]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            config.decode::<u128>(&[0xFF; 10]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert!(matches!(
+            config.decode::<u128>(&[0xFF; 9]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+    }
+
+    #[test]
+    fn canonical() {
+        let config = EncodingConfig::new().canonical(true);
+        assert_eq!(config.decode::<u32>(&[0x00]), Ok((0, &[] as &[u8])));
+        assert_eq!(
+            config.decode::<u32>(&[0x80, 0x00]),
+            Err(VartyIntError::NonCanonical)
+        );
+        assert_eq!(config.decode::<u32>(&[0x80, 0x01]), Ok((128, &[] as &[u8])));
+    }
+
+    #[test]
+    fn most_significant_first() {
+        let config = EncodingConfig::new()
+            .zigzag(false)
+            .group_order(GroupOrder::MostSignificantFirst);
+        // Examples from the MIDI spec
+        for (val, bytes) in [
+            (0u32, &[0x00][..]),
+            (0x7F, &[0x7F]),
+            (0x80, &[0x81, 0x00]),
+            (0x2000, &[0xC0, 0x00]),
+            (0x3FFF, &[0xFF, 0x7F]),
+            (0x0FFF_FFFF, &[0xFF, 0xFF, 0xFF, 0x7F]),
+        ] {
+            let mut buf = Vec::new();
+            assert_eq!(config.encode(val, &mut buf), Ok(bytes.len()));
+            assert_eq!(buf, bytes);
+            assert_eq!(config.decode::<u32>(bytes), Ok((val, &[] as &[u8])));
+        }
+        let config = config.canonical(true);
+        assert_eq!(
+            config.decode::<u32>(&[0x80, 0x01]),
+            Err(VartyIntError::NonCanonical)
+        );
+        assert_eq!(
+            config.decode::<u8>(&[0x82, 0x00, 0x05]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            config.decode::<u8>(&[0x81, 0x7F, 0x05]),
+            Ok((255, &[5][..]))
+        );
+    }
+}
+
+mod group {
+    use crate::group::*;
+    use crate::*;
+
+    fn nums() -> Vec<u32> {
+        (0..2_000u32)
+            .map(|i| i.wrapping_mul(2_654_435_761) >> (i % 32))
+            .chain([0, u32::MAX, 255, 256, 65_535, 65_536])
+            .collect()
+    }
+
+    #[test]
+    fn g8iu() {
+        let mut buf = Vec::new();
+        assert_eq!(write_g8iu(&[1, 2, 300, 70_000], &mut buf), 9);
+        assert_eq!(
+            buf,
+            vec![0b1011_0100, 1, 2, 0x2C, 0x01, 0x70, 0x11, 0x01, 0]
+        );
+        assert_eq!(read_g8iu(&buf), Ok(vec![1, 2, 300, 70_000]));
+
+        // Doesn't fit, so goes into the next block
+        let mut buf = Vec::new();
+        write_g8iu(&[1, 2, 3, 4, 5, u32::MAX], &mut buf);
+        assert_eq!(buf.len(), 18);
+        assert_eq!(buf[0], 0b1110_0000);
+
+        let nums = nums();
+        let mut buf = Vec::new();
+        write_g8iu(&nums, &mut buf);
+        assert_eq!(read_g8iu_scalar(&buf), Ok(nums.clone()));
+        assert_eq!(read_g8iu(&buf), Ok(nums));
+
+        let mut buf = Vec::new();
+        assert_eq!(write_g8iu(&[], &mut buf), 0);
+        assert_eq!(read_g8iu(&buf), Ok(vec![]));
+    }
+
+    #[test]
+    fn g8cu() {
+        let mut buf = Vec::new();
+        assert_eq!(write_g8cu(&[1, 2, 3, 4, 5, u32::MAX], &mut buf), 18);
+        assert_eq!(buf[0..9], [0b1110_0000, 1, 2, 3, 4, 5, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(buf[9..], [0b1111_1110, 0xFF, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(read_g8cu(&buf), Ok(vec![1, 2, 3, 4, 5, u32::MAX]));
+
+        let nums = nums();
+        let mut buf = Vec::new();
+        write_g8cu(&nums, &mut buf);
+        assert_eq!(read_g8cu(&buf), Ok(nums));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            read_g8iu(&[0; 8]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            read_g8iu_scalar(&[0b1110_1111, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert!(matches!(
+            read_g8cu(&[0; 10]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(
+            read_g8iu(&[0b1110_1111, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        assert_eq!(
+            read_g8cu(&[0b1110_1111, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Err(VartyIntError::TooManyBytesForType)
+        );
+        // Padding at the end is ignored
+        assert_eq!(read_g8iu(&[0xFF, 1, 2, 3, 4, 5, 6, 7, 8]), Ok(vec![]));
+        assert_eq!(read_g8cu(&[0xFE, 1, 2, 3, 4, 5, 6, 7, 8]), Ok(vec![1]));
+    }
+}
+
+#[test]
+fn wrapping_saturating() {
+    use std::num::{Saturating, Wrapping};
+    let mut buf = Vec::new();
+    Wrapping(-1i32).write_varint(&mut buf);
+    Saturating(300u16).write_varint(&mut buf);
+    Wrapping(Raw(-1i8)).write_varint(&mut buf);
+    assert_eq!(buf, vec![0x01, 0xAC, 0x02, 0xFF, 0x01]);
+
+    let (a, rest) = Wrapping::<i32>::read_varint(&buf).unwrap();
+    let (b, rest) = Saturating::<u16>::read_varint(rest).unwrap();
+    let (c, rest) = Wrapping::<Raw<i8>>::read_varint(rest).unwrap();
+    assert_eq!(
+        (a, b, c),
+        (Wrapping(-1), Saturating(300), Wrapping(Raw(-1)))
+    );
+    assert!(rest.is_empty());
+
+    let nums = [Wrapping(u8::MAX), Wrapping(0), Wrapping(5)];
+    assert_eq!(
+        read_many_delta_new(&write_many_delta_new(&nums)),
+        Ok(nums.to_vec())
+    );
+}
+
+#[test]
+fn take_n_values() {
+    let buf = write_many_new(&[1u64, 1_000, 7]);
+    assert_eq!(take_n::<u64>(&buf, 2), Ok((vec![1, 1_000], &[7][..])));
+    assert_eq!(take_n::<u64>(&buf, 0), Ok((vec![], &buf[..])));
+    assert_eq!(take_n::<u64>(&buf, 3), Ok((vec![1, 1_000, 7], &[][..])));
+    assert!(matches!(
+        take_n::<u64>(&buf, 4),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert_eq!(
+        take_n::<u64>(&[], usize::MAX),
+        Err(VartyIntError::EmptyBuffer)
+    );
+}
+
+mod bits {
+    use crate::bits::*;
+    use crate::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut writer = BitWriter::new();
+        assert!(writer.is_empty());
+        writer.write_bits(0b1, 1);
+        writer.write_bits(u64::MAX, 64);
+        writer.write_bits(0, 0);
+        writer.write_bits(0x1234, 13);
+        assert_eq!(writer.len_bits(), 78);
+        let buf = writer.into_bytes();
+        assert_eq!(buf.len(), 10);
+
+        let mut reader = BitReader::new(&buf);
+        assert_eq!(reader.read_bits(1), Ok(1));
+        assert_eq!(reader.read_bits(64), Ok(u64::MAX));
+        assert_eq!(reader.read_bits(0), Ok(0));
+        assert_eq!(reader.read_bits(13), Ok(0x1234));
+        assert_eq!(reader.position_bits(), 78);
+        assert_eq!(reader.read_bits(2), Ok(0));
+        assert!(matches!(
+            reader.read_bits(1),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(reader.remaining_bytes().is_empty());
+    }
+}
+
+mod gorilla {
+    use crate::gorilla::*;
+    use crate::*;
+
+    #[test]
+    fn buckets() {
+        let vals = [
+            1_000,
+            1_060,
+            1_120, // 0
+            1_181, // 1
+            1_181, // -61
+            1_500, // 319
+            5_000, // 3181
+            5_000, // -3500
+            i64::MIN,
+            i64::MAX,
+            0,
+        ];
+        let mut buf = Vec::new();
+        let len = write_gorilla(&vals, &mut buf);
+        assert_eq!(len, buf.len());
+        buf.push(99);
+        assert_eq!(read_gorilla(&buf), Ok((vals.to_vec(), &[99][..])));
+    }
+
+    #[test]
+    fn short() {
+        for vals in [&[][..], &[-5], &[-5, 5]] {
+            let mut buf = Vec::new();
+            write_gorilla(vals, &mut buf);
+            assert_eq!(read_gorilla(&buf), Ok((vals.to_vec(), &[][..])));
+        }
+        let mut buf = Vec::new();
+        write_gorilla(&[1, 2, 3], &mut buf);
+        assert_eq!(buf, vec![3, 2, 2, 0]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(read_gorilla(&[]), Err(VartyIntError::EmptyBuffer));
+        assert!(matches!(
+            read_gorilla(&[1]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert!(matches!(
+            read_gorilla(&[2, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        // 9 delta of deltas, but only 8 bits
+        assert!(matches!(
+            read_gorilla(&[11, 0, 0, 0]),
+            Err(VartyIntError::NotEnoughBytes { .. })
+        ));
+        assert_eq!(read_gorilla(&[11, 0, 0, 0, 0]).unwrap().0, vec![0; 11]);
+    }
+}
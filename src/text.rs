@@ -0,0 +1,94 @@
+//! Varints as printable ASCII, safe to put in URLs, JSON strings & log lines.
+//!
+//! Like [`polyline`](crate::polyline), but for any integer type. The value is written in 5 bit
+//! groups, least significant first, each as one character from the URL safe base64 alphabet
+//! (`A`–`Z`, `a`–`z`, `0`–`9`, `-`, `_`). The 6th bit is set on every group except the last.
+//! Signed integers are zigzag encoded first.
+//!
+//! ```rust
+//! use vartyint::text;
+//! let s = text::encode(&[0i64, -1, 300, 1_000_000]);
+//! assert_eq!(s, "AB4Sgkh9B");
+//! assert_eq!(text::decode::<i64>(&s).unwrap(), vec![0, -1, 300, 1_000_000]);
+//! ```
+use crate::*;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn char_value(c: u8) -> Result<u8, VartyIntError> {
+    match c {
+        b'A'..=b'Z' => Ok(c - b'A'),
+        b'a'..=b'z' => Ok(c - b'a' + 26),
+        b'0'..=b'9' => Ok(c - b'0' + 52),
+        b'-' => Ok(62),
+        b'_' => Ok(63),
+        _ => Err(VartyIntError::InvalidCharacter),
+    }
+}
+
+/// Write this value to the end of `output`, returning the number of characters written.
+pub fn write<T: VarInt>(val: T, output: &mut String) -> usize {
+    // The (zigzag encoded) value, from the 7 bit groups of the binary varint
+    let mut num: u128 = 0;
+    for (i, byte) in val.as_varint().into_iter().enumerate() {
+        num |= ((byte & 0x7F) as u128) << (7 * i);
+    }
+    let mut len = 1;
+    while num >= 0x20 {
+        output.push(ALPHABET[(0x20 | (num & 0x1F)) as usize] as char);
+        num >>= 5;
+        len += 1;
+    }
+    output.push(ALPHABET[num as usize] as char);
+    len
+}
+
+/// Read one value from the start of `input`, returning it & the rest of the string.
+pub fn read<T: VarInt>(input: &str) -> Result<(T, &str), VartyIntError> {
+    if input.is_empty() {
+        return Err(VartyIntError::EmptyBuffer);
+    }
+    let mut num: u128 = 0;
+    let mut shift = 0;
+    for (i, c) in input.bytes().enumerate() {
+        let group = char_value(c)?;
+        let bits = (group & 0x1F) as u128;
+        if shift >= 128 || (bits << shift) >> shift != bits {
+            return Err(VartyIntError::TooManyBytesForType);
+        }
+        num |= bits << shift;
+        shift += 5;
+        if group & 0x20 == 0 {
+            // Convert to a binary varint & let `T` decode it, to check it fits
+            let mut bytes = Vec::with_capacity(19);
+            num.write_varint(&mut bytes);
+            let (val, _) = T::read_varint(&bytes)?;
+            return Ok((val, &input[i + 1..]));
+        }
+    }
+    // Each character has 5 bits of the value
+    Err(VartyIntError::not_enough(
+        input.len(),
+        (T::MAX_LEN * 7).div_ceil(5),
+    ))
+}
+
+/// Write all these values into a new string
+pub fn encode<T: VarInt>(nums: &[T]) -> String {
+    let mut output = String::new();
+    for num in nums {
+        write(*num, &mut output);
+    }
+    output
+}
+
+/// Read all the values in this string
+pub fn decode<T: VarInt>(mut input: &str) -> Result<Vec<T>, VartyIntError> {
+    let mut nums = Vec::new();
+    while !input.is_empty() {
+        let (num, rest) = read(input)?;
+        nums.push(num);
+        input = rest;
+    }
+    Ok(nums)
+}
@@ -0,0 +1,112 @@
+//! Write & read `SystemTime`s as integer offsets from the UNIX epoch, at a chosen resolution.
+//!
+//! Timestamps are written as signed varints, so times before 1970 work. Streams of timestamps
+//! which are (mostly) increasing are much smaller with the delta functions.
+//!
+//! ```rust
+//! use std::time::{Duration, UNIX_EPOCH};
+//! use vartyint::timestamp::{self, Resolution};
+//!
+//! let times = [UNIX_EPOCH + Duration::from_secs(1_700_000_000), UNIX_EPOCH + Duration::from_secs(1_700_000_001)];
+//! let mut buf = Vec::new();
+//! timestamp::write_timestamps_delta(&times, Resolution::Millis, &mut buf);
+//! assert_eq!(buf.len(), 8);
+//!
+//! let decoded = timestamp::read_timestamps_delta(&buf, Resolution::Millis)
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(decoded, times);
+//! ```
+use crate::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How precise the stored timestamps are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl Resolution {
+    /// How many nanoseconds in one unit of this resolution
+    fn nanos(&self) -> i128 {
+        match self {
+            Resolution::Seconds => 1_000_000_000,
+            Resolution::Millis => 1_000_000,
+            Resolution::Micros => 1_000,
+            Resolution::Nanos => 1,
+        }
+    }
+}
+
+/// Convert a time to the number of units since the UNIX epoch. Times which aren't an exact number
+/// of units are rounded down (i.e. towards the past).
+pub fn to_epoch(time: SystemTime, resolution: Resolution) -> i128 {
+    let nanos = match time.duration_since(UNIX_EPOCH) {
+        Ok(dur) => dur.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    };
+    nanos.div_euclid(resolution.nanos())
+}
+
+/// Convert a number of units since the UNIX epoch to a time. `None` if that time can't be
+/// represented.
+pub fn from_epoch(val: i128, resolution: Resolution) -> Option<SystemTime> {
+    let nanos = val.checked_mul(resolution.nanos())?;
+    let abs = nanos.unsigned_abs();
+    let dur = Duration::new(
+        (abs / 1_000_000_000).try_into().ok()?,
+        (abs % 1_000_000_000) as u32,
+    );
+    if nanos >= 0 {
+        UNIX_EPOCH.checked_add(dur)
+    } else {
+        UNIX_EPOCH.checked_sub(dur)
+    }
+}
+
+/// Write a time to this buffer, returning the number of bytes written
+pub fn write_timestamp(time: SystemTime, resolution: Resolution, buf: &mut Vec<u8>) -> usize {
+    write_i128(to_epoch(time, resolution), buf)
+}
+
+/// Read a time from this buffer. A time which can't be represented is `OutOfRange`.
+pub fn read_timestamp(
+    buf: &[u8],
+    resolution: Resolution,
+) -> Result<(SystemTime, &[u8]), VartyIntError> {
+    let (val, rest) = read_i128(buf)?;
+    let time = from_epoch(val, resolution).ok_or(VartyIntError::OutOfRange)?;
+    Ok((time, rest))
+}
+
+/// Write many times, delta encoded, adding to the end of the buf Vec. Returns the number of bytes
+/// written.
+pub fn write_timestamps_delta(
+    times: &[SystemTime],
+    resolution: Resolution,
+    buf: &mut Vec<u8>,
+) -> usize {
+    let mut len = 0;
+    let mut last = 0;
+    for time in times {
+        let val = to_epoch(*time, resolution);
+        len += write_i128(val - last, buf);
+        last = val;
+    }
+    len
+}
+
+/// Read many delta encoded times from this buffer, one after the other. If the running total
+/// overflows, `DeltaOverflow` is returned.
+pub fn read_timestamps_delta(
+    buf: &[u8],
+    resolution: Resolution,
+) -> impl Iterator<Item = Result<SystemTime, VartyIntError>> + '_ {
+    read_many_delta_checked::<i128>(buf).map(move |val| {
+        let val = val?;
+        from_epoch(val, resolution).ok_or(VartyIntError::OutOfRange)
+    })
+}
@@ -0,0 +1,64 @@
+//! Read integers from a file, without managing buffers.
+//!
+//! ```rust
+//! # let path = std::env::temp_dir().join(format!("vartyint-doctest-file-{}", std::process::id()));
+//! # std::fs::write(&path, vartyint::write_many_delta_new(&[100i64, 102, 99])).unwrap();
+//! let nums = vartyint::VarIntFile::open(&path)
+//!     .unwrap()
+//!     .deltas::<i64>()
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(nums, vec![100, 102, 99]);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+use crate::*;
+use std::io::BufReader;
+use std::path::Path;
+
+/// A file of varints, read with a `BufReader`
+#[derive(Debug)]
+pub struct VarIntFile {
+    reader: BufReader<std::fs::File>,
+}
+
+impl VarIntFile {
+    /// Open this file
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(VarIntFile {
+            reader: BufReader::new(std::fs::File::open(path)?),
+        })
+    }
+
+    /// A `VarIntReader` for this file, e.g. to skip values or seek to a recorded position
+    pub fn into_reader(self) -> VarIntReader<BufReader<std::fs::File>> {
+        VarIntReader::new(self.reader)
+    }
+
+    /// Read every value in the file. Stops after the first error.
+    pub fn values<T: VarInt>(self) -> impl Iterator<Item = Result<T, VartyIntReadError>> {
+        let mut reader = Some(self.reader);
+        std::iter::from_fn(move || {
+            let result = read_from::<T, _>(reader.as_mut()?).transpose()?;
+            if result.is_err() {
+                reader = None;
+            }
+            Some(result)
+        })
+    }
+
+    /// Read every value in the file, which was written with `write_many_delta`. If the running
+    /// total overflows, `DeltaOverflow` is returned. Stops after the first error.
+    pub fn deltas<T: DeltaInt>(self) -> impl Iterator<Item = Result<T, VartyIntReadError>> {
+        // `None` after an overflow
+        let mut last = Some(T::zero());
+        self.values::<T>().map_while(move |delta| {
+            let prev = last?;
+            let result = delta.and_then(|delta| {
+                prev.checked_add_delta(delta)
+                    .ok_or(VartyIntError::DeltaOverflow.into())
+            });
+            last = result.as_ref().ok().copied();
+            Some(result)
+        })
+    }
+}
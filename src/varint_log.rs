@@ -0,0 +1,190 @@
+//! An append only log of batches of integers, stored in a file.
+//!
+//! Each batch is written as the number of integers, the length of the batch in bytes, and then the
+//! integers as varints. If the program crashes while writing, the final batch might only be
+//! partially written ("torn"). `VarIntLogReader` stops before a torn batch, and `VarIntLog::open`
+//! removes it before appending more. Only a final batch which is shorter than its length is torn,
+//! `VarIntLog::open` won't remove anything else.
+//!
+//! ```rust
+//! use vartyint::{VarIntLog, VarIntLogReader};
+//! # let path = std::env::temp_dir().join(format!("vartyint-doctest-log-{}", std::process::id()));
+//! let mut log = VarIntLog::<u64>::open(&path).unwrap();
+//! log.append(&[1, 2, 3]).unwrap();
+//! log.append(&[1_000]).unwrap();
+//! log.sync().unwrap();
+//!
+//! let batches = VarIntLogReader::<u64>::open(&path)
+//!     .unwrap()
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(batches, vec![vec![1, 2, 3], vec![1_000]]);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+use crate::*;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Writes batches of integers to the end of a file
+#[derive(Debug)]
+pub struct VarIntLog<T> {
+    file: std::fs::File,
+    buf: Vec<u8>,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T: VarInt> VarIntLog<T> {
+    /// Open this log file, creating it if it doesn't exist. If the final batch is torn, it's
+    /// removed. If any batch can't be read (e.g. it's corrupt, or it's not a log file),
+    /// `InvalidData` is returned & the file isn't changed.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let file_len = file.metadata()?.len();
+        let valid_len = valid_length::<T>(&mut file, file_len)?;
+        if valid_len < file_len {
+            file.set_len(valid_len)?;
+        }
+        file.seek(SeekFrom::Start(valid_len))?;
+        Ok(VarIntLog {
+            file,
+            buf: Vec::new(),
+            _type: std::marker::PhantomData,
+        })
+    }
+
+    /// Append this batch to the end of the log, returning the number of bytes written. The whole
+    /// batch is written with one write.
+    pub fn append(&mut self, batch: &[T]) -> std::io::Result<usize> {
+        let payload = write_many_new(batch);
+        self.buf.clear();
+        write_usize(batch.len(), &mut self.buf);
+        write_usize(payload.len(), &mut self.buf);
+        self.buf.extend_from_slice(&payload);
+        self.file.write_all(&self.buf)?;
+        Ok(self.buf.len())
+    }
+
+    /// Flush all appended batches to disk. Batches appended before this won't be lost if the
+    /// machine crashes.
+    pub fn sync(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.file.sync_data()
+    }
+}
+
+/// Read the header of the batch at the start of this buffer: (number of integers, payload, rest).
+/// `None` if the batch is torn.
+fn read_batch(buf: &[u8]) -> Option<(usize, &[u8], &[u8])> {
+    let (count, rest) = read_usize(buf).ok()?;
+    let (len, rest) = read_usize(rest).ok()?;
+    if rest.len() < len {
+        return None;
+    }
+    let (payload, rest) = rest.split_at(len);
+    Some((count, payload, rest))
+}
+
+/// How many bytes at the start of this log file are complete batches. The file is read a batch at
+/// a time. A final batch which is shorter than its length is torn, any other batch which can't be
+/// read is `InvalidData`.
+fn valid_length<T: VarInt>(file: &mut std::fs::File, file_len: u64) -> std::io::Result<u64> {
+    let invalid = |e: VartyIntError| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    file.seek(SeekFrom::Start(0))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut pos = 0;
+    let mut payload = Vec::new();
+    loop {
+        let (count, len) = match read_from::<(usize, usize), _>(&mut reader) {
+            Ok(Some(header)) => header,
+            Ok(None) => return Ok(pos),
+            // The file ends part way through the header
+            Err(VartyIntReadError::VartyIntError(e @ VartyIntError::NotEnoughBytes { .. })) => {
+                return if file_len - pos < 2 * usize::MAX_LEN as u64 {
+                    Ok(pos)
+                } else {
+                    Err(invalid(e))
+                };
+            }
+            Err(VartyIntReadError::VartyIntError(e)) => return Err(invalid(e)),
+            Err(VartyIntReadError::ReadError(e)) => return Err(e),
+        };
+        let payload_start = reader.stream_position()?;
+        if len as u64 > file_len - payload_start {
+            return Ok(pos);
+        }
+        payload.resize(len, 0);
+        reader.read_exact(&mut payload)?;
+        match read_many_exact::<T>(&payload, count) {
+            Ok((_, [])) => {}
+            Ok(_) => return Err(invalid(VartyIntError::TrailingBytes)),
+            Err(e) => return Err(invalid(e)),
+        }
+        pos = payload_start + len as u64;
+    }
+}
+
+/// Reads the batches of a log, as `Vec`s. A torn final batch is skipped.
+#[derive(Debug, Clone)]
+pub struct VarIntLogReader<T> {
+    bytes: Vec<u8>,
+    pos: usize,
+    torn: bool,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T: VarInt> VarIntLogReader<T> {
+    /// Read the log in this file
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::from_bytes(std::fs::read(path)?))
+    }
+
+    /// Read the log in these bytes
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        VarIntLogReader {
+            bytes,
+            pos: 0,
+            torn: false,
+            _type: std::marker::PhantomData,
+        }
+    }
+
+    /// True iff a torn final batch has been found (and skipped)
+    pub fn is_torn(&self) -> bool {
+        self.torn
+    }
+}
+
+impl<T: VarInt> Iterator for VarIntLogReader<T> {
+    type Item = Result<Vec<T>, VartyIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.bytes[self.pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        let (count, payload, newrest) = match read_batch(rest) {
+            Some(batch) => batch,
+            None => {
+                self.torn = true;
+                self.pos = self.bytes.len();
+                return None;
+            }
+        };
+        self.pos = self.bytes.len() - newrest.len();
+        // The batch is complete, so any errors here are corruption, not a torn write
+        let result = match read_many_exact::<T>(payload, count) {
+            Ok((nums, [])) => Ok(nums),
+            Ok(_) => Err(VartyIntError::TrailingBytes),
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            self.pos = self.bytes.len();
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,317 @@
+//! `VarIntMap`, a sorted map from unsigned integer keys to byte string values, which is read
+//! straight from a byte slice (e.g. a memory mapped file), without loading it all first.
+//!
+//! ```rust
+//! use vartyint::varint_map::{VarIntMap, VarIntMapBuilder};
+//! let mut builder = VarIntMapBuilder::new().block_len(2);
+//! builder.insert(10u64, b"ten").unwrap();
+//! builder.insert(20, b"twenty").unwrap();
+//! builder.insert(1_000, b"thousand").unwrap();
+//! let buf = builder.finish();
+//!
+//! let map = VarIntMap::<u64>::new(&buf).unwrap();
+//! assert_eq!(map.len(), 3);
+//! assert_eq!(map.get(20).unwrap(), Some(&b"twenty"[..]));
+//! assert_eq!(map.get(21).unwrap(), None);
+//! assert_eq!(
+//!     map.range(15..).unwrap(),
+//!     vec![(20, &b"twenty"[..]), (1_000, &b"thousand"[..])]
+//! );
+//! ```
+//!
+//! The data starts with a [`header`](crate::header) (magic `VTKV`, version 1), then the number of
+//! entries, the number of blocks & the index: the first key (delta encoded from the previous
+//! block's), the number of entries & the length in bytes of each block. Then the blocks, each of
+//! which is the entries, as the key (delta encoded from the previous key in the block, the first
+//! is 0), the length of the value & the value.
+use crate::block_cache::BlockCache;
+use crate::header::{read_header, write_header, Header};
+use crate::*;
+use std::ops::{Bound, RangeBounds};
+
+const MAGIC: &[u8] = b"VTKV";
+const VERSION: u64 = 1;
+
+/// Default number of entries in a block
+const DEFAULT_BLOCK_LEN: usize = 64;
+
+/// Builds a `VarIntMap`. Keys must be inserted in order.
+#[derive(Debug, Clone)]
+pub struct VarIntMapBuilder<K> {
+    /// Number of entries in each block. Lookups decode (on average) half a block.
+    pub block_len: usize,
+    entries: Vec<(K, Vec<u8>)>,
+}
+
+impl<K: VarIntUnsigned> Default for VarIntMapBuilder<K> {
+    fn default() -> Self {
+        VarIntMapBuilder {
+            block_len: DEFAULT_BLOCK_LEN,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K: VarIntUnsigned> VarIntMapBuilder<K> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the number of entries in each block. Panics if it's 0.
+    pub fn block_len(mut self, block_len: usize) -> Self {
+        assert!(block_len > 0, "block_len must be at least 1");
+        self.block_len = block_len;
+        self
+    }
+
+    /// Add this entry. If `key` isn't bigger than the last key, `OutOfRange` is returned & it's
+    /// not added.
+    pub fn insert(&mut self, key: K, value: &[u8]) -> Result<(), VartyIntError> {
+        if self.entries.last().is_some_and(|(last, _)| *last >= key) {
+            return Err(VartyIntError::OutOfRange);
+        }
+        self.entries.push((key, value.to_vec()));
+        Ok(())
+    }
+
+    /// Number of entries so far
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True iff there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the map to the end of `buf`, returning the number of bytes written
+    pub fn write(&self, buf: &mut Vec<u8>) -> usize {
+        let start = buf.len();
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        let mut last_first = K::zero();
+        for block in self.entries.chunks(self.block_len.max(1)) {
+            let block_start = data.len();
+            let first = block[0].0;
+            let mut last = first;
+            for (key, value) in block {
+                key.checked_gap(last).unwrap().write_varint(&mut data);
+                value.len().write_varint(&mut data);
+                data.extend_from_slice(value);
+                last = *key;
+            }
+            first
+                .checked_gap(last_first)
+                .unwrap()
+                .write_varint(&mut index);
+            block.len().write_varint(&mut index);
+            (data.len() - block_start).write_varint(&mut index);
+            last_first = first;
+        }
+
+        write_header(MAGIC, &Header::new(VERSION), buf);
+        self.entries.len().write_varint(buf);
+        self.entries
+            .len()
+            .div_ceil(self.block_len.max(1))
+            .write_varint(buf);
+        buf.extend_from_slice(&index);
+        buf.extend_from_slice(&data);
+        buf.len() - start
+    }
+
+    /// The map, as a new Vec
+    pub fn finish(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write(&mut buf);
+        buf
+    }
+}
+
+/// The decoded entries of one block
+type Entries<'a, K> = Vec<(K, &'a [u8])>;
+
+/// One block in the index
+#[derive(Debug, Clone, Copy)]
+struct Block<K> {
+    first: K,
+    count: usize,
+    offset: usize,
+    len: usize,
+}
+
+/// A map written by `VarIntMapBuilder`. Only the index is decoded when it's opened, the blocks
+/// are decoded when they're needed, & values are borrowed from the buffer.
+#[derive(Debug, Clone)]
+pub struct VarIntMap<'a, K> {
+    len: usize,
+    blocks: Vec<Block<K>>,
+    data: &'a [u8],
+    cache: Option<BlockCache<Entries<'a, K>>>,
+}
+
+impl<'a, K: VarIntUnsigned> VarIntMap<'a, K> {
+    /// Read the header & index from this buffer. If it's not a `VarIntMap`, `WrongMagic` is
+    /// returned.
+    pub fn new(buf: &'a [u8]) -> Result<Self, VartyIntError> {
+        let (_, rest) = read_header(buf, MAGIC, VERSION..=VERSION)?;
+        let ((len, num_blocks), mut rest) =
+            <(usize, usize)>::read_varint(rest).map_err(empty_to_not_enough::<(usize, usize)>)?;
+        let mut blocks = Vec::with_capacity(num_blocks.min(rest.len()));
+        let mut first = K::zero();
+        let mut offset = 0usize;
+        let mut total = 0usize;
+        for _ in 0..num_blocks {
+            let ((gap, count, block_len), newrest) = <(K, usize, usize)>::read_varint(rest)
+                .map_err(empty_to_not_enough::<(K, usize, usize)>)?;
+            first = first
+                .checked_add_delta(gap)
+                .ok_or(VartyIntError::DeltaOverflow)?;
+            // Each entry is at least 2 bytes, so a block can't have more entries than bytes
+            if count == 0 || count > block_len || (!blocks.is_empty() && gap == K::zero()) {
+                return Err(VartyIntError::OutOfRange);
+            }
+            blocks.push(Block {
+                first,
+                count,
+                offset,
+                len: block_len,
+            });
+            offset = offset
+                .checked_add(block_len)
+                .ok_or(VartyIntError::OutOfRange)?;
+            total = total.checked_add(count).ok_or(VartyIntError::OutOfRange)?;
+            rest = newrest;
+        }
+        if total != len {
+            return Err(VartyIntError::OutOfRange);
+        }
+        if rest.len() < offset {
+            return Err(VartyIntError::not_enough(rest.len(), offset));
+        }
+        if rest.len() > offset {
+            return Err(VartyIntError::TrailingBytes);
+        }
+        Ok(VarIntMap {
+            len,
+            blocks,
+            data: rest,
+            cache: None,
+        })
+    }
+
+    /// Keep up to `blocks` decoded blocks in a `BlockCache`, so lookups in them are fast
+    pub fn with_cache(mut self, blocks: usize) -> Self {
+        self.cache = Some(BlockCache::new(blocks));
+        self
+    }
+
+    /// The cache, if there is one
+    pub fn cache(&self) -> Option<&BlockCache<Entries<'a, K>>> {
+        self.cache.as_ref()
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True iff there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of blocks
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Decode all the entries in block `i` (or get them from the cache)
+    fn block(&self, i: usize) -> Result<Entries<'a, K>, VartyIntError> {
+        match &self.cache {
+            Some(cache) => cache.get_or_insert_with(i, |i| self.decode_block(i)),
+            None => self.decode_block(i),
+        }
+    }
+
+    fn decode_block(&self, i: usize) -> Result<Entries<'a, K>, VartyIntError> {
+        let block = &self.blocks[i];
+        let mut buf = &self.data[block.offset..block.offset + block.len];
+        let mut entries = Vec::with_capacity(block.count.min(block.len));
+        let mut key = block.first;
+        for i in 0..block.count {
+            let ((gap, len), rest) =
+                <(K, usize)>::read_varint(buf).map_err(empty_to_not_enough::<(K, usize)>)?;
+            if i > 0 && gap == K::zero() {
+                return Err(VartyIntError::OutOfRange);
+            }
+            key = key
+                .checked_add_delta(gap)
+                .ok_or(VartyIntError::DeltaOverflow)?;
+            if rest.len() < len {
+                return Err(VartyIntError::not_enough(rest.len(), len));
+            }
+            entries.push((key, &rest[..len]));
+            buf = &rest[len..];
+        }
+        if !buf.is_empty() {
+            return Err(VartyIntError::TrailingBytes);
+        }
+        Ok(entries)
+    }
+
+    /// The block which `key` would be in, if any
+    fn block_for(&self, key: K) -> Option<usize> {
+        self.blocks
+            .partition_point(|block| block.first <= key)
+            .checked_sub(1)
+    }
+
+    /// The value for this key. Only the block it would be in is decoded.
+    pub fn get(&self, key: K) -> Result<Option<&'a [u8]>, VartyIntError> {
+        let Some(i) = self.block_for(key) else {
+            return Ok(None);
+        };
+        Ok(self
+            .block(i)?
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value))
+    }
+
+    /// True iff this key is in the map
+    pub fn contains_key(&self, key: K) -> Result<bool, VartyIntError> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// All the entries with keys in this range, in order. Only the blocks which could have keys
+    /// in the range are decoded.
+    pub fn range(&self, range: impl RangeBounds<K>) -> Result<Vec<(K, &'a [u8])>, VartyIntError> {
+        let start = match range.start_bound() {
+            Bound::Included(key) | Bound::Excluded(key) => self.block_for(*key).unwrap_or(0),
+            Bound::Unbounded => 0,
+        };
+        let mut entries = Vec::new();
+        for i in start..self.blocks.len() {
+            let past_end = match range.end_bound() {
+                Bound::Included(key) => self.blocks[i].first > *key,
+                Bound::Excluded(key) => self.blocks[i].first >= *key,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                break;
+            }
+            entries.extend(
+                self.block(i)?
+                    .into_iter()
+                    .filter(|(key, _)| range.contains(key)),
+            );
+        }
+        Ok(entries)
+    }
+
+    /// All the entries, in order
+    pub fn to_vec(&self) -> Result<Vec<(K, &'a [u8])>, VartyIntError> {
+        self.range(..)
+    }
+}
@@ -0,0 +1,232 @@
+//! A buffered reader of varints, which owns a `std::io::Read`. The matching reader for
+//! [`VarIntWriter`](crate::VarIntWriter).
+//!
+//! ```rust
+//! use vartyint::VarIntReader;
+//! let data: &[u8] = &[0xAC, 0x02, 0xD0, 0x0F, 0x02, 0x01, 0x01];
+//! let mut reader = VarIntReader::new(data);
+//! assert_eq!(reader.read_u64().unwrap(), Some(300));
+//! assert_eq!(reader.read_delta().unwrap(), Some(1_000));
+//! assert_eq!(reader.read_delta().unwrap(), Some(1_001));
+//! assert_eq!(reader.peek::<u8>().unwrap(), Some(1));
+//! assert_eq!(reader.position(), 5);
+//! assert_eq!(reader.read_value::<(u8, i32)>().unwrap(), Some((1, -1)));
+//! assert_eq!(reader.read_u8().unwrap(), None);
+//! ```
+use crate::*;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Default size of the read buffer
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+macro_rules! typed_read {
+    ( $( $name:ident $type:ty ),* ) => {
+        $(
+            /// Read one integer. Returns `Ok(None)` at the end of the reader.
+            pub fn $name(&mut self) -> Result<Option<$type>, VartyIntReadError> {
+                self.read_value()
+            }
+        )*
+    };
+}
+
+/// Reads varints from the inner reader, a buffer at a time
+#[derive(Debug)]
+pub struct VarIntReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    // `buf[start..end]` hasn't been read yet
+    start: usize,
+    end: usize,
+    eof: bool,
+    last: i64,
+    position: u64,
+}
+
+impl<R: Read> VarIntReader<R> {
+    /// A reader with an 8 KiB buffer
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    /// A reader which reads up to `capacity` bytes at a time from `inner`
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        VarIntReader {
+            inner,
+            buf: vec![0; capacity.max(1)],
+            start: 0,
+            end: 0,
+            eof: false,
+            last: 0,
+            position: 0,
+        }
+    }
+
+    /// Read one value. Returns `Ok(None)` at the end of the reader, and `NotEnoughBytes` if it
+    /// ends part way through the value.
+    ///
+    /// If the value can't be decoded, nothing is read, so `position` is the start of it.
+    pub fn read_value<T: VarInt>(&mut self) -> Result<Option<T>, VartyIntReadError> {
+        let res = self.decode::<T>()?;
+        Ok(res.map(|(val, len)| {
+            self.start += len;
+            self.position += len as u64;
+            val
+        }))
+    }
+
+    /// Read the next value without consuming it, so the next `peek` or `read_value` returns it
+    /// again.
+    pub fn peek<T: VarInt>(&mut self) -> Result<Option<T>, VartyIntReadError> {
+        Ok(self.decode::<T>()?.map(|(val, _)| val))
+    }
+
+    typed_read!(
+        read_u8 u8, read_u16 u16, read_u32 u32, read_u64 u64, read_u128 u128,
+        read_usize usize, read_i8 i8, read_i16 i16, read_i32 i32, read_i64 i64,
+        read_i128 i128, read_isize isize
+    );
+
+    /// Read a value written by `VarIntWriter::write_delta`, i.e. add the next delta to the
+    /// previous value returned by `read_delta` (or 0 for the first).
+    pub fn read_delta(&mut self) -> Result<Option<i64>, VartyIntReadError> {
+        Ok(self.read_value::<i64>()?.map(|delta| {
+            self.last = self.last.wrapping_add(delta);
+            self.last
+        }))
+    }
+
+    /// Start the next `read_delta` from 0 again
+    pub fn reset_delta(&mut self) {
+        self.last = 0;
+    }
+
+    /// Skip `n` values, without decoding them, by only looking for the last byte of each varint.
+    /// Returns the number skipped, which is less than `n` if the reader ends first. If it ends
+    /// part way through a value, `NotEnoughBytes` is returned, & `position` is the start of it.
+    ///
+    /// Tuples etc. are many varints, so each part counts as one value.
+    pub fn skip(&mut self, n: u64) -> Result<u64, VartyIntReadError> {
+        let mut skipped = 0;
+        while skipped < n {
+            // Up to the end of the last whole varint
+            let mut len = 0;
+            for (i, b) in self.buffer().iter().enumerate() {
+                if b >> 7 == 0 {
+                    skipped += 1;
+                    len = i + 1;
+                    if skipped == n {
+                        break;
+                    }
+                }
+            }
+            self.start += len;
+            self.position += len as u64;
+            if skipped == n {
+                break;
+            }
+            if self.eof {
+                if !self.buffer().is_empty() {
+                    return Err(VartyIntError::NotEnoughBytes {
+                        read: self.buffer().len(),
+                        max_more: None,
+                    }
+                    .into());
+                }
+                break;
+            }
+            self.fill()?;
+        }
+        Ok(skipped)
+    }
+
+    /// Number of bytes read so far, i.e. the offset of the next value in the data
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The bytes which have been read from the inner reader, but not decoded yet
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.start..self.end]
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The inner reader. Any buffered bytes are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decode the next value, returning it & its length, without consuming it
+    fn decode<T: VarInt>(&mut self) -> Result<Option<(T, usize)>, VartyIntReadError> {
+        // Only the last byte of a varint has the top bit unset. If there are too many bytes for
+        // `T`, there's no need to read any more, `T` will return an error.
+        while !self.eof
+            && self.buffer().len() < T::MAX_LEN
+            && self.buffer().iter().all(|b| b >> 7 == 1)
+        {
+            self.fill()?;
+        }
+        loop {
+            match T::read_varint(self.buffer()) {
+                Ok((val, rest)) => return Ok(Some((val, self.buffer().len() - rest.len()))),
+                Err(VartyIntError::EmptyBuffer) => return Ok(None),
+                // Values which are many varints (e.g. tuples) can need more than the first
+                Err(VartyIntError::NotEnoughBytes { .. }) if !self.eof => self.fill()?,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Read more bytes from the inner reader
+    fn fill(&mut self) -> std::io::Result<()> {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        if self.end == self.buf.len() {
+            self.buf.resize(self.buf.len() * 2, 0);
+        }
+        loop {
+            match self.inner.read(&mut self.buf[self.end..]) {
+                Ok(0) => self.eof = true,
+                Ok(len) => self.end += len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+            return Ok(());
+        }
+    }
+}
+
+impl<R: Read + Seek> VarIntReader<R> {
+    /// Go to this `position` (e.g. one recorded earlier), so the next value is read from there.
+    /// If it's in the buffer, the inner reader isn't used. Positions are from where the inner
+    /// reader was when this reader was made. The `read_delta` total isn't changed.
+    pub fn seek_to(&mut self, position: u64) -> std::io::Result<()> {
+        // `buf[0]` is at `buf_start` in the data
+        let buf_start = self.position - self.start as u64;
+        if position >= buf_start && position <= buf_start + self.end as u64 {
+            self.start = (position - buf_start) as usize;
+            self.position = position;
+            return Ok(());
+        }
+        // The inner reader is at the end of the buffer
+        let inner_pos = buf_start + self.end as u64;
+        let offset = if position > inner_pos {
+            i64::try_from(position - inner_pos)
+        } else {
+            i64::try_from(inner_pos - position).map(|offset| -offset)
+        }
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek too far"))?;
+        self.inner.seek(SeekFrom::Current(offset))?;
+        self.start = 0;
+        self.end = 0;
+        self.eof = false;
+        self.position = position;
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+//! `VarIntVec`, a list of integers stored as varints, & `VarIntSlice`, a borrowed view of one.
+use crate::*;
+
+/// A list of integers, stored as varints. Like a `Vec`, but (usually) much smaller.
+///
+/// ```rust
+/// use vartyint::VarIntVec;
+/// let mut nums: VarIntVec<u32> = (0..100).collect();
+/// nums.push(1_000_000);
+/// assert_eq!(nums.len(), 101);
+/// assert_eq!(nums.as_bytes().len(), 100 + 3);
+/// assert_eq!(nums.iter().last(), Some(1_000_000));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VarIntVec<T> {
+    bytes: Vec<u8>,
+    len: usize,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T> Default for VarIntVec<T> {
+    fn default() -> Self {
+        VarIntVec {
+            bytes: Vec::new(),
+            len: 0,
+            _type: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: VarInt> VarIntVec<T> {
+    /// Create a new empty list
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a list from the bytes of varints (e.g. from `write_many`). Returns an error if the
+    /// bytes aren't valid.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, VartyIntError> {
+        let len = count_valid::<T>(&bytes)?;
+        Ok(VarIntVec {
+            bytes,
+            len,
+            _type: std::marker::PhantomData,
+        })
+    }
+
+    /// Add this integer to the end
+    pub fn push(&mut self, val: T) {
+        val.write_varint(&mut self.bytes);
+        self.len += 1;
+    }
+
+    /// Number of integers
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True iff there are no integers
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the integers
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            bytes: &self.bytes,
+            remaining: self.len,
+            _type: std::marker::PhantomData,
+        }
+    }
+
+    /// The encoded bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Return the encoded bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// A borrowed view of all the integers
+    pub fn as_slice(&self) -> VarIntSlice<'_, T> {
+        VarIntSlice {
+            bytes: &self.bytes,
+            len: self.len,
+            _type: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Number of integers in this buffer, or an error if it isn't valid
+fn count_valid<T: VarInt>(bytes: &[u8]) -> Result<usize, VartyIntError> {
+    let mut len = 0;
+    for num in read_many::<T>(bytes) {
+        num?;
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// A borrowed list of integers stored as varints, i.e. `VarIntVec` is to `VarIntSlice` as
+/// `String` is to `&str`. The bytes are only checked (& counted) once, when it's created.
+///
+/// ```rust
+/// use vartyint::VarIntSlice;
+/// let buf = vartyint::write_many_new(&[5u64, 300, 70_000]);
+/// let nums = VarIntSlice::<u64>::new(&buf).unwrap();
+/// assert_eq!(nums.len(), 3);
+/// assert_eq!(nums.get(1), Some(300));
+/// assert_eq!(nums.iter().sum::<u64>(), 70_305);
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct VarIntSlice<'a, T> {
+    bytes: &'a [u8],
+    len: usize,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T> Clone for VarIntSlice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VarIntSlice<'_, T> {}
+
+impl<'a, T: VarInt> VarIntSlice<'a, T> {
+    /// A view of the varints in these bytes. Returns an error if the bytes aren't valid.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, VartyIntError> {
+        let len = count_valid::<T>(bytes)?;
+        Ok(VarIntSlice {
+            bytes,
+            len,
+            _type: std::marker::PhantomData,
+        })
+    }
+
+    /// Number of integers
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True iff there are no integers
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the integers
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            bytes: self.bytes,
+            remaining: self.len,
+            _type: std::marker::PhantomData,
+        }
+    }
+
+    /// The integer at position `i`. This decodes every integer before it, so use `build_index`
+    /// & `get_indexed` for many lookups.
+    pub fn get(&self, i: usize) -> Option<T> {
+        self.iter().nth(i)
+    }
+
+    /// Record the byte offset of every `every`th integer, for `get_indexed`
+    pub fn build_index(&self, every: usize) -> SliceIndex {
+        assert!(every > 0, "every must be at least 1");
+        let mut offsets = Vec::with_capacity(self.len / every + 1);
+        let mut bytes = self.bytes;
+        for i in 0..self.len {
+            if i % every == 0 {
+                offsets.push(self.bytes.len() - bytes.len());
+            }
+            bytes = next_valid::<T>(bytes).unwrap().1;
+        }
+        SliceIndex { every, offsets }
+    }
+
+    /// The integer at position `i`, decoding at most `every` integers, with an index from
+    /// `build_index` on this slice.
+    pub fn get_indexed(&self, index: &SliceIndex, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
+        }
+        let block = i / index.every;
+        let start = i - i % index.every;
+        Iter::<T> {
+            bytes: &self.bytes[index.offsets[block]..],
+            remaining: self.len - start,
+            _type: std::marker::PhantomData,
+        }
+        .nth(i % index.every)
+    }
+
+    /// The encoded bytes
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Copy into a new `VarIntVec`
+    pub fn to_vec(&self) -> VarIntVec<T> {
+        VarIntVec {
+            bytes: self.bytes.to_vec(),
+            len: self.len,
+            _type: std::marker::PhantomData,
+        }
+    }
+}
+
+/// The byte offsets of every `every`th integer in a `VarIntSlice`, from
+/// `VarIntSlice::build_index`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SliceIndex {
+    every: usize,
+    offsets: Vec<usize>,
+}
+
+impl<'a, T: VarInt> IntoIterator for VarIntSlice<'a, T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: VarInt> From<&'a VarIntVec<T>> for VarIntSlice<'a, T> {
+    fn from(vec: &'a VarIntVec<T>) -> Self {
+        vec.as_slice()
+    }
+}
+
+/// Read the next integer from a buffer which is known to be valid
+fn next_valid<T: VarInt>(bytes: &[u8]) -> Option<(T, &[u8])> {
+    T::read_varint(bytes).ok()
+}
+
+/// Iterator over the integers in a `VarIntVec` or `VarIntSlice`
+#[derive(Debug, Clone)]
+pub struct Iter<'a, T> {
+    bytes: &'a [u8],
+    remaining: usize,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T: VarInt> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let (num, rest) = next_valid(self.bytes)?;
+        self.bytes = rest;
+        self.remaining -= 1;
+        Some(num)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: VarInt> ExactSizeIterator for Iter<'_, T> {}
+impl<T: VarInt> std::iter::FusedIterator for Iter<'_, T> {}
+
+/// Owning iterator over the integers in a `VarIntVec`
+#[derive(Debug, Clone)]
+pub struct IntoIter<T> {
+    bytes: Vec<u8>,
+    pos: usize,
+    remaining: usize,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T: VarInt> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let bytes = &self.bytes[self.pos..];
+        let (num, rest) = next_valid(bytes)?;
+        self.pos += bytes.len() - rest.len();
+        self.remaining -= 1;
+        Some(num)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: VarInt> ExactSizeIterator for IntoIter<T> {}
+impl<T: VarInt> std::iter::FusedIterator for IntoIter<T> {}
+
+impl<T: VarInt> FromIterator<T> for VarIntVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = VarIntVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T: VarInt> Extend<T> for VarIntVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.bytes.reserve(iter.size_hint().0);
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<'a, T: VarInt> Extend<&'a T> for VarIntVec<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<T: VarInt> IntoIterator for VarIntVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            bytes: self.bytes,
+            pos: 0,
+            remaining: self.len,
+            _type: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, T: VarInt> IntoIterator for &'a VarIntVec<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
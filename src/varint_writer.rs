@@ -0,0 +1,142 @@
+//! A buffered writer of varints, which owns a `std::io::Write`.
+//!
+//! Values are collected in a staging buffer, and written to the inner writer when it's full, on
+//! `flush`, or when the `VarIntWriter` is dropped (where errors are ignored, so call `flush`).
+//!
+//! ```rust
+//! use vartyint::VarIntWriter;
+//! let mut writer = VarIntWriter::new(Vec::new());
+//! writer.write_u64(300).unwrap();
+//! writer.write_delta(1_000).unwrap();
+//! writer.write_delta(1_001).unwrap();
+//! writer.write_value((1u8, -1i32)).unwrap();
+//! assert_eq!(writer.bytes_written(), 7);
+//! let out = writer.into_inner().unwrap();
+//! assert_eq!(out, vec![0xAC, 0x02, 0xD0, 0x0F, 0x02, 0x01, 0x01]);
+//! ```
+use crate::*;
+use std::io::Write;
+
+/// Default size of the staging buffer
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+macro_rules! typed_write {
+    ( $( $name:ident $type:ty ),* ) => {
+        $(
+            /// Write this integer, returning the number of bytes written
+            pub fn $name(&mut self, val: $type) -> std::io::Result<usize> {
+                self.write_value(val)
+            }
+        )*
+    };
+}
+
+/// Writes varints to a buffer, & the buffer to the inner writer when it's full
+#[derive(Debug)]
+pub struct VarIntWriter<W: Write> {
+    // Only `None` in `into_inner`
+    inner: Option<W>,
+    buf: Vec<u8>,
+    capacity: usize,
+    last: i64,
+    written: u64,
+}
+
+impl<W: Write> VarIntWriter<W> {
+    /// A writer with an 8 KiB staging buffer
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    /// A writer which writes to `inner` once `capacity` bytes are staged
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        VarIntWriter {
+            inner: Some(inner),
+            buf: Vec::with_capacity(capacity),
+            capacity,
+            last: 0,
+            written: 0,
+        }
+    }
+
+    /// Write this value, returning the number of bytes written
+    pub fn write_value<T: VarInt>(&mut self, val: T) -> std::io::Result<usize> {
+        let len = val.write_varint(&mut self.buf);
+        self.written += len as u64;
+        if self.buf.len() >= self.capacity {
+            self.flush_buf()?;
+        }
+        Ok(len)
+    }
+
+    typed_write!(
+        write_u8 u8, write_u16 u16, write_u32 u32, write_u64 u64, write_u128 u128,
+        write_usize usize, write_i8 i8, write_i16 i16, write_i32 i32, write_i64 i64,
+        write_i128 i128, write_isize isize
+    );
+
+    /// Write the difference between this value & the previous value given to `write_delta` (or
+    /// 0 for the first), like `write_many_delta`. The difference wraps on overflow.
+    pub fn write_delta(&mut self, val: i64) -> std::io::Result<usize> {
+        let delta = val.wrapping_sub(self.last);
+        self.last = val;
+        self.write_value(delta)
+    }
+
+    /// Start the next `write_delta` from 0 again
+    pub fn reset_delta(&mut self) {
+        self.last = 0;
+    }
+
+    /// Total number of bytes written, incl. those which are still staged
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// The bytes which haven't been written to the inner writer yet
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Flush the staged bytes, & return the inner writer
+    pub fn into_inner(mut self) -> std::io::Result<W> {
+        self.flush_buf()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    /// Write the staged bytes to the inner writer, without flushing it
+    fn flush_buf(&mut self) -> std::io::Result<()> {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.write_all(&self.buf)?;
+        }
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for VarIntWriter<W> {
+    /// Write raw bytes, after any staged varints
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        self.written += buf.len() as u64;
+        if self.buf.len() >= self.capacity {
+            self.flush_buf()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flush_buf()?;
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for VarIntWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_buf();
+    }
+}
@@ -0,0 +1,198 @@
+//! Integers in blocks, with the count, minimum & maximum of every block (a "zone map"), so range
+//! queries can skip blocks without decoding them.
+//!
+//! ```rust
+//! use vartyint::zonemap::{write_zoned, ZoneMap};
+//! let temps = [12i32, 14, 15, 13, 25, 27, 26, 24, 11, 10];
+//! let mut buf = Vec::new();
+//! write_zoned(&temps, 4, &mut buf);
+//!
+//! let (zones, _) = ZoneMap::<i32>::new(&buf).unwrap();
+//! assert_eq!(zones.num_blocks(), 3);
+//! // Only the 2nd block is decoded
+//! assert_eq!(zones.blocks_overlapping(25..).collect::<Vec<_>>(), vec![1]);
+//! assert_eq!(zones.range(25..).unwrap(), vec![25, 27, 26]);
+//! ```
+//!
+//! The data is the number of blocks, then for each block its count, minimum, maximum & length in
+//! bytes, then all the blocks, each as varints.
+use crate::block_cache::BlockCache;
+use crate::*;
+use std::ops::{Bound, RangeBounds};
+
+/// The metadata of one block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Zone<T> {
+    /// Number of integers in the block
+    pub count: usize,
+    /// The smallest integer in the block
+    pub min: T,
+    /// The largest integer in the block
+    pub max: T,
+    /// Where the block is in the data
+    offset: usize,
+    len: usize,
+}
+
+impl<T: Ord> Zone<T> {
+    /// True iff any value from `min` to `max` is in this range
+    pub fn overlaps(&self, range: &impl RangeBounds<T>) -> bool {
+        let after_start = match range.start_bound() {
+            Bound::Included(start) => self.max >= *start,
+            Bound::Excluded(start) => self.max > *start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match range.end_bound() {
+            Bound::Included(end) => self.min <= *end,
+            Bound::Excluded(end) => self.min < *end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+}
+
+/// Write these integers to the end of `buf`, in blocks of (up to) `block_len`, returning the
+/// number of bytes written. Panics if `block_len` is 0.
+pub fn write_zoned<T: VarInt + Ord>(nums: &[T], block_len: usize, buf: &mut Vec<u8>) -> usize {
+    assert!(block_len > 0, "block_len must be at least 1");
+    let start = buf.len();
+    let mut data = Vec::new();
+    nums.len().div_ceil(block_len).write_varint(buf);
+    for block in nums.chunks(block_len) {
+        let min = *block.iter().min().unwrap();
+        let max = *block.iter().max().unwrap();
+        let len = write_many(block, &mut data);
+        (block.len(), min, max, len).write_varint(buf);
+    }
+    buf.extend_from_slice(&data);
+    buf.len() - start
+}
+
+/// The blocks from `write_zoned`
+#[derive(Debug, Clone)]
+pub struct ZoneMap<'a, T> {
+    zones: Vec<Zone<T>>,
+    /// Total number of integers
+    len: usize,
+    data: &'a [u8],
+    cache: Option<BlockCache<Vec<T>>>,
+}
+
+impl<'a, T: VarInt + Ord> ZoneMap<'a, T> {
+    /// Read the metadata at the start of this buffer, returning it & the rest of the buffer. The
+    /// blocks aren't decoded.
+    pub fn new(buf: &'a [u8]) -> Result<(Self, &'a [u8]), VartyIntError> {
+        let (num, mut rest) = usize::read_varint(buf)?;
+        let mut zones = Vec::with_capacity(num.min(buf.len()));
+        let mut offset = 0usize;
+        let mut total = 0usize;
+        for _ in 0..num {
+            let ((count, min, max, len), newrest) = <(usize, T, T, usize)>::read_varint(rest)
+                .map_err(empty_to_not_enough::<(usize, T, T, usize)>)?;
+            // Each integer is at least 1 byte, so a block can't have more integers than bytes
+            if min > max || count == 0 || count > len {
+                return Err(VartyIntError::OutOfRange);
+            }
+            zones.push(Zone {
+                count,
+                min,
+                max,
+                offset,
+                len,
+            });
+            offset = offset.checked_add(len).ok_or(VartyIntError::OutOfRange)?;
+            total = total.checked_add(count).ok_or(VartyIntError::OutOfRange)?;
+            rest = newrest;
+        }
+        if rest.len() < offset {
+            return Err(VartyIntError::not_enough(rest.len(), offset));
+        }
+        let (data, rest) = rest.split_at(offset);
+        Ok((
+            ZoneMap {
+                zones,
+                len: total,
+                data,
+                cache: None,
+            },
+            rest,
+        ))
+    }
+
+    /// Keep up to `blocks` decoded blocks in a `BlockCache`, so reading them again is fast
+    pub fn with_cache(mut self, blocks: usize) -> Self {
+        self.cache = Some(BlockCache::new(blocks));
+        self
+    }
+
+    /// The cache, if there is one
+    pub fn cache(&self) -> Option<&BlockCache<Vec<T>>> {
+        self.cache.as_ref()
+    }
+
+    /// Number of blocks
+    pub fn num_blocks(&self) -> usize {
+        self.zones.len()
+    }
+
+    /// Total number of integers
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True iff there are no integers
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// The metadata of every block
+    pub fn zones(&self) -> &[Zone<T>] {
+        &self.zones
+    }
+
+    /// Decode all the integers in block `i` (or get them from the cache). Panics if there is no
+    /// block `i`.
+    pub fn block(&self, i: usize) -> Result<Vec<T>, VartyIntError> {
+        match &self.cache {
+            Some(cache) => cache.get_or_insert_with(i, |i| self.decode_block(i)),
+            None => self.decode_block(i),
+        }
+    }
+
+    fn decode_block(&self, i: usize) -> Result<Vec<T>, VartyIntError> {
+        let zone = &self.zones[i];
+        let (nums, rest) =
+            read_many_exact::<T>(&self.data[zone.offset..zone.offset + zone.len], zone.count)?;
+        if !rest.is_empty() {
+            return Err(VartyIntError::TrailingBytes);
+        }
+        if nums.iter().any(|num| *num < zone.min || *num > zone.max) {
+            return Err(VartyIntError::OutOfRange);
+        }
+        Ok(nums)
+    }
+
+    /// The numbers of the blocks which could have values in this range
+    pub fn blocks_overlapping<'r>(
+        &'r self,
+        range: impl RangeBounds<T> + 'r,
+    ) -> impl Iterator<Item = usize> + 'r {
+        self.zones
+            .iter()
+            .enumerate()
+            .filter(move |(_, zone)| zone.overlaps(&range))
+            .map(|(i, _)| i)
+    }
+
+    /// All the integers in this range, in the order they were written. Only the blocks which
+    /// could have values in the range are decoded.
+    pub fn range(&self, range: impl RangeBounds<T>) -> Result<Vec<T>, VartyIntError> {
+        let mut nums = Vec::new();
+        for (i, zone) in self.zones.iter().enumerate() {
+            if zone.overlaps(&range) {
+                nums.extend(self.block(i)?.into_iter().filter(|num| range.contains(num)));
+            }
+        }
+        Ok(nums)
+    }
+}
@@ -0,0 +1,377 @@
+//! `#[derive(VarIntCodec)]` for the [`vartyint`](https://docs.rs/vartyint/) crate.
+//!
+//! Use this via the `derive` feature of `vartyint`, rather than directly.
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// How one field is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// With its own `VarIntCodec` impl
+    Plain,
+    /// A `Vec` of integers, delta encoded
+    Delta,
+    /// An integer, as fixed width little endian bytes
+    Fixed,
+}
+
+struct Field {
+    /// The field name, or `None` for tuple structs
+    name: Option<String>,
+    encoding: Encoding,
+}
+
+enum Fields {
+    Named(Vec<Field>),
+    Unnamed(Vec<Field>),
+    Unit,
+}
+
+/// Derive `vartyint::VarIntCodec` for a struct, writing each field in order.
+///
+/// Fields can have `#[varint(delta)]` or `#[varint(fixed)]` to change how they are written.
+///
+/// For a C-like enum (no fields), the discriminant is written as a varint of the `#[repr(...)]`
+/// type (or `isize` if there is none). Reading an unknown discriminant returns
+/// `VartyIntError::UnknownDiscriminant`.
+#[proc_macro_derive(VarIntCodec, attributes(varint))]
+pub fn derive_varint_codec(input: TokenStream) -> TokenStream {
+    match derive(input) {
+        Ok(output) => output.parse().unwrap(),
+        Err(msg) => format!("compile_error!({:?});", msg).parse().unwrap(),
+    }
+}
+
+fn derive(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter();
+
+    // Skip outer attributes (noting any `#[repr(...)]`) & visibility, up to `struct`/`enum`
+    let mut repr = None;
+    let kind = loop {
+        match tokens.next() {
+            None => return Err("Expected a struct or enum".to_string()),
+            Some(TokenTree::Ident(ident))
+                if ident.to_string() == "struct" || ident.to_string() == "enum" =>
+            {
+                break ident.to_string()
+            }
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                if let Some(r) = parse_repr(g.stream()) {
+                    repr = Some(r);
+                }
+            }
+            Some(_) => {}
+        }
+    };
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err(format!("Expected a name after `{}`", kind)),
+    };
+
+    let body = match tokens.next() {
+        Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+            return Err(format!(
+                "VarIntCodec cannot be derived for generic {}s",
+                kind
+            ))
+        }
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "where" => {
+            return Err(format!(
+                "VarIntCodec cannot be derived for generic {}s",
+                kind
+            ))
+        }
+        other => other,
+    };
+
+    if kind == "enum" {
+        let variants = match body {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                parse_variants(g.stream())?
+            }
+            _ => return Err(format!("Unexpected tokens after `enum {}`", name)),
+        };
+        // Rust's default discriminant type
+        let repr = repr.unwrap_or_else(|| "isize".to_string());
+        return Ok(generate_enum(&name, &repr, &variants));
+    }
+
+    let fields = match body {
+        Some(TokenTree::Punct(p)) if p.as_char() == ';' => Fields::Unit,
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+            Fields::Named(parse_fields(g.stream(), true)?)
+        }
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            Fields::Unnamed(parse_fields(g.stream(), false)?)
+        }
+        _ => return Err(format!("Unexpected tokens after `struct {}`", name)),
+    };
+
+    Ok(generate(&name, &fields))
+}
+
+/// Parse the inside of `#[...]`, returning the integer type if it's a `repr(...)` attribute.
+fn parse_repr(stream: TokenStream) -> Option<String> {
+    let mut tokens = stream.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "repr" => {}
+        _ => return None,
+    }
+    match tokens.next() {
+        Some(TokenTree::Group(g)) => g.stream().into_iter().find_map(|t| match t {
+            TokenTree::Ident(ident) => {
+                let ident = ident.to_string();
+                let is_int = matches!(
+                    ident.as_str(),
+                    "u8" | "u16"
+                        | "u32"
+                        | "u64"
+                        | "u128"
+                        | "usize"
+                        | "i8"
+                        | "i16"
+                        | "i32"
+                        | "i64"
+                        | "i128"
+                        | "isize"
+                );
+                is_int.then_some(ident)
+            }
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Parse the variants of a C-like enum, returning their names.
+fn parse_variants(stream: TokenStream) -> Result<Vec<String>, String> {
+    let mut variants = Vec::new();
+    let mut tokens = stream.into_iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            // Attribute on a variant, `#[...]`
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                tokens.next();
+            }
+            TokenTree::Ident(ident) => {
+                variants.push(ident.to_string());
+                // Fields come straight after the name (`A(u8)` or `A { x: u8 }`). Skip an explicit
+                // discriminant (`= 4`, which can have groups, `= (1 << 3)`) up to the next comma.
+                let mut after_name = true;
+                for token in tokens.by_ref() {
+                    match token {
+                        TokenTree::Punct(p) if p.as_char() == ',' => break,
+                        TokenTree::Group(g) if after_name && g.delimiter() != Delimiter::None => {
+                            return Err(format!(
+                                "VarIntCodec can only be derived for enums without fields, but {} has fields",
+                                variants.last().unwrap()
+                            ));
+                        }
+                        _ => {}
+                    }
+                    after_name = false;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(variants)
+}
+
+/// Split a list of fields on the top level commas, and parse each one.
+fn parse_fields(stream: TokenStream, named: bool) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    let mut current: Vec<TokenTree> = Vec::new();
+    let mut angle_depth = 0;
+    for token in stream {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == '<' => angle_depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' && angle_depth > 0 => angle_depth -= 1,
+            TokenTree::Punct(p) if p.as_char() == ',' && angle_depth == 0 => {
+                fields.push(parse_field(std::mem::take(&mut current), named)?);
+                continue;
+            }
+            _ => {}
+        }
+        current.push(token);
+    }
+    if !current.is_empty() {
+        fields.push(parse_field(current, named)?);
+    }
+    Ok(fields)
+}
+
+fn parse_field(tokens: Vec<TokenTree>, named: bool) -> Result<Field, String> {
+    let mut encoding = Encoding::Plain;
+    let mut name = None;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            // An attribute, `#[...]`
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                if let Some(TokenTree::Group(g)) = tokens.next() {
+                    if let Some(enc) = parse_attribute(g.stream())? {
+                        encoding = enc;
+                    }
+                }
+            }
+            TokenTree::Ident(ident) if ident.to_string() == "pub" => {
+                // `pub(crate)` etc.
+                if let Some(TokenTree::Group(g)) = tokens.peek() {
+                    if g.delimiter() == Delimiter::Parenthesis {
+                        tokens.next();
+                    }
+                }
+            }
+            TokenTree::Ident(ident) if named => {
+                name = Some(ident.to_string());
+                break;
+            }
+            _ => break,
+        }
+    }
+    if named && name.is_none() {
+        return Err("Could not find the field name".to_string());
+    }
+    Ok(Field { name, encoding })
+}
+
+/// Parse the inside of `#[...]`, returning the encoding if it's a `varint(...)` attribute.
+fn parse_attribute(stream: TokenStream) -> Result<Option<Encoding>, String> {
+    let mut tokens = stream.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "varint" => {}
+        _ => return Ok(None),
+    }
+    let args = match tokens.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => g.stream(),
+        _ => return Err("Expected `#[varint(delta)]` or `#[varint(fixed)]`".to_string()),
+    };
+    let args = args.to_string();
+    match args.trim() {
+        "delta" => Ok(Some(Encoding::Delta)),
+        "fixed" => Ok(Some(Encoding::Fixed)),
+        other => Err(format!(
+            "Unknown varint attribute {:?}, expected `delta` or `fixed`",
+            other
+        )),
+    }
+}
+
+fn generate(name: &str, fields: &Fields) -> String {
+    let fields_list: &[Field] = match fields {
+        Fields::Named(f) | Fields::Unnamed(f) => f,
+        Fields::Unit => &[],
+    };
+
+    let mut writes = Vec::new();
+    let mut reads = String::new();
+    for (i, field) in fields_list.iter().enumerate() {
+        let access = match &field.name {
+            Some(name) => name.clone(),
+            None => i.to_string(),
+        };
+        let (write_fn, read_fn) = match field.encoding {
+            Encoding::Plain => (
+                "::vartyint::VarIntCodec::write_codec",
+                "::vartyint::VarIntCodec::read_codec",
+            ),
+            Encoding::Delta => (
+                "::vartyint::__derive::write_delta",
+                "::vartyint::__derive::read_delta",
+            ),
+            Encoding::Fixed => (
+                "::vartyint::__derive::write_fixed",
+                "::vartyint::__derive::read_fixed",
+            ),
+        };
+        writes.push(format!("{}(&self.{}, buf)", write_fn, access));
+        // The data can't end after the first field
+        let map_err = if i == 0 {
+            ""
+        } else {
+            ".map_err(::vartyint::__derive::not_first)"
+        };
+        reads.push_str(&format!(
+            "let (field{}, buf) = {}(buf){}?;\n",
+            i, read_fn, map_err
+        ));
+    }
+
+    let construct = match fields {
+        Fields::Named(f) => format!(
+            "{} {{ {} }}",
+            name,
+            f.iter()
+                .enumerate()
+                .map(|(i, field)| format!("{}: field{}", field.name.as_ref().unwrap(), i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Fields::Unnamed(f) => format!(
+            "{}({})",
+            name,
+            (0..f.len())
+                .map(|i| format!("field{}", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Fields::Unit => name.to_string(),
+    };
+
+    format!(
+        "impl ::vartyint::VarIntCodec for {name} {{
+            #[allow(unused_variables)]
+            fn write_codec(&self, buf: &mut ::std::vec::Vec<u8>) -> usize {{
+                {writes}
+            }}
+            fn read_codec(buf: &[u8]) -> ::std::result::Result<(Self, &[u8]), ::vartyint::VartyIntError> {{
+                {reads}
+                ::std::result::Result::Ok(({construct}, buf))
+            }}
+        }}",
+        name = name,
+        // The total number of bytes written, adding up the fields in order
+        writes = if writes.is_empty() {
+            "0".to_string()
+        } else {
+            writes.join("\n+ ")
+        },
+        reads = reads,
+        construct = construct,
+    )
+}
+
+fn generate_enum(name: &str, repr: &str, variants: &[String]) -> String {
+    let writes = variants
+        .iter()
+        .map(|v| format!("Self::{v} => Self::{v} as {repr},", v = v, repr = repr))
+        .collect::<String>();
+    let reads = variants
+        .iter()
+        .map(|v| {
+            format!(
+                "if val == Self::{v} as {repr} {{ return ::std::result::Result::Ok((Self::{v}, buf)); }}\n",
+                v = v,
+                repr = repr
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "impl ::vartyint::VarIntCodec for {name} {{
+            fn write_codec(&self, buf: &mut ::std::vec::Vec<u8>) -> usize {{
+                let val: {repr} = match self {{ {writes} }};
+                ::vartyint::VarIntCodec::write_codec(&val, buf)
+            }}
+            fn read_codec(buf: &[u8]) -> ::std::result::Result<(Self, &[u8]), ::vartyint::VartyIntError> {{
+                let (val, buf): ({repr}, &[u8]) = ::vartyint::VarIntCodec::read_codec(buf)?;
+                {reads}
+                ::std::result::Result::Err(::vartyint::VartyIntError::UnknownDiscriminant)
+            }}
+        }}",
+        name = name,
+        repr = repr,
+        writes = writes,
+        reads = reads,
+    )
+}
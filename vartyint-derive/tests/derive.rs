@@ -0,0 +1,209 @@
+use vartyint::{Raw, VarIntCodec, VartyIntError, Zigzag};
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct Simple {
+    a: u32,
+    pub b: i64,
+    pub(crate) c: u8,
+}
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct WithAttrs {
+    #[varint(fixed)]
+    magic: u32,
+    /// Sorted ids
+    #[varint(delta)]
+    ids: Vec<i64>,
+    tags: Vec<u16>,
+}
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct Tuple(u64, #[varint(fixed)] i16);
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct Unit;
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct Nested {
+    inner: Simple,
+    list: Vec<Tuple>,
+}
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct Wrapped {
+    raw: Raw<i32>,
+    zigzag: Zigzag<i32>,
+}
+
+fn roundtrip<T: VarIntCodec + std::fmt::Debug + PartialEq>(val: T, expected: &[u8]) {
+    let mut buf = Vec::new();
+    assert_eq!(val.write_codec(&mut buf), expected.len());
+    assert_eq!(buf, expected);
+    assert_eq!(T::read_codec(&buf), Ok((val, &[] as &[u8])));
+}
+
+#[test]
+fn simple() {
+    roundtrip(
+        Simple {
+            a: 300,
+            b: -1,
+            c: 2,
+        },
+        &[0xAC, 0x02, 0x01, 0x02],
+    );
+}
+
+#[test]
+fn attrs() {
+    roundtrip(
+        WithAttrs {
+            magic: 0x0102_0304,
+            ids: vec![10, 11, 13],
+            tags: vec![1, 2],
+        },
+        &[0x04, 0x03, 0x02, 0x01, 3, 20, 2, 4, 2, 1, 2],
+    );
+}
+
+#[test]
+fn tuple_and_unit() {
+    roundtrip(Tuple(1, -2), &[1, 0xFE, 0xFF]);
+    roundtrip(Unit, &[]);
+}
+
+#[test]
+fn wrappers() {
+    roundtrip(
+        Wrapped {
+            raw: Raw(-1),
+            zigzag: Zigzag(-1),
+        },
+        &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x01],
+    );
+}
+
+#[test]
+fn nested() {
+    roundtrip(
+        Nested {
+            inner: Simple { a: 1, b: 1, c: 1 },
+            list: vec![Tuple(5, 0)],
+        },
+        &[1, 2, 1, 1, 5, 0, 0],
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(Simple::read_codec(&[]), Err(VartyIntError::EmptyBuffer));
+    assert!(matches!(
+        Tuple::read_codec(&[1, 0]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        WithAttrs::read_codec(&[0, 0, 0, 0, 2, 1]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    // The data ends between fields
+    assert!(matches!(
+        Simple::read_codec(&[1]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        Simple::read_codec(&[1, 2]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        Tuple::read_codec(&[1]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        WithAttrs::read_codec(&[0, 0, 0, 0]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        Nested::read_codec(&[1, 2, 1]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    // The data ends part way through a `Vec`
+    assert!(matches!(
+        Vec::<u32>::read_codec(&[3, 1]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+    assert!(matches!(
+        Nested::read_codec(&[1, 2, 1, 2, 5, 0]),
+        Err(VartyIntError::NotEnoughBytes { .. })
+    ));
+}
+
+#[derive(VarIntCodec, Debug, PartialEq, Clone, Copy)]
+#[repr(u16)]
+enum Status {
+    Ok = 200,
+    NotFound = 404,
+    /// Teapot
+    Teapot = 418,
+}
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+#[repr(i8)]
+enum Direction {
+    Back = -1,
+    Stay,
+    Forward,
+}
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+enum NoRepr {
+    A,
+    B,
+}
+
+const FLAG: u8 = 20;
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+#[repr(u8)]
+enum Expressions {
+    Shifted = (1 << 3),
+    Block = { FLAG },
+    Next,
+}
+
+#[derive(VarIntCodec, Debug, PartialEq)]
+struct Response {
+    status: Status,
+    dirs: Vec<Direction>,
+}
+
+#[test]
+fn enums() {
+    roundtrip(Status::Ok, &[0xC8, 0x01]);
+    roundtrip(Status::Teapot, &[0xA2, 0x03]);
+    roundtrip(Direction::Back, &[0x01]);
+    roundtrip(Direction::Stay, &[0x00]);
+    roundtrip(Direction::Forward, &[0x02]);
+    roundtrip(NoRepr::B, &[0x02]);
+    roundtrip(Expressions::Shifted, &[8]);
+    roundtrip(Expressions::Block, &[20]);
+    roundtrip(Expressions::Next, &[21]);
+    roundtrip(
+        Response {
+            status: Status::NotFound,
+            dirs: vec![Direction::Forward, Direction::Back],
+        },
+        &[0x94, 0x03, 2, 2, 1],
+    );
+}
+
+#[test]
+fn unknown_discriminant() {
+    assert_eq!(
+        Status::read_codec(&[0x01]),
+        Err(VartyIntError::UnknownDiscriminant)
+    );
+    assert_eq!(
+        Direction::read_codec(&[0x04]),
+        Err(VartyIntError::UnknownDiscriminant)
+    );
+}